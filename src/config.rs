@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 
 use stq_http;
@@ -16,6 +17,67 @@ pub const FIAT_SELLER_CURRENCY: Currency = Currency::EUR;
 #[derive(Debug, Deserialize, Clone)]
 pub struct Microservice {
     pub url: String,
+    /// Overrides `gateway.circuit_breaker` for this microservice
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Overrides `gateway.http_timeout_ms`
+    pub timeout_ms: Option<u64>,
+    /// Overrides `gateway.http_client_retries`, only `GET` requests are retried
+    pub retries: Option<usize>,
+    /// Overrides `gateway.http_retry_backoff_ms`
+    pub retry_backoff_ms: Option<u64>,
+    /// Overrides `gateway.http_client_buffer_size`
+    pub pool_size: Option<usize>,
+    /// Instances of microservice which requests to `url` are balanced between
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// If set, host of `url` is resolved with this interval and requests are balanced between its addresses
+    pub dns_refresh_ms: Option<u64>,
+    #[serde(default)]
+    pub balancing: Balancing,
+    /// Overrides `gateway.ejection` for this microservice
+    pub ejection: Option<EjectionConfig>,
+    /// Opt-in hedging policies of endpoint classes
+    #[serde(default)]
+    pub hedging: Vec<HedgingConfig>,
+}
+
+/// Second request is sent if there is no response after `percentile` of observed latency
+#[derive(Debug, Deserialize, Clone)]
+pub struct HedgingConfig {
+    /// Path relative to microservice url, `{..}` matches any segment and trailing `*` matches the rest
+    pub path: String,
+    /// Allows hedging `POST` requests of read endpoints taking query in body, like search
+    #[serde(default)]
+    pub read_only_post: bool,
+    pub percentile: f64,
+    /// Delay used until enough latencies are observed, also the lower bound of delay
+    pub min_delay_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Balancing {
+    RoundRobin,
+    LeastInFlight,
+}
+
+impl Default for Balancing {
+    fn default() -> Self {
+        Balancing::RoundRobin
+    }
+}
+
+/// Endpoint is excluded from balancing for `duration_ms` after `consecutive_failures`
+#[derive(Debug, Deserialize, Clone)]
+pub struct EjectionConfig {
+    pub consecutive_failures: u32,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: u32,
+    pub open_duration_ms: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -26,6 +88,17 @@ pub struct Gateway {
     pub http_client_retries: usize,
     pub records_limit: usize,
     pub http_timeout_ms: u64,
+    /// Deadline of the whole GraphQL operation, clients may request a shorter one
+    pub operation_timeout_ms: u64,
+    /// Delay before retry, multiplied by attempt number
+    pub http_retry_backoff_ms: u64,
+    /// Proxies allowed to set `X-Forwarded-For` header
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// Default circuit breaker settings for every microservice
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Default ejection settings for every microservice with several endpoints
+    pub ejection: Option<EjectionConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,6 +114,21 @@ pub struct Config {
     pub delivery_microservice: Microservice,
     pub jwt: JWT,
     pub cors: CORS,
+    pub jwt_cookie: Option<JWTCookie>,
+    pub anonymous_session: Option<AnonymousSession>,
+    pub rate_limit: Option<RateLimit>,
+    pub resilience: Option<Resilience>,
+    #[serde(default)]
+    pub schema_explorer: SchemaExplorer,
+    #[serde(default)]
+    pub metrics: Metrics,
+    /// Offline mode, upstream calls are answered from fixtures
+    pub mock: Option<Mock>,
+    /// Record or replay of upstream traffic
+    pub recorder: Option<Recorder>,
+    /// Alternate urls of microservices keyed by microservice name, e.g. `orders`
+    #[serde(default)]
+    pub canary: HashMap<String, CanaryRoute>,
     pub graylog: Option<GrayLogConfig>,
     pub sentry: Option<SentryConfig>,
 }
@@ -49,6 +137,136 @@ pub struct Config {
 pub struct JWT {
     pub public_key_path: String,
     pub leeway: i64,
+    /// Seconds a JWT session is trusted as not revoked without asking users microservice, 0 disables caching
+    #[serde(default)]
+    pub session_check_cache_sec: u64,
+}
+
+/// Opt-in mode in which JWT is also kept in `HttpOnly` cookie
+#[derive(Debug, Deserialize, Clone)]
+pub struct JWTCookie {
+    pub name: String,
+    pub csrf_cookie_name: String,
+    pub csrf_header_name: String,
+    pub domain: Option<String>,
+    pub same_site: String,
+    pub max_age: u32,
+}
+
+/// Session ids for anonymous users signed by gateway
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnonymousSession {
+    pub secret: String,
+    pub idle_timeout_sec: u32,
+    pub cookie_name: Option<String>,
+    pub cookie_domain: Option<String>,
+}
+
+/// Token bucket limits keyed by top level GraphQL field name
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimit {
+    pub operations: HashMap<String, OperationLimit>,
+}
+
+/// Bucket of `capacity` requests refilled evenly during `period_sec`
+#[derive(Debug, Deserialize, Clone)]
+pub struct OperationLimit {
+    pub capacity: u32,
+    pub period_sec: u32,
+}
+
+/// Fields named as `Type.field`, e.g. `CartStore.deliveryCost`, which serve
+/// last known good value for `stale_ttl_sec` when microservice fails
+#[derive(Debug, Deserialize, Clone)]
+pub struct Resilience {
+    #[serde(default)]
+    pub stale_fields: Vec<String>,
+    pub stale_ttl_sec: u64,
+}
+
+/// Exposure of the schema to clients
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SchemaExplorer {
+    /// In-browser IDE served on `GET /`
+    #[serde(default)]
+    pub ide: Ide,
+    #[serde(default)]
+    pub introspection: IntrospectionAccess,
+    /// Client ips allowed to run introspection queries when `introspection` is restricted
+    #[serde(default)]
+    pub introspection_allowed_ips: Vec<String>,
+}
+
+/// Access to `GET /metrics`, which is not served to anybody else
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Metrics {
+    /// Client ips allowed to scrape metrics
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Ide {
+    Disabled,
+    Graphiql,
+    Playground,
+}
+
+impl Default for Ide {
+    fn default() -> Self {
+        Ide::Graphiql
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntrospectionAccess {
+    Everyone,
+    /// Superusers and `introspection_allowed_ips`
+    Superusers,
+    /// Only `introspection_allowed_ips`
+    AllowedIps,
+}
+
+impl Default for IntrospectionAccess {
+    fn default() -> Self {
+        IntrospectionAccess::Everyone
+    }
+}
+
+/// Mock microservices server listening on `url`, microservice urls are expected to point to it
+#[derive(Debug, Deserialize, Clone)]
+pub struct Mock {
+    pub url: String,
+    pub fixtures_path: String,
+}
+
+/// Proxy listening on `url` which records upstream traffic to `path` or replays it from there
+#[derive(Debug, Deserialize, Clone)]
+pub struct Recorder {
+    pub mode: RecorderMode,
+    pub url: String,
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecorderMode {
+    Record,
+    Replay,
+}
+
+/// Request is routed to `url` if `X-Canary` header contains microservice name
+/// or one of `header_values`, if user is in `user_ids`, or for `percentage` of clients
+#[derive(Debug, Deserialize, Clone)]
+pub struct CanaryRoute {
+    pub url: String,
+    #[serde(default)]
+    pub header_values: Vec<String>,
+    #[serde(default)]
+    pub user_ids: Vec<i32>,
+    pub percentage: Option<u8>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -78,11 +296,48 @@ impl Config {
     pub fn to_http_config(&self) -> stq_http::client::Config {
         stq_http::client::Config {
             http_client_buffer_size: self.gateway.http_client_buffer_size,
-            http_client_retries: self.gateway.http_client_retries,
+            // retries are made by gateway, so that mutations are never retried
+            http_client_retries: 0,
             timeout_duration_ms: self.gateway.http_timeout_ms,
         }
     }
 
+    pub fn to_http_config_for(&self, microservice: &Microservice) -> stq_http::client::Config {
+        stq_http::client::Config {
+            http_client_buffer_size: microservice.pool_size.unwrap_or(self.gateway.http_client_buffer_size),
+            http_client_retries: 0,
+            timeout_duration_ms: microservice.timeout_ms.unwrap_or(self.gateway.http_timeout_ms),
+        }
+    }
+
+    /// All upstream microservices by name
+    pub fn upstreams(&self) -> Vec<(&'static str, &Microservice)> {
+        vec![
+            ("users", &self.users_microservice),
+            ("stores", &self.stores_microservice),
+            ("saga", &self.saga_microservice),
+            ("orders", &self.orders_microservice),
+            ("billing", &self.billing_microservice),
+            ("warehouses", &self.warehouses_microservice),
+            ("notifications", &self.notifications_microservice),
+            ("delivery", &self.delivery_microservice),
+        ]
+    }
+
+    pub fn upstream_mut(&mut self, name: &str) -> &mut Microservice {
+        match name {
+            "users" => &mut self.users_microservice,
+            "stores" => &mut self.stores_microservice,
+            "saga" => &mut self.saga_microservice,
+            "orders" => &mut self.orders_microservice,
+            "billing" => &mut self.billing_microservice,
+            "warehouses" => &mut self.warehouses_microservice,
+            "notifications" => &mut self.notifications_microservice,
+            "delivery" => &mut self.delivery_microservice,
+            _ => panic!("Unknown microservice {}", name),
+        }
+    }
+
     pub fn service_url(&self, service: StqService) -> String {
         match service {
             StqService::Users => self.users_microservice.url.clone(),
@@ -0,0 +1,104 @@
+//! Signed session ids for anonymous users, issued by gateway
+
+use std::time::SystemTime;
+
+use hyper::header::{Cookie, Headers};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, encode, Algorithm, Header, Validation};
+use juniper::FieldError;
+use uuid::Uuid;
+
+use stq_http::request_util::SessionId as SessionIdHeader;
+use stq_types::SessionId;
+
+use super::cookies::{build_cookie, ResponseCookies};
+use config::AnonymousSession;
+
+const COOKIE_SAME_SITE: &str = "Lax";
+/// Response header with prolonged session token, named as the request one
+pub const SESSION_ID_HEADER: &str = "SessionId";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SessionClaims {
+    session_id: String,
+    exp: i64,
+}
+
+/// Signed session token along with the id it carries
+#[derive(Clone, Debug)]
+pub struct SessionToken {
+    pub session_id: SessionId,
+    pub token: String,
+    pub exp: i64,
+}
+
+/// Issues token for a new session or prolongs existing one
+pub fn issue(config: &AnonymousSession, session_id: Option<SessionId>) -> Result<SessionToken, FieldError> {
+    let session_id = match session_id {
+        Some(session_id) => session_id,
+        None => Uuid::new_v4().hyphenated().to_string().parse::<SessionId>()?,
+    };
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|n| n.as_secs() as i64)
+        .unwrap_or_default();
+    let claims = SessionClaims {
+        session_id: session_id.to_string(),
+        exp: now + i64::from(config.idle_timeout_sec),
+    };
+    let token = encode(&Header::new(Algorithm::HS256), &claims, config.secret.as_bytes())?;
+
+    Ok(SessionToken {
+        session_id,
+        token,
+        exp: claims.exp,
+    })
+}
+
+/// Reads session token from `SessionId` header or session cookie.
+/// Expired token means that session is gone, while unsigned or forged token is rejected.
+pub fn session_id_from_request(headers: &Headers, config: &AnonymousSession) -> Result<Option<SessionId>, FieldError> {
+    let header_token = headers.get::<SessionIdHeader>().map(|sid| sid.to_string());
+    let cookie_token = config
+        .cookie_name
+        .as_ref()
+        .and_then(|name| headers.get::<Cookie>().and_then(|cookie| cookie.get(name)).map(|token| token.to_string()));
+
+    let token = match header_token.or(cookie_token) {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+
+    let validation = Validation::new(Algorithm::HS256);
+    match decode::<SessionClaims>(&token, config.secret.as_bytes(), &validation) {
+        Ok(data) => data.claims.session_id.parse::<SessionId>().map(Some).map_err(|_| invalid_session_error()),
+        Err(e) => match *e.kind() {
+            ErrorKind::ExpiredSignature => Ok(None),
+            _ => Err(invalid_session_error()),
+        },
+    }
+}
+
+/// Returns prolonged token in `SessionId` response header, and in session cookie if it is enabled
+pub fn set_session_token(cookies: &ResponseCookies, config: &AnonymousSession, session_token: &SessionToken) {
+    cookies.set_session_token(session_token.token.clone());
+    if let Some(ref name) = config.cookie_name {
+        let domain = config.cookie_domain.as_ref().map(|domain| domain.as_str());
+        cookies.push(build_cookie(
+            name,
+            &session_token.token,
+            domain,
+            COOKIE_SAME_SITE,
+            true,
+            config.idle_timeout_sec,
+        ));
+    }
+}
+
+fn invalid_session_error() -> FieldError {
+    FieldError::new(
+        "Invalid session id.",
+        graphql_value!({ "code": 115, "details": { "Session id must be issued by startSession mutation." }}),
+    )
+}
@@ -0,0 +1,66 @@
+//! Info about client device making the request
+
+use hyper::header::UserAgent;
+use hyper::server::Request;
+
+/// Header with the name of client application, e.g. `web` or `ios`
+pub const CLIENT_NAME_HEADER: &str = "X-Client-Name";
+/// Header with the release of client application
+pub const CLIENT_VERSION_HEADER: &str = "X-Client-Version";
+
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Client application, from `X-Client-Name` header
+    pub name: Option<String>,
+    /// Client application release, from `X-Client-Version` header
+    pub version: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &Request, trusted_proxies: &[String]) -> Self {
+        let remote_ip = req.remote_addr().map(|addr| addr.ip().to_string());
+        let ip = match remote_ip {
+            Some(ref remote_ip) if trusted_proxies.contains(remote_ip) => {
+                forwarded_for(req, trusted_proxies).or_else(|| Some(remote_ip.clone()))
+            }
+            _ => remote_ip,
+        };
+
+        Self {
+            ip,
+            user_agent: req.headers().get::<UserAgent>().map(|user_agent| user_agent.to_string()),
+            name: header_value(req, CLIENT_NAME_HEADER),
+            version: header_value(req, CLIENT_VERSION_HEADER),
+        }
+    }
+}
+
+fn header_value(req: &Request, name: &str) -> Option<String> {
+    req.headers()
+        .get_raw(name)
+        .and_then(|raw| raw.one())
+        .and_then(|value| ::std::str::from_utf8(value).ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Finds the rightmost address in `X-Forwarded-For` which was not added by trusted proxy
+fn forwarded_for(req: &Request, trusted_proxies: &[String]) -> Option<String> {
+    let raw = req.headers().get_raw("X-Forwarded-For")?;
+    let addresses = raw
+        .iter()
+        .filter_map(|line| ::std::str::from_utf8(line).ok())
+        .flat_map(|line| line.split(','))
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty())
+        .collect::<Vec<_>>();
+
+    addresses
+        .iter()
+        .rev()
+        .find(|addr| !trusted_proxies.contains(addr))
+        .or_else(|| addresses.first())
+        .cloned()
+}
@@ -0,0 +1,101 @@
+//! Cookie based JWT session mode with double-submit CSRF protection
+
+use std::sync::{Arc, Mutex};
+
+use hyper::header::{Cookie, Headers};
+use juniper::FieldError;
+use uuid::Uuid;
+
+use config::JWTCookie;
+
+/// Cookies to be set on the response of one request, along with anonymous session token
+/// for clients sending it in header. Created for every request and shared between its controller
+/// and response middleware only.
+#[derive(Clone, Default)]
+pub struct ResponseCookies(Arc<Mutex<PendingCookies>>);
+
+#[derive(Default)]
+struct PendingCookies {
+    cookies: Vec<String>,
+    session_token: Option<String>,
+}
+
+impl ResponseCookies {
+    pub fn push(&self, cookie: String) {
+        if let Ok(mut pending) = self.0.lock() {
+            pending.cookies.push(cookie);
+        }
+    }
+
+    pub fn take(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .map(|mut pending| pending.cookies.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn set_session_token(&self, token: String) {
+        if let Ok(mut pending) = self.0.lock() {
+            pending.session_token = Some(token);
+        }
+    }
+
+    pub fn take_session_token(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|mut pending| pending.session_token.take())
+    }
+}
+
+/// Reads JWT from cookie. Token is accepted only if csrf header
+/// is equal to csrf cookie value, requests without JWT cookie are not checked.
+pub fn jwt_from_cookies(headers: &Headers, config: &JWTCookie) -> Result<Option<String>, FieldError> {
+    let cookie = match headers.get::<Cookie>() {
+        Some(cookie) => cookie,
+        None => return Ok(None),
+    };
+
+    // cleared cookie may still be sent with empty value
+    let token = match cookie.get(&config.name).filter(|token| !token.is_empty()) {
+        Some(token) => token.to_string(),
+        None => return Ok(None),
+    };
+
+    let csrf_cookie = cookie.get(&config.csrf_cookie_name);
+    let csrf_header = headers
+        .get_raw(&config.csrf_header_name)
+        .and_then(|raw| raw.one())
+        .and_then(|value| ::std::str::from_utf8(value).ok());
+
+    match (csrf_cookie, csrf_header) {
+        (Some(csrf_cookie), Some(csrf_header)) if !csrf_cookie.is_empty() && csrf_cookie == csrf_header => Ok(Some(token)),
+        _ => Err(FieldError::new(
+            "CSRF token mismatch.",
+            graphql_value!({ "code": 113, "details": { "CSRF header must be equal to CSRF cookie when JWT is sent in cookie." }}),
+        )),
+    }
+}
+
+/// Sets JWT cookie along with a new csrf cookie
+pub fn set_jwt_cookies(cookies: &ResponseCookies, config: &JWTCookie, token: &str) {
+    let csrf_token = Uuid::new_v4().simple().to_string();
+    let domain = config.domain.as_ref().map(|domain| domain.as_str());
+    cookies.push(build_cookie(&config.name, token, domain, &config.same_site, true, config.max_age));
+    cookies.push(build_cookie(&config.csrf_cookie_name, &csrf_token, domain, &config.same_site, false, config.max_age));
+}
+
+/// Expires JWT and csrf cookies
+pub fn clear_jwt_cookies(cookies: &ResponseCookies, config: &JWTCookie) {
+    let domain = config.domain.as_ref().map(|domain| domain.as_str());
+    cookies.push(build_cookie(&config.name, "", domain, &config.same_site, true, 0));
+    cookies.push(build_cookie(&config.csrf_cookie_name, "", domain, &config.same_site, false, 0));
+}
+
+pub fn build_cookie(name: &str, value: &str, domain: Option<&str>, same_site: &str, http_only: bool, max_age: u32) -> String {
+    let mut cookie = format!("{}={}; Path=/; Max-Age={}; Secure; SameSite={}", name, value, max_age, same_site);
+    if let Some(domain) = domain {
+        cookie.push_str(&format!("; Domain={}", domain));
+    }
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    cookie
+}
@@ -1,6 +1,14 @@
-//! Utility module to generate a GraphiQL interface
+//! Utility module to generate in-browser GraphQL IDEs
 
-/// Generate the HTML source to show a GraphiQL interface
+/// Headers gateway reads, prefilled in header editors
+const DEFAULT_HEADERS: &str = r#"{
+  "Authorization": "Bearer <JWT>",
+  "Currency": "STQ",
+  "FiatCurrency": "EUR",
+  "SessionId": ""
+}"#;
+
+/// Generate the HTML source to show a GraphiQL interface with header editor
 pub fn source(graphql_endpoint_url: &str) -> String {
     let stylesheet_source = r#"
     <style>
@@ -14,13 +22,15 @@ pub fn source(graphql_endpoint_url: &str) -> String {
     "#;
     let fetcher_source = r#"
     <script>
-        function graphQLFetcher(params) {
+        function graphQLFetcher(params, options) {
+            var headers = Object.assign({
+                'Accept': 'application/json',
+                'Content-Type': 'application/json',
+            }, options && options.headers);
             return fetch(GRAPHQL_URL, {
                 method: 'post',
-                headers: {
-                    'Accept': 'application/json',
-                    'Content-Type': 'application/json',
-                },
+                headers: headers,
+                credentials: 'include',
                 body: JSON.stringify(params)
             }).then(function (response) {
                 return response.text();
@@ -35,6 +45,9 @@ pub fn source(graphql_endpoint_url: &str) -> String {
         ReactDOM.render(
             React.createElement(GraphiQL, {
                 fetcher: graphQLFetcher,
+                headerEditorEnabled: true,
+                shouldPersistHeaders: true,
+                headers: DEFAULT_HEADERS,
             }),
             document.querySelector('#app'));
     </script>
@@ -47,21 +60,59 @@ pub fn source(graphql_endpoint_url: &str) -> String {
 <head>
     <title>GraphQL</title>
     {stylesheet_source}
-    <link rel="stylesheet" type="text/css" href="//cdnjs.cloudflare.com/ajax/libs/graphiql/0.10.2/graphiql.css">
+    <link rel="stylesheet" type="text/css" href="//unpkg.com/graphiql@1.4.7/graphiql.min.css">
 </head>
 <body>
     <div id="app"></div>
-    <script src="//cdnjs.cloudflare.com/ajax/libs/fetch/2.0.3/fetch.js"></script>
-    <script src="//cdnjs.cloudflare.com/ajax/libs/react/15.6.0/react.js"></script>
-    <script src="//cdnjs.cloudflare.com/ajax/libs/react/15.6.0/react-dom.js"></script>
-    <script src="//cdnjs.cloudflare.com/ajax/libs/graphiql/0.10.2/graphiql.js"></script>
+    <script src="//unpkg.com/react@16.14.0/umd/react.production.min.js"></script>
+    <script src="//unpkg.com/react-dom@16.14.0/umd/react-dom.production.min.js"></script>
+    <script src="//unpkg.com/graphiql@1.4.7/graphiql.min.js"></script>
     <script>var GRAPHQL_URL = '{graphql_url}';</script>
+    <script>var DEFAULT_HEADERS = {default_headers};</script>
     {fetcher_source}
 </body>
 </html>
 "#,
         graphql_url = graphql_endpoint_url,
+        default_headers = js_string(DEFAULT_HEADERS),
         stylesheet_source = stylesheet_source,
         fetcher_source = fetcher_source
     )
 }
+
+/// Generate the HTML source to show a GraphQL Playground interface, headers are edited in its "HTTP headers" tab
+pub fn playground_source(graphql_endpoint_url: &str) -> String {
+    format!(
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="user-scalable=no, initial-scale=1.0, minimum-scale=1.0, maximum-scale=1.0, minimal-ui">
+    <title>GraphQL Playground</title>
+    <link rel="stylesheet" href="//cdn.jsdelivr.net/npm/graphql-playground-react@1.7.26/build/static/css/index.css" />
+    <script src="//cdn.jsdelivr.net/npm/graphql-playground-react@1.7.26/build/static/js/middleware.js"></script>
+</head>
+<body>
+    <div id="root"></div>
+    <script>
+        window.addEventListener('load', function () {{
+            GraphQLPlayground.init(document.getElementById('root'), {{
+                endpoint: '{graphql_url}',
+                settings: {{ 'request.credentials': 'include' }},
+                tabs: [{{ endpoint: '{graphql_url}', query: '', headers: {default_headers} }}],
+            }});
+        }});
+    </script>
+</body>
+</html>
+"#,
+        graphql_url = graphql_endpoint_url,
+        default_headers = DEFAULT_HEADERS,
+    )
+}
+
+/// GraphiQL takes headers as text of the editor
+fn js_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'").replace('\n', "\\n"))
+}
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 
 use chrono::prelude::*;
 use failure::Error as FailureError;
@@ -12,9 +13,9 @@ use hyper::server::Request;
 use hyper::Method::{Get, Post};
 use jsonwebtoken::{decode, Algorithm, Validation};
 use juniper::http::GraphQLRequest;
+use juniper::{FieldError, FieldResult};
 use serde_json;
 
-use stq_http::client::{ClientHandle, HttpClient, TimeLimitedHttpClient};
 use stq_http::controller::Controller;
 use stq_http::controller::ControllerFuture;
 use stq_http::errors::ErrorMessageWrapper;
@@ -28,47 +29,90 @@ use stq_routes::service::Service;
 use stq_static_resources::Currency;
 use stq_types::SessionId;
 
+use self::client_info::ClientInfo;
+use self::cookies::ResponseCookies;
+use self::rate_limit::RateLimiter;
 use self::routes::Route;
-use config::Config;
+use config::{Config, Ide, IntrospectionAccess};
 use errors::Error;
+use graphql::analytics::{OperationAnalytics, OperationKey};
 use graphql::context::{check_jwt_not_revoked, Context, GraphQLResponse};
+use graphql::deprecation::DeprecationTracker;
+use graphql::document::{Document, Operation, RawGraphQLRequest};
+use graphql::document::is_introspection;
 use graphql::models::jwt::JWTPayload;
+use graphql::resilience::LastKnownGood;
 use graphql::schema::Schema;
+use graphql::session_cache::ActiveSessions;
 use sentry_integration::log_and_capture_error;
+use upstream::{CanaryRouting, CircuitBreakers, Deadline, HttpClients};
 
+pub mod anonymous_session;
+pub mod client_info;
+pub mod cookies;
 pub mod graphiql;
+pub mod rate_limit;
 pub mod routes;
 
+#[derive(Clone)]
 pub struct ControllerImpl {
     jwt_public_key: Vec<u8>,
     route_parser: Arc<RouteParser<Route>>,
     cpu_pool: CpuPool,
-    http_client: ClientHandle,
+    http_clients: HttpClients,
     jwt_leeway: i64,
     config: Config,
     schema: Arc<Schema>,
+    response_cookies: ResponseCookies,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    circuit_breakers: Arc<CircuitBreakers>,
+    last_known_good: Arc<LastKnownGood>,
+    deprecations: Arc<DeprecationTracker>,
+    analytics: Arc<OperationAnalytics>,
+    active_sessions: Arc<ActiveSessions>,
 }
 
 impl ControllerImpl {
     /// Create a new controller based on services
     pub fn new(
-        http_client: ClientHandle,
+        http_clients: HttpClients,
         jwt_public_key: Vec<u8>,
         cpu_pool: CpuPool,
         jwt_leeway: i64,
         config: Config,
         schema: Arc<Schema>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        circuit_breakers: Arc<CircuitBreakers>,
+        last_known_good: Arc<LastKnownGood>,
+        deprecations: Arc<DeprecationTracker>,
+        analytics: Arc<OperationAnalytics>,
+        active_sessions: Arc<ActiveSessions>,
     ) -> Self {
         let route_parser = Arc::new(routes::create_route_parser());
 
         Self {
             jwt_leeway,
-            http_client,
+            http_clients,
             jwt_public_key,
             route_parser,
             cpu_pool,
             config,
             schema,
+            response_cookies: ResponseCookies::default(),
+            rate_limiter,
+            circuit_breakers,
+            last_known_good,
+            deprecations,
+            analytics,
+            active_sessions,
+        }
+    }
+
+    /// Controller serving one request, cookies it sets are added to the response of this request only
+    pub fn with_response_cookies(&self, response_cookies: ResponseCookies) -> Self {
+        Self {
+            response_cookies,
+            ..self.clone()
         }
     }
 }
@@ -83,74 +127,184 @@ impl Controller for ControllerImpl {
         let jwt_public_key = self.jwt_public_key.clone();
         let cpu_pool = self.cpu_pool.clone();
         let schema = self.schema.clone();
+        let response_cookies = self.response_cookies.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let circuit_breakers = self.circuit_breakers.clone();
+        let last_known_good = self.last_known_good.clone();
+        let deprecations = self.deprecations.clone();
+        let analytics = self.analytics.clone();
+        let active_sessions = self.active_sessions.clone();
 
-        let request_timeout = Duration::from_millis(self.config.gateway.http_timeout_ms);
-        let client = TimeLimitedHttpClient::new(self.http_client.clone(), request_timeout);
+        let http_clients = self.http_clients.clone();
         let saga_addr = self.config.saga_microservice.url.clone();
 
         Box::new(
             match (&req.method().clone(), self.route_parser.test(req.path())) {
-                (&Get, Some(Route::Root)) => Box::new(future::ok(graphiql::source("/graphql"))),
+                (&Get, Some(Route::Root)) => Box::new(future::result(match config.schema_explorer.ide {
+                    Ide::Graphiql => Ok(graphiql::source("/graphql")),
+                    Ide::Playground => Ok(graphiql::playground_source("/graphql")),
+                    Ide::Disabled => Err(format_err!("GraphQL IDE is disabled").context(Error::NotFound).into()),
+                })),
 
                 (&Post, Some(Route::Graphql)) => {
                     let headers = req.headers().clone();
-                    let auth_header = headers.get::<Authorization<Bearer>>();
+                    let client_info = ClientInfo::from_request(&req, &config.gateway.trusted_proxies);
+                    let deadline = Deadline::from_headers(&headers, &config.gateway);
+                    let token = match (headers.get::<Authorization<Bearer>>(), config.jwt_cookie.as_ref()) {
+                        (Some(auth), _) => Ok(Some(auth.0.token.clone())),
+                        (None, Some(cookie_config)) => cookies::jwt_from_cookies(&headers, cookie_config),
+                        (None, None) => Ok(None),
+                    };
 
                     let mut validation = Validation {
                         leeway,
                         ..Validation::new(Algorithm::RS256)
                     };
-                    let token_payload = auth_header.and_then(move |auth| {
-                        let token = auth.0.token.as_ref();
+                    let token_payload = token.as_ref().ok().and_then(|token| token.as_ref()).and_then(|token| {
                         decode::<JWTPayload>(token, jwt_public_key.as_ref(), &validation)
                             .ok()
                             .map(|t| t.claims)
                     });
 
-                    let session_id_header = headers.get::<SessionIdHeader>().and_then(|sid| sid.parse::<SessionId>().ok());
+                    let session_id = match config.anonymous_session.as_ref() {
+                        Some(session_config) => anonymous_session::session_id_from_request(&headers, session_config),
+                        None => Ok(headers.get::<SessionIdHeader>().and_then(|sid| sid.parse::<SessionId>().ok())),
+                    };
                     let currency_header = headers.get::<CurrencyHeader>().and_then(|sid| sid.parse::<Currency>().ok());
                     let fiat_currency_header = headers.get::<FiatCurrencyHeader>().and_then(|sid| sid.parse::<Currency>().ok());
                     let correlation_token = headers.get::<CorrelationToken>().map(|token| token.clone());
 
                     serialize_future::<_, FailureError, _>(
-                        parse_body::<GraphQLRequest>(req.body())
-                            .map_err(|e| {
+                        parse_body::<serde_json::Value>(req.body())
+                            .and_then(|body| {
+                                let graphql_req = serde_json::from_value::<GraphQLRequest>(body.clone())?;
+                                let raw_req = serde_json::from_value::<RawGraphQLRequest>(body)?;
+                                Ok((graphql_req, raw_req))
+                            })
+                            .map_err(|e: FailureError| {
                                 e.context("Parsing body // POST /graphql in GraphQLRequest failed!")
                                     .context(Error::Parse)
                                     .into()
                             })
-                            .and_then(move |graphql_req| {
+                            .and_then(move |(graphql_req, raw_req)| {
                                 cpu_pool
                                     .spawn_fn(move || {
+                                        let started_at = Instant::now();
+                                        let document = raw_req.document();
+                                        let operation = document.operation(raw_req.operation_name());
+                                        let operation_key = OperationKey::new(
+                                            operation.and_then(|operation| operation.name.clone()),
+                                            client_info.name.clone(),
+                                            client_info.version.clone(),
+                                        );
+                                        if let Err(e) = token {
+                                            return rejected(&analytics, operation_key, started_at, HashMap::new(), e);
+                                        }
+                                        let session_id_header = match session_id {
+                                            Ok(session_id) => session_id,
+                                            Err(e) => return rejected(&analytics, operation_key, started_at, HashMap::new(), e),
+                                        };
+                                        if let (Some(session_id), Some(session_config)) =
+                                            (session_id_header, config.anonymous_session.as_ref())
+                                        {
+                                            if let Ok(session_token) = anonymous_session::issue(session_config, Some(session_id)) {
+                                                anonymous_session::set_session_token(&response_cookies, session_config, &session_token);
+                                            }
+                                        }
+                                        let user_id = token_payload.as_ref().map(|payload| payload.user_id);
+                                        if let Some(ref rate_limiter) = rate_limiter {
+                                            let root_fields = operation.map(|op| document.root_fields(op)).unwrap_or_default();
+                                            let session_id = session_id_header.map(|session_id| session_id.to_string());
+                                            let keys = rate_limit::client_keys(user_id, session_id, client_info.ip.clone());
+                                            if let Err(retry_after) = rate_limiter.check_keys(&root_fields, &keys) {
+                                                let e = rate_limit::rate_limited_error(retry_after);
+                                                return rejected(&analytics, operation_key, started_at, HashMap::new(), e);
+                                            }
+                                        }
+                                        let key = client_key(&token_payload, &session_id_header, &client_info);
+                                        let canary = CanaryRouting::select(&config, &headers, user_id, &key);
+                                        if !canary.is_empty() {
+                                            info!(
+                                                "Canary routes: {:?}, correlation token: {:?}",
+                                                canary.services(),
+                                                correlation_token.as_ref().map(|token| token.0.as_str())
+                                            );
+                                        }
                                         if let Some(ref payload) = token_payload {
-                                            let res = check_jwt_not_revoked(&client, payload, config.service_url(Service::Users));
+                                            let users_url = config.service_url(Service::Users);
+                                            let correlation_token = correlation_token.clone();
+                                            let res = check_jwt_not_revoked(
+                                                &http_clients,
+                                                deadline,
+                                                correlation_token,
+                                                payload,
+                                                users_url,
+                                                &active_sessions,
+                                            );
                                             if let Err(e) = res {
-                                                return serde_json::to_value(GraphQLResponse::from_field_error(e));
+                                                return rejected(&analytics, operation_key, started_at, HashMap::new(), e);
                                             }
                                         }
+                                        let client_name = client_name(&client_info, &token_payload);
+                                        deprecations.record(&client_name, &raw_req.query, raw_req.operation_name());
                                         let graphql_context = Context::new(
-                                            client,
+                                            http_clients,
                                             token_payload,
                                             session_id_header,
                                             currency_header,
                                             fiat_currency_header,
                                             config,
                                             correlation_token,
+                                            response_cookies,
+                                            client_info,
+                                            circuit_breakers,
+                                            deadline,
+                                            canary,
+                                            last_known_good,
+                                            deprecations,
+                                            analytics.clone(),
+                                            active_sessions,
                                         );
+                                        if let Err(e) = check_introspection_access(&graphql_context, &raw_req) {
+                                            let upstream_calls = graphql_context.upstream_calls.counts();
+                                            return rejected(&analytics, operation_key, started_at, upstream_calls, e);
+                                        }
                                         let resp = graphql_req.execute(&*schema, &graphql_context);
-                                        serde_json::to_value(resp)
+                                        let resp = serde_json::to_value(resp)?;
+                                        let failed = !resp["errors"].is_null();
+                                        audit_impersonated_operation(&graphql_context, &document, operation, !failed);
+                                        let upstream_calls = graphql_context.upstream_calls.counts();
+                                        analytics.record(operation_key, started_at.elapsed(), failed, upstream_calls);
+                                        Ok(resp)
                                     })
                                     .map_err(From::from)
                             }),
                     )
                 }
 
+                (&Get, Some(Route::Metrics)) => {
+                    let client_info = ClientInfo::from_request(&req, &config.gateway.trusted_proxies);
+                    let allowed = client_info.ip.map_or(false, |ip| config.metrics.allowed_ips.contains(&ip));
+                    Box::new(future::result(if allowed {
+                        Ok(self.deprecations.metrics() + &self.analytics.metrics())
+                    } else {
+                        Err(format_err!("Metrics are not available for this client")
+                            .context(Error::Forbidden)
+                            .into())
+                    }))
+                }
+
+                (&Get, Some(Route::Readiness)) => Box::new(future::ok(
+                    json!({ "circuit_breakers": self.circuit_breakers.states() }).to_string(),
+                )),
+
                 (&Get, Some(Route::VerifyEmail(token))) => {
                     let body = json!({ "token": token }).to_string();
                     let url = format!("{}/email_verify_apply", saga_addr);
                     Box::new(
-                        client
-                            .request_json::<String>(Post, url.clone(), Some(body), None)
+                        http_clients
+                            .for_url(&url)
+                            .request_json::<String>(Post, url.clone(), Some(body), None, None)
                             .map_err(From::from)
                             .then(move |r| match r {
                                 Err(e) => Err(e),
@@ -359,3 +513,92 @@ impl Controller for ControllerImpl {
         )
     }
 }
+
+/// Client is identified by user id, session id or ip in this order
+/// Identifies client for sticky canary routing
+fn client_key(token_payload: &Option<JWTPayload>, session_id: &Option<SessionId>, client_info: &ClientInfo) -> String {
+    if let Some(ref payload) = *token_payload {
+        return format!("user:{}", payload.user_id);
+    }
+    if let Some(ref session_id) = *session_id {
+        return format!("session:{}", session_id);
+    }
+    format!("ip:{}", client_info.ip.clone().unwrap_or_default())
+}
+
+/// Client application named in header, or user from JWT
+fn client_name(client_info: &ClientInfo, token_payload: &Option<JWTPayload>) -> String {
+    match (client_info.name.as_ref(), token_payload) {
+        (Some(name), _) => name.clone(),
+        (None, &Some(ref payload)) => format!("user:{}", payload.user_id),
+        (None, &None) => "anonymous".to_string(),
+    }
+}
+
+/// Operations rejected before execution are counted as failed
+fn rejected(
+    analytics: &OperationAnalytics,
+    key: OperationKey,
+    started_at: Instant,
+    upstream_calls: HashMap<String, u64>,
+    e: FieldError,
+) -> Result<serde_json::Value, serde_json::Error> {
+    analytics.record(key, started_at.elapsed(), true, upstream_calls);
+    serde_json::to_value(GraphQLResponse::from_field_error(e))
+}
+
+fn check_introspection_access(context: &Context, raw_req: &RawGraphQLRequest) -> FieldResult<()> {
+    let explorer = &context.config.schema_explorer;
+    if explorer.introspection == IntrospectionAccess::Everyone || !is_introspection(&raw_req.query) {
+        return Ok(());
+    }
+
+    let allowed_ip = context
+        .client_info
+        .ip
+        .as_ref()
+        .map_or(false, |ip| explorer.introspection_allowed_ips.contains(ip));
+    if allowed_ip {
+        return Ok(());
+    }
+    if explorer.introspection == IntrospectionAccess::Superusers && context.user.is_some() && context.permissions().is_superuser()? {
+        return Ok(());
+    }
+
+    Err(FieldError::new(
+        "Introspection is forbidden.",
+        graphql_value!({ "code": 119, "type": "INTROSPECTION_FORBIDDEN", "details": { "Introspection is not available for this client." }}),
+    ))
+}
+
+/// Records mutations done by admin on behalf of another user
+/// Query is not logged as it may carry passwords and payment details, root fields are logged instead
+fn audit_impersonated_operation(context: &Context, document: &Document, operation: Option<&Operation>, success: bool) {
+    let (user_id, actor) = match context.user {
+        Some(ref payload) => match payload.actor {
+            Some(actor) => (payload.user_id, actor),
+            None => return,
+        },
+        None => return,
+    };
+
+    let operation_type = operation.map(|op| op.kind.as_str());
+    if operation_type == Some("query") {
+        return;
+    }
+
+    let operation_name = operation.and_then(|op| op.name.clone()).unwrap_or_default();
+    let root_fields = operation.map(|op| document.root_fields(op)).unwrap_or_default();
+    let correlation_token = context.correlation_token.clone().map(|token| token.0).unwrap_or(context.uuid.clone());
+    info!(
+        target: "audit",
+        "Impersonated operation {:?} `{}` by actor user {} on behalf of user {}, success: {}, correlation token: {}, fields: {}",
+        operation_type,
+        operation_name,
+        actor,
+        user_id,
+        success,
+        correlation_token,
+        root_fields.join(", "),
+    );
+}
@@ -0,0 +1,140 @@
+//! Token bucket rate limiter for top level GraphQL fields
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use juniper::FieldError;
+use stq_types::UserId;
+
+use config::{OperationLimit, RateLimit};
+
+/// Buckets are cleaned up after this amount is reached
+const MAX_BUCKETS: usize = 100_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+pub struct RateLimiter {
+    limits: HashMap<String, OperationLimit>,
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimit) -> Self {
+        // config keys are lowercased by config crate
+        let limits = config
+            .operations
+            .iter()
+            .map(|(field, limit)| (field.to_lowercase(), limit.clone()))
+            .collect();
+
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for every limited field requested by client identified by `key`,
+    /// field selected several times under different aliases takes as many tokens.
+    /// Returns number of seconds to wait if any of the buckets has not enough tokens.
+    pub fn check(&self, fields: &[String], key: &str) -> Result<(), u64> {
+        self.check_keys(fields, &[key.to_string()])
+    }
+
+    /// Same as `check` for client identified by several `keys`, tokens are taken
+    /// from buckets of every key only if all of them have enough
+    pub fn check_keys(&self, fields: &[String], keys: &[String]) -> Result<(), u64> {
+        let mut limited: Vec<(String, &OperationLimit, f64)> = vec![];
+        for field in fields {
+            let field = field.to_lowercase();
+            if let Some(limit) = self.limits.get(&field) {
+                match limited.iter().position(|&(ref limited_field, _, _)| *limited_field == field) {
+                    Some(index) => limited[index].2 += 1.0,
+                    None => limited.push((field, limit, 1.0)),
+                }
+            }
+        }
+        if limited.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(_) => return Ok(()),
+        };
+        let now = Instant::now();
+
+        if buckets.len() > MAX_BUCKETS {
+            let limits = &self.limits;
+            buckets.retain(|&(ref field, _), bucket| match limits.get(field) {
+                Some(limit) => now.duration_since(bucket.updated_at) < Duration::from_secs(u64::from(limit.period_sec)),
+                None => false,
+            });
+        }
+
+        let mut retry_after = 0;
+        for key in keys {
+            for &(ref field, limit, count) in &limited {
+                let bucket = buckets.entry((field.clone(), key.clone())).or_insert_with(|| Bucket {
+                    tokens: f64::from(limit.capacity),
+                    updated_at: now,
+                });
+                refill(bucket, limit, now);
+                if bucket.tokens < count {
+                    // more selections than capacity can never succeed, client is asked to wait for a full bucket
+                    let wait = (count.min(f64::from(limit.capacity)) - bucket.tokens) / refill_rate(limit);
+                    retry_after = retry_after.max((wait.ceil() as u64).max(1));
+                }
+            }
+        }
+
+        if retry_after > 0 {
+            return Err(retry_after);
+        }
+
+        for key in keys {
+            for &(ref field, _, count) in &limited {
+                if let Some(bucket) = buckets.get_mut(&(field.clone(), key.clone())) {
+                    bucket.tokens -= count;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn refill_rate(limit: &OperationLimit) -> f64 {
+    f64::from(limit.capacity) / f64::from(limit.period_sec.max(1))
+}
+
+fn refill(bucket: &mut Bucket, limit: &OperationLimit, now: Instant) {
+    let elapsed = now.duration_since(bucket.updated_at);
+    let elapsed_sec = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+    bucket.tokens = (bucket.tokens + elapsed_sec * refill_rate(limit)).min(f64::from(limit.capacity));
+    bucket.updated_at = now;
+}
+
+/// Buckets charged for a request: user from JWT, otherwise client ip together with session id if any,
+/// as anonymous clients can send a new session id with every request
+pub fn client_keys(user_id: Option<UserId>, session_id: Option<String>, ip: Option<String>) -> Vec<String> {
+    if let Some(user_id) = user_id {
+        return vec![format!("user:{}", user_id)];
+    }
+    let mut keys = vec![format!("ip:{}", ip.unwrap_or_default())];
+    if let Some(session_id) = session_id {
+        keys.push(format!("session:{}", session_id));
+    }
+    keys
+}
+
+pub fn rate_limited_error(retry_after: u64) -> FieldError {
+    let retry_after = retry_after as i32;
+    FieldError::new(
+        "Too many requests.",
+        graphql_value!({ "code": 116, "type": "RATE_LIMITED", "details": { "retry_after": retry_after }}),
+    )
+}
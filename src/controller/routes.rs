@@ -6,6 +6,8 @@ pub enum Route {
     Root,
     Graphql,
     Healthcheck,
+    Readiness,
+    Metrics,
     VerifyEmail(String),
     ResetPassword,
     RegisterDevice,
@@ -16,6 +18,8 @@ pub fn create_route_parser() -> RouteParser<Route> {
     router.add_route(r"^/$", || Route::Root);
     router.add_route(r"^/graphql$", || Route::Graphql);
     router.add_route(r"^/healthcheck$", || Route::Healthcheck);
+    router.add_route(r"^/readiness$", || Route::Readiness);
+    router.add_route(r"^/metrics$", || Route::Metrics);
     router.add_route_with_params(r"^/verify_email/(\S+)$", |params| {
         params.get(0).map(|s| s.to_string()).map(Route::VerifyEmail)
     });
@@ -0,0 +1,196 @@
+//! Statistics of GraphQL operations by operation name and client application: count, errors,
+//! latency percentiles and upstream calls. Kept in memory since gateway start.
+
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use graphql::metrics::{bounded_key, escape_label, OTHER};
+use graphql::models::{OperationStatistics, UpstreamCallsCount};
+
+/// Percentiles are computed over this number of latest operations
+const LATENCY_SAMPLES: usize = 1000;
+const UNKNOWN: &str = "unknown";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OperationKey {
+    pub operation_name: String,
+    pub client_name: String,
+    pub client_version: String,
+}
+
+impl OperationKey {
+    pub fn new(operation_name: Option<String>, client_name: Option<String>, client_version: Option<String>) -> Self {
+        Self {
+            operation_name: operation_name.unwrap_or_else(|| UNKNOWN.to_string()),
+            client_name: client_name.unwrap_or_else(|| UNKNOWN.to_string()),
+            client_version: client_version.unwrap_or_else(|| UNKNOWN.to_string()),
+        }
+    }
+
+    fn other() -> Self {
+        Self {
+            operation_name: OTHER.to_string(),
+            client_name: OTHER.to_string(),
+            client_version: OTHER.to_string(),
+        }
+    }
+}
+
+/// Upstream calls made while executing one operation, by microservice name
+#[derive(Debug, Default)]
+pub struct UpstreamCalls(Mutex<HashMap<String, u64>>);
+
+impl UpstreamCalls {
+    pub fn add(&self, microservice: &str) {
+        *self.0.lock().unwrap().entry(microservice.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn counts(&self) -> HashMap<String, u64> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    count: u64,
+    errors: u64,
+    latencies_ms: VecDeque<u64>,
+    upstream_calls: HashMap<String, u64>,
+}
+
+impl Stats {
+    fn percentile_ms(&self, percentile: f64) -> f64 {
+        let mut latencies = self.latencies_ms.iter().cloned().collect::<Vec<_>>();
+        if latencies.is_empty() {
+            return 0.0;
+        }
+        latencies.sort();
+        let rank = (percentile / 100.0 * latencies.len() as f64).ceil() as usize;
+        latencies[cmp::min(cmp::max(rank, 1), latencies.len()) - 1] as f64
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.count as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OperationAnalytics {
+    operations: Mutex<HashMap<OperationKey, Stats>>,
+}
+
+impl OperationAnalytics {
+    /// Operation failed if its response has any errors
+    pub fn record(&self, key: OperationKey, elapsed: Duration, failed: bool, upstream_calls: HashMap<String, u64>) {
+        let mut operations = self.operations.lock().unwrap();
+        let key = bounded_key(&operations, key, OperationKey::other);
+
+        let stats = operations.entry(key).or_insert_with(Stats::default);
+        stats.count += 1;
+        if failed {
+            stats.errors += 1;
+        }
+        if stats.latencies_ms.len() == LATENCY_SAMPLES {
+            stats.latencies_ms.pop_front();
+        }
+        let elapsed_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_nanos()) / 1_000_000;
+        stats.latencies_ms.push_back(elapsed_ms);
+        for (microservice, count) in upstream_calls {
+            *stats.upstream_calls.entry(microservice).or_insert(0) += count;
+        }
+    }
+
+    /// Statistics sorted by operation name, client name and client version
+    pub fn statistics(&self) -> Vec<OperationStatistics> {
+        let operations = self.operations.lock().unwrap();
+        let mut statistics = operations
+            .iter()
+            .map(|(key, stats)| {
+                let mut upstream_calls = stats
+                    .upstream_calls
+                    .iter()
+                    .map(|(microservice, &count)| UpstreamCallsCount {
+                        microservice: microservice.clone(),
+                        count: to_i32(count),
+                    })
+                    .collect::<Vec<_>>();
+                upstream_calls.sort_by(|a, b| a.microservice.cmp(&b.microservice));
+                (
+                    key.clone(),
+                    OperationStatistics {
+                        operation_name: key.operation_name.clone(),
+                        client_name: key.client_name.clone(),
+                        client_version: key.client_version.clone(),
+                        count: to_i32(stats.count),
+                        error_rate: stats.error_rate(),
+                        p50_latency_ms: stats.percentile_ms(50.0),
+                        p95_latency_ms: stats.percentile_ms(95.0),
+                        upstream_calls,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        statistics.sort_by(|a, b| a.0.cmp(&b.0));
+        statistics.into_iter().map(|(_, statistics)| statistics).collect()
+    }
+
+    /// Statistics in Prometheus text format
+    pub fn metrics(&self) -> String {
+        let operations = self.operations.lock().unwrap();
+        let mut keys = operations.keys().collect::<Vec<_>>();
+        keys.sort();
+
+        let mut counts = "# HELP gateway_operations_total GraphQL operations\n# TYPE gateway_operations_total counter\n".to_string();
+        let mut errors = "# HELP gateway_operation_errors_total GraphQL operations with errors in response\n\
+                          # TYPE gateway_operation_errors_total counter\n"
+            .to_string();
+        let mut latencies = "# HELP gateway_operation_latency_ms Latency of latest GraphQL operations\n\
+                             # TYPE gateway_operation_latency_ms summary\n"
+            .to_string();
+        let mut upstream_calls = "# HELP gateway_operation_upstream_calls_total Microservice calls made by GraphQL operations\n\
+                                  # TYPE gateway_operation_upstream_calls_total counter\n"
+            .to_string();
+
+        for key in keys {
+            let stats = &operations[key];
+            let labels = format!(
+                "operation=\"{}\",client=\"{}\",version=\"{}\"",
+                escape_label(&key.operation_name),
+                escape_label(&key.client_name),
+                escape_label(&key.client_version)
+            );
+            counts += &format!("gateway_operations_total{{{}}} {}\n", labels, stats.count);
+            errors += &format!("gateway_operation_errors_total{{{}}} {}\n", labels, stats.errors);
+            for &(quantile, percentile) in &[("0.5", 50.0), ("0.95", 95.0)] {
+                latencies += &format!(
+                    "gateway_operation_latency_ms{{{},quantile=\"{}\"}} {}\n",
+                    labels,
+                    quantile,
+                    stats.percentile_ms(percentile)
+                );
+            }
+            let mut microservices = stats.upstream_calls.iter().collect::<Vec<_>>();
+            microservices.sort();
+            for (microservice, count) in microservices {
+                upstream_calls += &format!(
+                    "gateway_operation_upstream_calls_total{{{},microservice=\"{}\"}} {}\n",
+                    labels,
+                    escape_label(microservice),
+                    count
+                );
+            }
+        }
+
+        counts + &errors + &latencies + &upstream_calls
+    }
+}
+
+fn to_i32(value: u64) -> i32 {
+    cmp::min(value, i32::max_value() as u64) as i32
+}
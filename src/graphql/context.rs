@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use std::time::SystemTime;
 
 use chrono::prelude::*;
@@ -14,21 +15,34 @@ use serde::ser::SerializeMap;
 use uuid::Uuid;
 
 use config::Config;
+use controller::client_info::ClientInfo;
+use controller::cookies::ResponseCookies;
+use errors::into_graphql;
 
 use http::{
     header::{HeaderName, HeaderValue},
     HeaderMap,
 };
+use stq_api::errors::Error as ApiError;
 use stq_api::rpc_client::RestApiClient;
-use stq_http::client::{ClientHandle, Error, HttpClient, TimeLimitedHttpClient};
+use stq_http::client::Error;
 use stq_http::request_util::{CorrelationToken, Currency as CurrencyHeader, FiatCurrency as FiatCurrencyHeader};
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_static_resources::Currency;
 use stq_types::{SessionId, StoresRole};
 
+use upstream::{
+    deadline_exceeded_error, is_upstream_failure, upstream_timeout_error, upstream_unavailable_error, CanaryRouting, CircuitBreaker,
+    CircuitBreakers, Deadline, HttpClients, PickedEndpoint,
+};
+
+use graphql::analytics::{OperationAnalytics, UpstreamCalls};
+use graphql::deprecation::DeprecationTracker;
 use graphql::models::jwt::JWTPayload;
-use graphql::models::User;
+use graphql::resilience::{is_upstream_failure_error, partial_result_error, LastKnownGood};
+use graphql::session_cache::ActiveSessions;
+use graphql::models::{NewUserSession, User, UserSession};
 
 use graphql::microservice::{
     BillingService, BillingServiceImpl, DeliveryService, DeliveryServiceImpl, OrdersService, OrdersServiceImpl, SagaService,
@@ -36,7 +50,7 @@ use graphql::microservice::{
 };
 
 pub struct Context {
-    pub http_client: TimeLimitedHttpClient<ClientHandle>,
+    pub http_clients: HttpClients,
     pub user: Option<JWTPayload>,
     pub session_id: Option<SessionId>,
     pub currency: Option<Currency>,
@@ -44,6 +58,17 @@ pub struct Context {
     pub correlation_token: Option<CorrelationToken>,
     pub uuid: String,
     pub config: Config,
+    pub response_cookies: ResponseCookies,
+    pub client_info: ClientInfo,
+    pub circuit_breakers: Arc<CircuitBreakers>,
+    pub deadline: Deadline,
+    pub canary: CanaryRouting,
+    pub last_known_good: Arc<LastKnownGood>,
+    pub deprecations: Arc<DeprecationTracker>,
+    pub analytics: Arc<OperationAnalytics>,
+    pub active_sessions: Arc<ActiveSessions>,
+    /// Microservice calls made by this operation
+    pub upstream_calls: UpstreamCalls,
 }
 
 pub struct Permissions<'r> {
@@ -54,18 +79,27 @@ impl juniper::Context for Context {}
 
 impl Context {
     pub fn new(
-        http_client: TimeLimitedHttpClient<ClientHandle>,
+        http_clients: HttpClients,
         user: Option<JWTPayload>,
         session_id: Option<SessionId>,
         currency: Option<Currency>,
         fiat_currency: Option<Currency>,
         config: Config,
         correlation_token: Option<CorrelationToken>,
+        response_cookies: ResponseCookies,
+        client_info: ClientInfo,
+        circuit_breakers: Arc<CircuitBreakers>,
+        deadline: Deadline,
+        canary: CanaryRouting,
+        last_known_good: Arc<LastKnownGood>,
+        deprecations: Arc<DeprecationTracker>,
+        analytics: Arc<OperationAnalytics>,
+        active_sessions: Arc<ActiveSessions>,
     ) -> Self {
         let uuid = Uuid::new_v4().hyphenated().to_string();
 
         Self {
-            http_client,
+            http_clients,
             user,
             session_id,
             currency,
@@ -73,20 +107,96 @@ impl Context {
             uuid,
             config,
             correlation_token,
+            response_cookies,
+            client_info,
+            circuit_breakers,
+            deadline,
+            canary,
+            last_known_good,
+            deprecations,
+            analytics,
+            active_sessions,
+            upstream_calls: UpstreamCalls::default(),
         }
     }
 
-    pub fn get_rest_api_client(&self, s: Service) -> RestApiClient {
+    /// Calls microservice through `RestApiClient`, guarded by circuit breaker and balanced between endpoints the same way
+    /// as `request` and limited by `timeout_ms` of microservice and the operation deadline. Calls are never retried,
+    /// as `RestApiClient` does not tell reads from writes, and `pool_size` does not apply to its own http client.
+    pub fn call_rest_api<T, F, R>(&self, s: Service, call: F) -> FieldResult<T>
+    where
+        F: FnOnce(&RestApiClient) -> R,
+        R: Future<Item = T, Error = ApiError>,
+    {
+        let service_url = self.config.service_url(s);
+        let base_url = self.canary.route(&service_url);
+        let circuit_breaker = self.acquire_circuit_breaker(&base_url)?;
+        let endpoint = self.http_clients.endpoint(&base_url);
+        let client = self.rest_api_client(endpoint.url())?;
+        let remaining = self.deadline.remaining().ok_or_else(deadline_exceeded_error)?;
+        let timeout = self.http_clients.for_url(&base_url).timeout().min(remaining);
+
+        self.upstream_calls.add(self.upstream_name(&service_url));
+        // `None` stands for timeout
+        let result = match self.http_clients.wait_with_timeout(call(&client), timeout) {
+            Ok(Some(x)) => Ok(x),
+            Ok(None) => Err(None),
+            Err(e) => Err(Some(e)),
+        };
+        let failure = result.as_ref().err().map(|e| e.as_ref().map_or(true, is_api_failure));
+        record_circuit_breaker(circuit_breaker, self.deadline, failure);
+        record_balancer(endpoint, self.deadline, failure);
+        result.map_err(|e| match e {
+            Some(e) => into_graphql(e),
+            None if self.deadline.remaining().is_none() => deadline_exceeded_error(),
+            None => upstream_timeout_error(self.upstream_name(&service_url)),
+        })
+    }
+
+    fn rest_api_client(&self, endpoint_url: &str) -> FieldResult<RestApiClient> {
+        let remaining_ms = self.deadline.remaining_ms().ok_or_else(deadline_exceeded_error)?;
+
         let header_name = HeaderName::from_static("correlation-token");
 
-        let headers = match self.correlation_token.clone() {
+        let mut headers = match self.correlation_token.clone() {
             Some(value) => vec![(header_name, HeaderValue::from_str(&value.0).unwrap())],
             None => vec![(header_name, HeaderValue::from_str(&self.uuid).unwrap())],
         }
         .into_iter()
         .collect::<HeaderMap>();
+        headers.insert(
+            HeaderName::from_static("request-timeout"),
+            HeaderValue::from_str(&remaining_ms.to_string()).unwrap(),
+        );
+
+        Ok(RestApiClient::new_with_default_headers(
+            endpoint_url,
+            self.user.clone().map(|u| u.user_id),
+            Some(headers),
+        ))
+    }
 
-        RestApiClient::new_with_default_headers(&self.config.service_url(s), self.user.clone().map(|u| u.user_id), Some(headers))
+    /// Microservice name from config, unknown urls are named `other`
+    fn upstream_name(&self, url: &str) -> &'static str {
+        self.config
+            .upstreams()
+            .into_iter()
+            .find(|&(_, microservice)| url.starts_with(&microservice.url))
+            .map_or("other", |(name, _)| name)
+    }
+
+    /// Fails immediately if circuit breaker of microservice is open
+    fn acquire_circuit_breaker(&self, url: &str) -> Result<Option<Arc<CircuitBreaker>>, FieldError> {
+        match self.circuit_breakers.for_url(url) {
+            Some(breaker) => {
+                if breaker.acquire() {
+                    Ok(Some(breaker))
+                } else {
+                    Err(upstream_unavailable_error(breaker.name()))
+                }
+            }
+            None => Ok(None),
+        }
     }
 
     pub fn request<T>(&self, method: hyper::Method, url: String, body: Option<String>) -> Box<Future<Item = T, Error = FieldError> + Send>
@@ -124,12 +234,31 @@ impl Context {
 
         self.set_correlation_token(&mut headers);
 
+        if self.deadline.remaining().is_none() {
+            return Box::new(future::err(deadline_exceeded_error()));
+        }
+        let upstream = self.upstream_name(&url);
+        // canary builds are not covered by circuit breakers of stable ones
+        let url = self.canary.route(&url);
+        let circuit_breaker = match self.acquire_circuit_breaker(&url) {
+            Ok(circuit_breaker) => circuit_breaker,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        // calls rejected by circuit breaker or deadline never reach microservice
+        self.upstream_calls.add(upstream);
+        let deadline = self.deadline;
+
         let dt = Local::now();
         let correlation_token = self.correlation_token.clone().map(|token| token.0).unwrap_or(self.uuid.clone());
 
         Box::new(
-            self.http_client
-                .request_json(method, url.clone(), body, Some(headers))
+            self.http_clients
+                .for_url(&url)
+                .request_json(method, url.clone(), body, Some(headers), Some(deadline))
+                .then(move |r| {
+                    record_circuit_breaker(circuit_breaker, deadline, r.as_ref().err().map(is_upstream_failure));
+                    r
+                })
                 .map_err(Error::into_graphql)
                 .then(move |r| {
                     let d = Local::now() - dt;
@@ -171,12 +300,30 @@ impl Context {
     {
         let mut headers = Headers::new();
         self.set_correlation_token(&mut headers);
+        if self.deadline.remaining().is_none() {
+            return Box::new(future::err(deadline_exceeded_error()));
+        }
+        let upstream = self.upstream_name(&url);
+        // canary builds are not covered by circuit breakers of stable ones
+        let url = self.canary.route(&url);
+        let circuit_breaker = match self.acquire_circuit_breaker(&url) {
+            Ok(circuit_breaker) => circuit_breaker,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        // calls rejected by circuit breaker or deadline never reach microservice
+        self.upstream_calls.add(upstream);
+        let deadline = self.deadline;
         let dt = Local::now();
         let correlation_token = self.correlation_token.clone().map(|token| token.0).unwrap_or(self.uuid.clone());
 
         Box::new(
-            self.http_client
-                .request_json(method, url.clone(), body, Some(headers))
+            self.http_clients
+                .for_url(&url)
+                .request_json(method, url.clone(), body, Some(headers), Some(deadline))
+                .then(move |r| {
+                    record_circuit_breaker(circuit_breaker, deadline, r.as_ref().err().map(is_upstream_failure));
+                    r
+                })
                 .map_err(Error::into_graphql)
                 .then(move |r| {
                     let d = Local::now() - dt;
@@ -234,6 +381,42 @@ impl Context {
         Box::new(OrdersServiceImpl::new(self))
     }
 
+    /// Resolves remote field so that failure of microservice does not fail the parent object.
+    /// Serves last known good value if `field` is configured to, otherwise returns null with typed error.
+    /// Errors which are not caused by microservice failure are returned as they are.
+    pub fn resilient<T, F>(&self, field: &str, key: &str, resolve: F) -> FieldResult<Option<T>>
+    where
+        T: Clone + Send + 'static,
+        F: FnOnce() -> FieldResult<T>,
+    {
+        let serves_stale = self.last_known_good.serves_stale(field);
+        match resolve() {
+            Ok(value) => {
+                if serves_stale {
+                    self.last_known_good.put(field, key, value.clone());
+                }
+                Ok(Some(value))
+            }
+            Err(e) => {
+                if !is_upstream_failure_error(&e) {
+                    return Err(e);
+                }
+                warn!("Field {} failed with error `{:?}`, correlation token: {}", field, e, self.uuid);
+                match self.last_known_good.get::<T>(field, key) {
+                    Some(value) if serves_stale => Ok(Some(value)),
+                    _ => Err(partial_result_error(field)),
+                }
+            }
+        }
+    }
+
+    pub fn new_user_session(&self) -> NewUserSession {
+        NewUserSession {
+            device: self.client_info.user_agent.clone(),
+            ip: self.client_info.ip.clone(),
+        }
+    }
+
     pub fn permissions(&self) -> Permissions {
         Permissions { context: &self }
     }
@@ -246,35 +429,110 @@ impl<'r> Permissions<'r> {
         }
         Ok(Vec::new())
     }
+
+    pub fn is_superuser(&self) -> FieldResult<bool> {
+        self.store_roles().map(|roles| roles.contains(&StoresRole::Superuser))
+    }
+
+    pub fn require_superuser(&self) -> FieldResult<()> {
+        if self.is_superuser()? {
+            return Ok(());
+        }
+        Err(FieldError::new(
+            "Operation is forbidden.",
+            graphql_value!({ "code": 117, "type": "SUPERUSER_REQUIRED", "details": { "Only superuser can do this operation." }}),
+        ))
+    }
+
+    /// Forbids operations which must be done by the user himself
+    pub fn deny_impersonated(&self) -> FieldResult<()> {
+        match self.context.user {
+            Some(ref payload) if payload.is_impersonated() => Err(FieldError::new(
+                "Operation is forbidden while impersonating.",
+                graphql_value!({
+                    "code": 118,
+                    "type": "IMPERSONATED_OPERATION_FORBIDDEN",
+                    "details": { "This operation can not be done on behalf of another user." }
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `failure` is `None` for successful request, otherwise it tells if microservice has failed.
+/// Requests cut short by the operation deadline say nothing about microservice health.
+fn record_circuit_breaker(circuit_breaker: Option<Arc<CircuitBreaker>>, deadline: Deadline, failure: Option<bool>) {
+    if let Some(circuit_breaker) = circuit_breaker {
+        match failure {
+            Some(_) if deadline.remaining().is_none() => {}
+            Some(true) => circuit_breaker.on_failure(),
+            _ => circuit_breaker.on_success(),
+        }
+    }
+}
+
+/// Same as `record_circuit_breaker` for the endpoint picked by balancer,
+/// the endpoint is no longer counted as in flight once the result is recorded
+fn record_balancer(endpoint: PickedEndpoint, deadline: Deadline, failure: Option<bool>) {
+    match failure {
+        Some(_) if deadline.remaining().is_none() => {}
+        Some(true) => endpoint.on_failure(),
+        _ => endpoint.on_success(),
+    }
+}
+
+/// Same as `is_upstream_failure` for errors of `RestApiClient`
+fn is_api_failure(e: &ApiError) -> bool {
+    match *e {
+        ApiError::Api(ref status, _) => status.is_server_error(),
+        ApiError::Network(_) => true,
+        _ => false,
+    }
 }
 
 pub fn check_jwt_not_revoked(
-    http_client: &TimeLimitedHttpClient<ClientHandle>,
+    http_clients: &HttpClients,
+    deadline: Deadline,
+    correlation_token: Option<CorrelationToken>,
     jwt_payload: &JWTPayload,
     users_url: String,
+    active_sessions: &ActiveSessions,
 ) -> Result<(), FieldError> {
     let mut headers = Headers::new();
     headers.set(Authorization(jwt_payload.to_string()));
+    if let Some(ref correlation_token) = correlation_token {
+        headers.set(correlation_token.clone());
+    }
     let url = format!("{}/{}/current", users_url, Model::User.to_url());
-    let user = http_client
-        .request_json::<Option<User>>(hyper::Method::Get, url, None, Some(headers))
+    let user = http_clients
+        .for_url(&url)
+        .request_json::<Option<User>>(hyper::Method::Get, url, None, Some(headers), Some(deadline))
         .map_err(Error::into_graphql)
         .wait()?;
     if let Some(user) = user {
         //jwt exp must be greater or equal than revoke timestamp
         match user.revoke_before.duration_since(SystemTime::UNIX_EPOCH) {
             Ok(n) => {
-                if jwt_payload.exp >= n.as_secs() as i64 {
-                    Ok(())
-                } else {
-                    Err(FieldError::new(
+                if jwt_payload.exp < n.as_secs() as i64 {
+                    return Err(FieldError::new(
                         "JWT has been revoked.",
                         graphql_value!({ "code": 112, "details": { "Current JWT can not be used anymore." }}),
-                    ))
+                    ));
                 }
             }
             Err(_) => unreachable!(),
         }
+
+        match jwt_payload.user_session_id {
+            Some(user_session_id) if active_sessions.is_active(user_session_id) => Ok(()),
+            Some(user_session_id) => {
+                check_user_session_not_revoked(http_clients, deadline, correlation_token, jwt_payload, user_session_id, users_url)?;
+                active_sessions.mark_active(user_session_id);
+                Ok(())
+            }
+            None => Ok(()),
+        }
     } else {
         Err(FieldError::new(
             "Could not get user info by jwt token.",
@@ -283,6 +541,34 @@ pub fn check_jwt_not_revoked(
     }
 }
 
+fn check_user_session_not_revoked(
+    http_clients: &HttpClients,
+    deadline: Deadline,
+    correlation_token: Option<CorrelationToken>,
+    jwt_payload: &JWTPayload,
+    user_session_id: Uuid,
+    users_url: String,
+) -> Result<(), FieldError> {
+    let mut headers = Headers::new();
+    headers.set(Authorization(jwt_payload.to_string()));
+    if let Some(ref correlation_token) = correlation_token {
+        headers.set(correlation_token.clone());
+    }
+    let url = format!("{}/user_sessions/{}", users_url, user_session_id.hyphenated());
+    let session = http_clients
+        .for_url(&url)
+        .request_json::<Option<UserSession>>(hyper::Method::Get, url, None, Some(headers), Some(deadline))
+        .map_err(Error::into_graphql)
+        .wait()?;
+    match session {
+        Some(ref session) if session.revoked_at.is_none() => Ok(()),
+        _ => Err(FieldError::new(
+            "Session has been revoked.",
+            graphql_value!({ "code": 112, "details": { "Current JWT session can not be used anymore." }}),
+        )),
+    }
+}
+
 pub struct GraphQLResponse(juniper::Value, Vec<juniper::ExecutionError>);
 
 impl GraphQLResponse {
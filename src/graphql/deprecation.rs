@@ -0,0 +1,232 @@
+//! Usage of deprecated fields and arguments by clients, so that deprecated paths can be removed once nobody calls them.
+//!
+//! Selections of each operation are matched against the schema introspection. Fields are deprecated
+//! with `field deprecated`, arguments by `[DEPRECATED]` in the beginning of their description.
+
+use std::cmp;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
+
+use failure::Error as FailureError;
+use serde_json::Value;
+
+use config::Config;
+use graphql::document::{Document, Parser, Selection};
+use graphql::introspection;
+use graphql::metrics::{bounded_key, escape_label, OTHER};
+use graphql::models::DeprecatedFieldUsage;
+
+const DEPRECATED_ARGUMENT_PREFIX: &str = "[DEPRECATED]";
+
+#[derive(Debug, Default)]
+struct FieldInfo {
+    /// Named type of the field with lists and non-nulls unwrapped
+    type_name: String,
+    deprecated: bool,
+    deprecated_args: Vec<String>,
+}
+
+/// Fields of object and interface types needed to follow selections
+#[derive(Debug, Default)]
+pub struct SchemaFields {
+    query_type: String,
+    mutation_type: Option<String>,
+    types: HashMap<String, HashMap<String, FieldInfo>>,
+}
+
+impl SchemaFields {
+    pub fn from_introspection(introspection: &Value) -> Result<Self, FailureError> {
+        let schema = introspection::schema_of(introspection)?;
+        let mut types = HashMap::new();
+        for type_ in schema["types"].as_array().map(|types| types.as_slice()).unwrap_or(&[]) {
+            let fields = match type_["fields"].as_array() {
+                Some(fields) => fields,
+                None => continue,
+            };
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    let deprecated_args = field["args"]
+                        .as_array()
+                        .map(|args| {
+                            args.iter()
+                                .filter(|arg| {
+                                    arg["description"]
+                                        .as_str()
+                                        .map_or(false, |d| d.starts_with(DEPRECATED_ARGUMENT_PREFIX))
+                                })
+                                .map(|arg| name_of(arg).to_string())
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    let info = FieldInfo {
+                        type_name: named_type(&field["type"]).to_string(),
+                        deprecated: field["isDeprecated"].as_bool().unwrap_or(false),
+                        deprecated_args,
+                    };
+                    (name_of(field).to_string(), info)
+                })
+                .collect();
+            types.insert(name_of(type_).to_string(), fields);
+        }
+
+        Ok(Self {
+            query_type: schema["queryType"]["name"].as_str().unwrap_or("Query").to_string(),
+            mutation_type: schema["mutationType"]["name"].as_str().map(|name| name.to_string()),
+            types,
+        })
+    }
+
+    /// Deprecated fields and arguments selected by operation, as `Type.field` and `Type.field(argument)`
+    pub fn deprecated_in(&self, query: &str, operation_name: Option<&str>) -> BTreeSet<String> {
+        let document = Parser::new(query).document();
+        let mut used = BTreeSet::new();
+
+        if let Some(operation) = document.operation(operation_name) {
+            let root = match operation.kind.as_str() {
+                "mutation" => self.mutation_type.as_ref().unwrap_or(&self.query_type),
+                _ => &self.query_type,
+            };
+            self.collect(&document, root, &operation.selections, &mut HashSet::new(), &mut used);
+        }
+        used
+    }
+
+    fn collect<'a>(
+        &self,
+        document: &'a Document,
+        parent: &str,
+        selections: &'a [Selection],
+        fragments: &mut HashSet<&'a str>,
+        used: &mut BTreeSet<String>,
+    ) {
+        for selection in selections {
+            match *selection {
+                Selection::Field {
+                    ref name,
+                    ref args,
+                    ref selections,
+                } => {
+                    let field = match self.types.get(parent).and_then(|fields| fields.get(name)) {
+                        Some(field) => field,
+                        None => continue,
+                    };
+                    if field.deprecated {
+                        used.insert(format!("{}.{}", parent, name));
+                    }
+                    for arg in args.iter().filter(|arg| field.deprecated_args.contains(arg)) {
+                        used.insert(format!("{}.{}({})", parent, name, arg));
+                    }
+                    self.collect(document, &field.type_name, selections, fragments, used);
+                }
+                Selection::InlineFragment {
+                    ref type_condition,
+                    ref selections,
+                } => {
+                    let parent = type_condition.as_ref().map(|type_| type_.as_str()).unwrap_or(parent);
+                    self.collect(document, parent, selections, fragments, used);
+                }
+                Selection::FragmentSpread(ref name) => {
+                    if !fragments.insert(name) {
+                        continue;
+                    }
+                    if let Some(&(ref type_condition, ref selections)) = document.fragments.get(name) {
+                        self.collect(document, type_condition, selections, fragments, used);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Counts of deprecated fields usage by client
+#[derive(Debug, Default)]
+pub struct DeprecationTracker {
+    schema: SchemaFields,
+    /// Counts by field by client
+    usage: Mutex<HashMap<String, HashMap<String, u64>>>,
+}
+
+impl DeprecationTracker {
+    /// Tracking is disabled if schema can not be introspected
+    pub fn new(config: &Config) -> Self {
+        let schema = introspection::introspect(config.clone()).and_then(|introspection| SchemaFields::from_introspection(&introspection));
+        match schema {
+            Ok(schema) => Self::with_schema(schema),
+            Err(e) => {
+                error!("Deprecated fields usage will not be tracked, schema introspection failed: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn with_schema(schema: SchemaFields) -> Self {
+        Self {
+            schema,
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, client: &str, query: &str, operation_name: Option<&str>) {
+        let used = self.schema.deprecated_in(query, operation_name);
+        if used.is_empty() {
+            return;
+        }
+
+        let mut usage = self.usage.lock().unwrap();
+        let client = bounded_key(&usage, client.to_string(), || OTHER.to_string());
+        let counts = usage.entry(client).or_insert_with(HashMap::new);
+        for field in used {
+            *counts.entry(field).or_insert(0) += 1;
+        }
+    }
+
+    /// Usage sorted by field, then by client
+    pub fn usage(&self) -> Vec<DeprecatedFieldUsage> {
+        self.counts()
+            .into_iter()
+            .map(|(field, client, count)| DeprecatedFieldUsage {
+                field,
+                client,
+                count: cmp::min(count, i32::max_value() as u64) as i32,
+            })
+            .collect()
+    }
+
+    /// Usage in Prometheus text format
+    pub fn metrics(&self) -> String {
+        let mut metrics = "# HELP gateway_deprecated_field_usage_total Operations using deprecated field or argument\n\
+                           # TYPE gateway_deprecated_field_usage_total counter\n"
+            .to_string();
+        for (field, client, count) in self.counts() {
+            metrics += &format!(
+                "gateway_deprecated_field_usage_total{{field=\"{}\",client=\"{}\"}} {}\n",
+                escape_label(&field),
+                escape_label(&client),
+                count
+            );
+        }
+        metrics
+    }
+
+    fn counts(&self) -> Vec<(String, String, u64)> {
+        let usage = self.usage.lock().unwrap();
+        let mut counts = usage
+            .iter()
+            .flat_map(|(client, counts)| counts.iter().map(move |(field, &count)| (field.clone(), client.clone(), count)))
+            .collect::<Vec<_>>();
+        counts.sort();
+        counts
+    }
+}
+
+fn name_of(value: &Value) -> &str {
+    value["name"].as_str().unwrap_or_default()
+}
+
+fn named_type(type_ref: &Value) -> &str {
+    match type_ref["kind"].as_str() {
+        Some("NON_NULL") | Some("LIST") => named_type(&type_ref["ofType"]),
+        _ => name_of(type_ref),
+    }
+}
@@ -0,0 +1,411 @@
+//! Parser of selections in GraphQL request document, everything else in the document is skipped.
+//! Invalid documents give partial result, they are rejected by juniper anyway.
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct Operation {
+    /// `query`, `mutation` or `subscription`
+    pub kind: String,
+    pub name: Option<String>,
+    pub selections: Vec<Selection>,
+}
+
+#[derive(Debug)]
+pub enum Selection {
+    Field {
+        name: String,
+        args: Vec<String>,
+        selections: Vec<Selection>,
+    },
+    InlineFragment {
+        type_condition: Option<String>,
+        selections: Vec<Selection>,
+    },
+    FragmentSpread(String),
+}
+
+/// Fields of GraphQL request body which gateway itself is interested in
+#[derive(Deserialize, Clone, Debug)]
+pub struct RawGraphQLRequest {
+    pub query: String,
+    #[serde(rename = "operationName")]
+    pub operation_name: Option<String>,
+}
+
+impl RawGraphQLRequest {
+    pub fn document(&self) -> Document {
+        Parser::new(&self.query).document()
+    }
+
+    pub fn operation_name(&self) -> Option<&str> {
+        self.operation_name.as_ref().map(|name| name.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    /// Type condition and selections by fragment name
+    pub fragments: HashMap<String, (String, Vec<Selection>)>,
+}
+
+impl Document {
+    /// Operation to be executed - either named by `operationName` or the only one in the document
+    pub fn operation(&self, operation_name: Option<&str>) -> Option<&Operation> {
+        match operation_name {
+            Some(name) => self.operations.iter().find(|op| op.name.as_ref().map(|n| n.as_str()) == Some(name)),
+            None if self.operations.len() == 1 => self.operations.first(),
+            None => None,
+        }
+    }
+
+    /// Top level fields selected by operation with aliases resolved and fragments expanded,
+    /// a field selected several times is listed as many times
+    pub fn root_fields(&self, operation: &Operation) -> Vec<String> {
+        let mut fields = vec![];
+        self.collect_fields(&operation.selections, &mut HashSet::new(), &mut fields);
+        fields
+    }
+
+    fn collect_fields<'a>(&'a self, selections: &'a [Selection], fragments: &mut HashSet<&'a str>, fields: &mut Vec<String>) {
+        for selection in selections {
+            match *selection {
+                Selection::Field { ref name, .. } => fields.push(name.clone()),
+                Selection::InlineFragment { ref selections, .. } => self.collect_fields(selections, fragments, fields),
+                Selection::FragmentSpread(ref name) => {
+                    // fragment cycles are rejected by juniper, spreading a fragment twice selects the same fields
+                    if !fragments.insert(name) {
+                        continue;
+                    }
+                    if let Some(&(_, ref selections)) = self.fragments.get(name) {
+                        self.collect_fields(selections, fragments, fields);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Punctuator(char),
+    Spread,
+    Name(String),
+    /// String or number literal
+    Literal,
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(query: &str) -> Self {
+        Self {
+            tokens: tokenize(query),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_next(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punctuator(c))
+    }
+
+    fn name(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(&Token::Name(_)) => {}
+            _ => return None,
+        }
+        match self.next() {
+            Some(Token::Name(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn document(mut self) -> Document {
+        let mut document = Document::default();
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punctuator('{') => {
+                    let selections = self.selection_set();
+                    document.operations.push(Operation {
+                        kind: "query".to_string(),
+                        name: None,
+                        selections,
+                    });
+                }
+                Token::Name(ref keyword) if keyword == "fragment" => {
+                    self.next();
+                    let name = self.name().unwrap_or_default();
+                    self.name(); // on
+                    let type_condition = self.name().unwrap_or_default();
+                    self.skip_to_selection_set();
+                    let selections = self.selection_set();
+                    document.fragments.insert(name, (type_condition, selections));
+                }
+                Token::Name(kind) => {
+                    self.next();
+                    let name = self.name();
+                    self.skip_to_selection_set();
+                    let selections = self.selection_set();
+                    document.operations.push(Operation { kind, name, selections });
+                }
+                _ => {
+                    self.next();
+                }
+            }
+        }
+        document
+    }
+
+    /// Skips variable definitions and directives
+    fn skip_to_selection_set(&mut self) {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punctuator('{') => return,
+                Token::Punctuator('(') => self.skip_balanced('(', ')'),
+                _ => {
+                    self.next();
+                }
+            }
+        }
+    }
+
+    fn selection_set(&mut self) -> Vec<Selection> {
+        let mut selections = vec![];
+        if !self.is_next('{') {
+            return selections;
+        }
+        self.next();
+
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punctuator('}') => {
+                    self.next();
+                    break;
+                }
+                Token::Spread => {
+                    self.next();
+                    match self.peek().cloned() {
+                        Some(Token::Name(ref on)) if on == "on" => {
+                            self.next();
+                            let type_condition = self.name();
+                            self.skip_directives();
+                            let fragment_selections = self.selection_set();
+                            selections.push(Selection::InlineFragment {
+                                type_condition,
+                                selections: fragment_selections,
+                            });
+                        }
+                        Some(Token::Name(name)) => {
+                            self.next();
+                            self.skip_directives();
+                            selections.push(Selection::FragmentSpread(name));
+                        }
+                        _ => {
+                            self.skip_directives();
+                            let fragment_selections = self.selection_set();
+                            selections.push(Selection::InlineFragment {
+                                type_condition: None,
+                                selections: fragment_selections,
+                            });
+                        }
+                    }
+                }
+                Token::Name(_) => {
+                    let mut name = self.name().unwrap_or_default();
+                    if self.is_next(':') {
+                        self.next();
+                        name = self.name().unwrap_or_default();
+                    }
+                    let args = self.arguments();
+                    self.skip_directives();
+                    let field_selections = if self.is_next('{') { self.selection_set() } else { vec![] };
+                    selections.push(Selection::Field {
+                        name,
+                        args,
+                        selections: field_selections,
+                    });
+                }
+                _ => {
+                    self.next();
+                }
+            }
+        }
+        selections
+    }
+
+    fn arguments(&mut self) -> Vec<String> {
+        let mut args = vec![];
+        if !self.is_next('(') {
+            return args;
+        }
+        self.next();
+
+        while let Some(token) = self.next() {
+            match token {
+                Token::Punctuator(')') => break,
+                Token::Name(name) => {
+                    if self.is_next(':') {
+                        self.next();
+                    }
+                    self.skip_value();
+                    args.push(name);
+                }
+                _ => {}
+            }
+        }
+        args
+    }
+
+    fn skip_value(&mut self) {
+        match self.peek().cloned() {
+            Some(Token::Punctuator('[')) => self.skip_balanced('[', ']'),
+            Some(Token::Punctuator('{')) => self.skip_balanced('{', '}'),
+            Some(Token::Punctuator('$')) => {
+                self.next();
+                self.next();
+            }
+            Some(Token::Punctuator(')')) | None => {}
+            Some(_) => {
+                self.next();
+            }
+        }
+    }
+
+    fn skip_directives(&mut self) {
+        while self.is_next('@') {
+            self.next();
+            self.next();
+            if self.is_next('(') {
+                self.skip_balanced('(', ')');
+            }
+        }
+    }
+
+    fn skip_balanced(&mut self, open: char, close: char) {
+        let mut depth = 0usize;
+        while let Some(token) = self.next() {
+            if token == Token::Punctuator(open) {
+                depth += 1;
+            } else if token == Token::Punctuator(close) {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Whether document selects `__schema` or `__type`, `__typename` is not an introspection
+pub fn is_introspection(query: &str) -> bool {
+    tokenize(query).iter().any(|token| match *token {
+        Token::Name(ref name) => name == "__schema" || name == "__type",
+        _ => false,
+    })
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            '"' => {
+                let mut quotes = 1;
+                while quotes < 3 && chars.peek() == Some(&'"') {
+                    chars.next();
+                    quotes += 1;
+                }
+                match quotes {
+                    // empty string
+                    2 => {}
+                    // block string
+                    3 => {
+                        let mut closing = 0;
+                        while let Some(next) = chars.next() {
+                            match next {
+                                '\\' if closing == 0 => {
+                                    chars.next();
+                                }
+                                '"' => {
+                                    closing += 1;
+                                    if closing == 3 {
+                                        break;
+                                    }
+                                }
+                                _ => closing = 0,
+                            }
+                        }
+                    }
+                    _ => {
+                        while let Some(next) = chars.next() {
+                            match next {
+                                '\\' => {
+                                    chars.next();
+                                }
+                                '"' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                tokens.push(Token::Literal);
+            }
+            '.' => {
+                if chars.peek() == Some(&'.') {
+                    chars.next();
+                    chars.next();
+                    tokens.push(Token::Spread);
+                }
+            }
+            _ if c == '-' || c.is_ascii_digit() => {
+                while let Some(&next) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || next == '.' || next == '+' || next == '-' {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Literal);
+            }
+            _ if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next == '_' || next.is_ascii_alphanumeric() {
+                        name.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Name(name));
+            }
+            _ if c.is_whitespace() || c == ',' => {}
+            _ => tokens.push(Token::Punctuator(c)),
+        }
+    }
+
+    tokens
+}
@@ -0,0 +1,414 @@
+//! Schema export without starting servers: introspection result, SDL and breaking changes between two schemas
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use failure::Error as FailureError;
+use juniper::http::GraphQLRequest;
+use serde_json::{self, Value};
+use tokio_core::reactor::Core;
+
+use config::Config;
+use controller::client_info::ClientInfo;
+use controller::cookies::ResponseCookies;
+use graphql::analytics::OperationAnalytics;
+use graphql::context::Context;
+use graphql::deprecation::DeprecationTracker;
+use graphql::resilience::LastKnownGood;
+use graphql::schema;
+use graphql::session_cache::ActiveSessions;
+use upstream::{CanaryRouting, CircuitBreakers, Deadline, HttpClients};
+
+/// Same query as graphql-js `introspectionQuery`, so the result can be consumed by relay compiler
+pub const INTROSPECTION_QUERY: &str = "
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives { name description locations args { ...InputValue } }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } } }
+}
+";
+
+const BUILT_IN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+/// Runs introspection query against `schema::create()`, types are sorted by name to keep output stable
+pub fn introspect(config: Config) -> Result<Value, FailureError> {
+    let core = Core::new()?;
+    let handle = core.handle();
+    let context = Context::new(
+        HttpClients::new(&config, &handle),
+        None,
+        None,
+        None,
+        None,
+        config.clone(),
+        None,
+        ResponseCookies::default(),
+        ClientInfo::default(),
+        Arc::new(CircuitBreakers::new(&config)),
+        Deadline::new(Duration::from_millis(config.gateway.operation_timeout_ms)),
+        CanaryRouting::default(),
+        Arc::new(LastKnownGood::new(config.resilience.as_ref())),
+        Arc::new(DeprecationTracker::default()),
+        Arc::new(OperationAnalytics::default()),
+        Arc::new(ActiveSessions::default()),
+    );
+
+    let request = GraphQLRequest::new(INTROSPECTION_QUERY.to_string(), None, None);
+    let response = request.execute(&schema::create(), &context);
+    let mut result = serde_json::to_value(&response)?;
+    if !response.is_ok() || !result["errors"].is_null() {
+        return Err(format_err!("Introspection failed: {}", result["errors"]));
+    }
+
+    if let Some(types) = result["data"]["__schema"]["types"].as_array_mut() {
+        types.sort_by(|a, b| type_name(a).cmp(type_name(b)));
+    }
+    Ok(result)
+}
+
+/// Accepts both full response `{ "data": { "__schema": .. } }` and bare `{ "__schema": .. }`
+pub fn schema_of(introspection: &Value) -> Result<&Value, FailureError> {
+    let schema = match introspection.get("data") {
+        Some(data) => &data["__schema"],
+        None => &introspection["__schema"],
+    };
+    if schema.is_object() {
+        Ok(schema)
+    } else {
+        Err(format_err!("Not an introspection result, __schema is missing"))
+    }
+}
+
+/// Prints schema in GraphQL schema definition language
+pub fn print_sdl(introspection: &Value) -> Result<String, FailureError> {
+    let schema = schema_of(introspection)?;
+    let mut blocks = vec![];
+
+    let query = schema["queryType"]["name"].as_str();
+    let mutation = schema["mutationType"]["name"].as_str();
+    if query != Some("Query") || mutation.map_or(false, |name| name != "Mutation") {
+        let mut block = "schema {\n".to_string();
+        if let Some(query) = query {
+            block += &format!("  query: {}\n", query);
+        }
+        if let Some(mutation) = mutation {
+            block += &format!("  mutation: {}\n", mutation);
+        }
+        blocks.push(block + "}");
+    }
+
+    let mut types = user_types(schema);
+    types.sort_by(|a, b| type_name(a).cmp(type_name(b)));
+    for type_ in types {
+        blocks.push(print_type(type_));
+    }
+
+    Ok(blocks.join("\n\n") + "\n")
+}
+
+/// Lists changes of `new` schema which can break clients written against `old` one
+pub fn breaking_changes(old: &Value, new: &Value) -> Result<Vec<String>, FailureError> {
+    let old_types = types_by_name(schema_of(old)?);
+    let new_types = types_by_name(schema_of(new)?);
+    let mut changes = vec![];
+
+    for (name, old_type) in &old_types {
+        let new_type = match new_types.get(name) {
+            Some(new_type) => new_type,
+            None => {
+                changes.push(format!("Type {} was removed", name));
+                continue;
+            }
+        };
+        let (old_kind, new_kind) = (old_type["kind"].as_str(), new_type["kind"].as_str());
+        if old_kind != new_kind {
+            changes.push(format!(
+                "Type {} changed kind from {} to {}",
+                name,
+                old_kind.unwrap_or_default(),
+                new_kind.unwrap_or_default()
+            ));
+            continue;
+        }
+        match old_kind {
+            Some("OBJECT") | Some("INTERFACE") => {
+                fields_changes(name, old_type, new_type, &mut changes);
+                removed_names(name, "interface", &old_type["interfaces"], &new_type["interfaces"], &mut changes);
+            }
+            Some("INPUT_OBJECT") => input_fields_changes(name, old_type, new_type, &mut changes),
+            Some("ENUM") => removed_names(name, "value", &old_type["enumValues"], &new_type["enumValues"], &mut changes),
+            Some("UNION") => removed_names(name, "member", &old_type["possibleTypes"], &new_type["possibleTypes"], &mut changes),
+            _ => {}
+        }
+    }
+
+    changes.sort();
+    Ok(changes)
+}
+
+fn type_name(value: &Value) -> &str {
+    value["name"].as_str().unwrap_or_default()
+}
+
+fn items(value: &Value) -> &[Value] {
+    value.as_array().map(|items| items.as_slice()).unwrap_or(&[])
+}
+
+fn user_types(schema: &Value) -> Vec<&Value> {
+    items(&schema["types"])
+        .iter()
+        .filter(|type_| {
+            let name = type_name(type_);
+            !name.starts_with("__") && !BUILT_IN_SCALARS.contains(&name)
+        })
+        .collect()
+}
+
+fn types_by_name(schema: &Value) -> HashMap<&str, &Value> {
+    user_types(schema).into_iter().map(|type_| (type_name(type_), type_)).collect()
+}
+
+fn print_type(type_: &Value) -> String {
+    let name = type_name(type_);
+    let definition = match type_["kind"].as_str() {
+        Some("SCALAR") => format!("scalar {}", name),
+        Some("OBJECT") => {
+            let interfaces = items(&type_["interfaces"]).iter().map(type_name).collect::<Vec<_>>();
+            let implements = if interfaces.is_empty() {
+                String::new()
+            } else {
+                format!(" implements {}", interfaces.join(" & "))
+            };
+            format!("type {}{} {}", name, implements, print_fields(type_))
+        }
+        Some("INTERFACE") => format!("interface {} {}", name, print_fields(type_)),
+        Some("UNION") => {
+            let members = items(&type_["possibleTypes"]).iter().map(type_name).collect::<Vec<_>>();
+            format!("union {} = {}", name, members.join(" | "))
+        }
+        Some("ENUM") => {
+            let values = items(&type_["enumValues"])
+                .iter()
+                .map(|value| {
+                    format!(
+                        "{}  {}{}",
+                        print_description(value, "  "),
+                        type_name(value),
+                        print_deprecated(value)
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("enum {} {{\n{}\n}}", name, values.join("\n"))
+        }
+        Some("INPUT_OBJECT") => {
+            let fields = items(&type_["inputFields"])
+                .iter()
+                .map(|field| format!("{}  {}", print_description(field, "  "), print_input_value(field)))
+                .collect::<Vec<_>>();
+            format!("input {} {{\n{}\n}}", name, fields.join("\n"))
+        }
+        other => format!("# {} of unknown kind {}", name, other.unwrap_or_default()),
+    };
+    print_description(type_, "") + &definition
+}
+
+fn print_fields(type_: &Value) -> String {
+    let fields = items(&type_["fields"])
+        .iter()
+        .map(|field| {
+            let args = items(&field["args"]).iter().map(print_input_value).collect::<Vec<_>>();
+            let args = if args.is_empty() {
+                String::new()
+            } else {
+                format!("({})", args.join(", "))
+            };
+            format!(
+                "{}  {}{}: {}{}",
+                print_description(field, "  "),
+                type_name(field),
+                args,
+                print_type_ref(&field["type"]),
+                print_deprecated(field)
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("{{\n{}\n}}", fields.join("\n"))
+}
+
+fn print_input_value(value: &Value) -> String {
+    let default = match value["defaultValue"].as_str() {
+        Some(default) => format!(" = {}", default),
+        None => String::new(),
+    };
+    format!("{}: {}{}", type_name(value), print_type_ref(&value["type"]), default)
+}
+
+fn print_type_ref(type_ref: &Value) -> String {
+    match type_ref["kind"].as_str() {
+        Some("NON_NULL") => format!("{}!", print_type_ref(&type_ref["ofType"])),
+        Some("LIST") => format!("[{}]", print_type_ref(&type_ref["ofType"])),
+        _ => type_name(type_ref).to_string(),
+    }
+}
+
+/// Description lines to be put before the definition, indented the same
+fn print_description(value: &Value, indent: &str) -> String {
+    match value["description"].as_str() {
+        Some(description) if !description.is_empty() => {
+            let description = description.replace("\"\"\"", "\\\"\"\"");
+            if description.contains('\n') {
+                format!("{}\"\"\"\n{}\n{}\"\"\"\n", indent, description, indent)
+            } else {
+                format!("{}\"\"\"{}\"\"\"\n", indent, description)
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+fn print_deprecated(value: &Value) -> String {
+    if value["isDeprecated"].as_bool() != Some(true) {
+        return String::new();
+    }
+    match value["deprecationReason"].as_str() {
+        Some(reason) => format!(" @deprecated(reason: {})", Value::String(reason.to_string())),
+        None => " @deprecated".to_string(),
+    }
+}
+
+fn fields_changes(type_name_: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    let new_fields = items(&new["fields"])
+        .iter()
+        .map(|field| (type_name(field), field))
+        .collect::<HashMap<_, _>>();
+    for old_field in items(&old["fields"]) {
+        let name = type_name(old_field);
+        let new_field = match new_fields.get(name) {
+            Some(new_field) => new_field,
+            None => {
+                changes.push(format!("Field {}.{} was removed", type_name_, name));
+                continue;
+            }
+        };
+        if !is_safe_output_change(&old_field["type"], &new_field["type"]) {
+            changes.push(format!(
+                "Field {}.{} changed type from {} to {}",
+                type_name_,
+                name,
+                print_type_ref(&old_field["type"]),
+                print_type_ref(&new_field["type"])
+            ));
+        }
+        let field = format!("{}.{}", type_name_, name);
+        input_values_changes(&field, "argument", &old_field["args"], &new_field["args"], changes);
+    }
+}
+
+fn input_fields_changes(type_name_: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    input_values_changes(type_name_, "input field", &old["inputFields"], &new["inputFields"], changes);
+}
+
+fn input_values_changes(owner: &str, what: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    let old_values = items(old).iter().map(|value| (type_name(value), value)).collect::<HashMap<_, _>>();
+    let new_values = items(new).iter().map(|value| (type_name(value), value)).collect::<HashMap<_, _>>();
+
+    for (name, old_value) in &old_values {
+        match new_values.get(name) {
+            None => changes.push(format!("{} {} of {} was removed", capitalize(what), name, owner)),
+            Some(new_value) => {
+                if !is_safe_input_change(&old_value["type"], &new_value["type"]) {
+                    changes.push(format!(
+                        "{} {} of {} changed type from {} to {}",
+                        capitalize(what),
+                        name,
+                        owner,
+                        print_type_ref(&old_value["type"]),
+                        print_type_ref(&new_value["type"])
+                    ));
+                }
+            }
+        }
+    }
+
+    for (name, new_value) in &new_values {
+        let required = new_value["type"]["kind"].as_str() == Some("NON_NULL") && new_value["defaultValue"].is_null();
+        if required && !old_values.contains_key(name) {
+            changes.push(format!("Required {} {} was added to {}", what, name, owner));
+        }
+    }
+}
+
+fn removed_names(type_name_: &str, what: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    let new_names = items(new).iter().map(type_name).collect::<Vec<_>>();
+    for name in items(old).iter().map(type_name) {
+        if !new_names.contains(&name) {
+            changes.push(format!("{} {} was removed from {}", capitalize(what), name, type_name_));
+        }
+    }
+}
+
+/// Output type may only become stricter
+fn is_safe_output_change(old: &Value, new: &Value) -> bool {
+    match (old["kind"].as_str(), new["kind"].as_str()) {
+        (Some("NON_NULL"), Some("NON_NULL")) => is_safe_output_change(&old["ofType"], &new["ofType"]),
+        (Some("NON_NULL"), _) => false,
+        (_, Some("NON_NULL")) => is_safe_output_change(old, &new["ofType"]),
+        (Some("LIST"), Some("LIST")) => is_safe_output_change(&old["ofType"], &new["ofType"]),
+        (old_kind, new_kind) => old_kind == new_kind && type_name(old) == type_name(new),
+    }
+}
+
+/// Input type may only become looser
+fn is_safe_input_change(old: &Value, new: &Value) -> bool {
+    match (old["kind"].as_str(), new["kind"].as_str()) {
+        (Some("NON_NULL"), Some("NON_NULL")) => is_safe_input_change(&old["ofType"], &new["ofType"]),
+        (Some("NON_NULL"), _) => is_safe_input_change(&old["ofType"], new),
+        (_, Some("NON_NULL")) => false,
+        (Some("LIST"), Some("LIST")) => is_safe_input_change(&old["ofType"], &new["ofType"]),
+        (old_kind, new_kind) => old_kind == new_kind && type_name(old) == type_name(new),
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
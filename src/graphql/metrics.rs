@@ -0,0 +1,26 @@
+//! Helpers shared by statistics exposed in Prometheus text format
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Keys come from requests, so the number of label values kept in memory is bounded
+pub const MAX_KEYS: usize = 1000;
+/// Label value of every key beyond `MAX_KEYS`
+pub const OTHER: &str = "other";
+
+/// Returns `key` if it is already counted or there is room for it, otherwise the key made by `other`
+pub fn bounded_key<K, V, F>(map: &HashMap<K, V>, key: K, other: F) -> K
+where
+    K: Eq + Hash,
+    F: FnOnce() -> K,
+{
+    if map.contains_key(&key) || map.len() < MAX_KEYS {
+        key
+    } else {
+        other()
+    }
+}
+
+pub fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
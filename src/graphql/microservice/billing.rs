@@ -4,7 +4,7 @@ use juniper::FieldResult;
 
 use stq_routes::model::Model;
 use stq_routes::service::Service;
-use stq_types::{InvoiceId, OrderId, StoreId, SubscriptionPaymentId};
+use stq_types::{InvoiceId, OrderId, PayoutId, StoreId, SubscriptionPaymentId};
 
 use graphql::context::Context;
 use graphql::microservice::requests::*;
@@ -58,6 +58,8 @@ pub trait BillingService {
 
     fn get_payouts_by_store_id(&self, store_id: StoreId) -> FieldResult<PayoutsByStoreId>;
 
+    fn get_payout(&self, payout_id: PayoutId) -> FieldResult<Option<Payout>>;
+
     fn pay_out_to_seller(&self, input: PayOutToSellerPayload) -> FieldResult<Payout>;
 
     fn get_balance_by_store_id(&self, store_id: StoreId) -> FieldResult<Balances>;
@@ -259,6 +261,12 @@ impl<'ctx> BillingService for BillingServiceImpl<'ctx> {
         self.context.request(Method::Get, url, None).wait()
     }
 
+    fn get_payout(&self, payout_id: PayoutId) -> FieldResult<Option<Payout>> {
+        let request_path = format!("payouts/{}", payout_id.inner());
+        let url = self.request_url(&request_path);
+        self.context.request(Method::Get, url, None).wait()
+    }
+
     fn pay_out_to_seller(&self, input: PayOutToSellerPayload) -> FieldResult<Payout> {
         let request_path = "payouts";
         let url = self.request_url(&request_path);
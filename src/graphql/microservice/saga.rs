@@ -94,7 +94,7 @@ impl<'ctx> SagaService for SagaServiceImpl<'ctx> {
 
     fn update_base_product(&self, input: UpdateBaseProductInput) -> FieldResult<BaseProduct> {
         let identifier = ID::from_str(&*input.id)?;
-        let base_product_id = BaseProductId(identifier.raw_id);
+        let base_product_id = BaseProductId(identifier.raw_id.int()?);
         let request_path = format!("{}/{}/update", Model::BaseProduct.to_url(), base_product_id);
         let url = self.request_url(&request_path);
 
@@ -1,4 +1,11 @@
+pub mod analytics;
 pub mod context;
+pub mod deprecation;
+pub mod document;
+pub mod introspection;
+pub mod metrics;
 pub mod microservice;
 pub mod models;
+pub mod resilience;
 pub mod schema;
+pub mod session_cache;
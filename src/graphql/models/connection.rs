@@ -1,5 +1,233 @@
+use std::cmp;
+use std::fmt;
+use std::str::FromStr;
+
+use base64::{decode, encode};
+use futures::Future;
+use hyper::Method;
+use juniper::{self, FieldError, FieldResult};
+
 use super::*;
-use juniper;
+use graphql::context::Context;
+
+/// Position of an edge, given to clients as an opaque base64 cursor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cursor {
+    /// Index of the edge in the whole list
+    Offset(i32),
+    /// Raw id of the node, for lists paged by id
+    Key(i32),
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let raw = match *self {
+            Cursor::Offset(offset) => format!("cursor|offset|{}", offset),
+            Cursor::Key(key) => format!("cursor|key|{}", key),
+        };
+        write!(f, "{}", encode(&*raw))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = FieldError;
+
+    fn from_str(cursor: &str) -> Result<Self, Self::Err> {
+        let raw = decode(cursor).ok().and_then(|raw| String::from_utf8(raw).ok()).unwrap_or_default();
+        let v: Vec<&str> = raw.split('|').collect();
+        if v.len() == 3 && v[0] == "cursor" {
+            match (v[1], v[2].parse::<i32>()) {
+                ("offset", Ok(offset)) if offset >= 0 => return Ok(Cursor::Offset(offset)),
+                ("key", Ok(key)) => return Ok(Cursor::Key(key)),
+                _ => {}
+            }
+        }
+
+        // cursors given out before they became opaque: plain offsets and global ids of nodes
+        if let Ok(offset) = cursor.parse::<i32>() {
+            if offset >= 0 {
+                return Ok(Cursor::Offset(offset));
+            }
+        }
+        ID::from_str(cursor)
+            .and_then(|id| id.raw_id.int())
+            .map(Cursor::Key)
+            .map_err(|_| invalid_cursor(cursor))
+    }
+}
+
+impl From<Cursor> for juniper::ID {
+    fn from(cursor: Cursor) -> Self {
+        juniper::ID::from(cursor.to_string())
+    }
+}
+
+fn invalid_cursor(cursor: &str) -> FieldError {
+    FieldError::new(
+        "Cursor parsing error",
+        graphql_value!({ "code": 300, "details": { format!("Invalid cursor {}", cursor) }}),
+    )
+}
+
+fn unsupported_arguments(details: &str) -> FieldError {
+    FieldError::new("Pagination error", graphql_value!({ "code": 300, "details": { details }}))
+}
+
+/// Relay pagination arguments: `first` and `after` page forward, `last` and `before` page backward
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionArgs {
+    pub first: Option<i32>,
+    pub after: Option<juniper::ID>,
+    pub last: Option<i32>,
+    pub before: Option<juniper::ID>,
+}
+
+impl ConnectionArgs {
+    pub fn new(first: Option<i32>, after: Option<juniper::ID>, last: Option<i32>, before: Option<juniper::ID>) -> Self {
+        Self {
+            first,
+            after,
+            last,
+            before,
+        }
+    }
+
+    pub fn forward(first: Option<i32>, after: Option<juniper::ID>) -> Self {
+        Self::new(first, after, None, None)
+    }
+
+    fn offset(cursor: &Option<juniper::ID>) -> FieldResult<Option<i32>> {
+        match *cursor {
+            Some(ref cursor) => match Cursor::from_str(cursor)? {
+                Cursor::Offset(offset) => Ok(Some(offset)),
+                Cursor::Key(_) => Err(invalid_cursor(cursor)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Window of a list paged by offset. Going forward one record more than the page size
+    /// is requested to find out if there is a next page.
+    pub fn offset_window(&self, records_limit: i32) -> FieldResult<OffsetWindow> {
+        let lower = Self::offset(&self.after)?.map(|after| after + 1).unwrap_or_default();
+        let before = Self::offset(&self.before)?;
+
+        match (self.first, self.last) {
+            (Some(_), Some(_)) => Err(unsupported_arguments("Use either first or last")),
+            (_, Some(last)) => {
+                let upper = before.ok_or_else(|| unsupported_arguments("last requires before cursor for this connection"))?;
+                let upper = cmp::max(upper, lower);
+                let offset = cmp::max(lower, upper - page_size(Some(last), records_limit));
+                Ok(OffsetWindow {
+                    offset,
+                    count: upper - offset,
+                    page_size: upper - offset,
+                    backward: true,
+                })
+            }
+            (first, None) => {
+                let mut size = page_size(first, records_limit);
+                if let Some(before) = before {
+                    size = cmp::min(size, cmp::max(0, before - lower));
+                }
+                Ok(OffsetWindow {
+                    offset: lower,
+                    count: size + 1,
+                    page_size: size,
+                    backward: false,
+                })
+            }
+        }
+    }
+
+    /// Pages a list that is already fetched as a whole
+    pub fn slice<T>(&self, nodes: Vec<T>, records_limit: i32) -> FieldResult<Connection<T, PageInfo>> {
+        let total = nodes.len() as i32;
+        let args = if self.last.is_some() && self.before.is_none() {
+            ConnectionArgs {
+                before: Some(Cursor::Offset(total).into()),
+                ..self.clone()
+            }
+        } else {
+            self.clone()
+        };
+        let window = args.offset_window(records_limit)?;
+
+        let nodes = nodes.into_iter().skip(window.offset as usize).take(window.count as usize).collect();
+        let mut connection = window.connection(nodes).with_total_count(TotalCount::Known(total));
+        connection.page_info.has_next_page = window.offset + (connection.edges.len() as i32) < total;
+        Ok(connection)
+    }
+
+    /// Window of a list paged by id of the last seen node, such lists can be paged only forward
+    pub fn key_window(&self, records_limit: i32) -> FieldResult<KeyWindow> {
+        if self.last.is_some() || self.before.is_some() {
+            return Err(unsupported_arguments("last and before are not supported by this connection"));
+        }
+        let after = match self.after {
+            Some(ref after) => match Cursor::from_str(after)? {
+                Cursor::Key(key) => Some(key),
+                Cursor::Offset(_) => return Err(invalid_cursor(after)),
+            },
+            None => None,
+        };
+        let size = page_size(self.first, records_limit);
+        Ok(KeyWindow {
+            after,
+            count: size + 1,
+            page_size: size,
+        })
+    }
+}
+
+fn page_size(size: Option<i32>, records_limit: i32) -> i32 {
+    cmp::max(0, cmp::min(size.unwrap_or(records_limit), records_limit))
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OffsetWindow {
+    /// Offset of the first record to request
+    pub offset: i32,
+    /// Number of records to request
+    pub count: i32,
+    page_size: i32,
+    backward: bool,
+}
+
+impl OffsetWindow {
+    /// Builds connection from records requested with `offset` and `count`
+    pub fn connection<T>(&self, mut nodes: Vec<T>) -> Connection<T, PageInfo> {
+        // going backward the page always ends right before an existing record
+        let has_next_page = self.backward || nodes.len() as i32 > self.page_size;
+        nodes.truncate(self.page_size as usize);
+        Connection::from_edges(Edge::create_vec(nodes, self.offset), has_next_page, self.offset > 0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct KeyWindow {
+    /// Raw id of the last seen node
+    pub after: Option<i32>,
+    /// Number of records to request
+    pub count: i32,
+    page_size: i32,
+}
+
+impl KeyWindow {
+    /// Builds connection from records requested with `after` and `count`, `key` gives raw id of a node
+    pub fn connection<T, F>(&self, mut nodes: Vec<T>, key: F) -> Connection<T, PageInfo>
+    where
+        F: Fn(&T) -> i32,
+    {
+        let has_next_page = nodes.len() as i32 > self.page_size;
+        nodes.truncate(self.page_size as usize);
+        let edges = nodes
+            .into_iter()
+            .map(|node| Edge::new(Cursor::Key(key(&node)).into(), node))
+            .collect();
+        Connection::from_edges(edges, has_next_page, self.after.is_some())
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Edge<T> {
@@ -12,10 +240,11 @@ impl<T> Edge<T> {
         Self { cursor, node }
     }
 
+    /// Edges of the records starting at `offset` of the whole list
     pub fn create_vec(vec: Vec<T>, offset: i32) -> Vec<Edge<T>> {
         vec.into_iter()
             .enumerate()
-            .map(|(i, item)| Edge::new(juniper::ID::from((i as i32 + offset).to_string()), item))
+            .map(|(i, item)| Edge::new(Cursor::Offset(i as i32 + offset).into(), item))
             .collect()
     }
 }
@@ -44,6 +273,18 @@ pub struct PageInfoStoresSearch {
     pub search_filters: StoresSearchFilters,
 }
 
+impl PageInfoStoresSearch {
+    pub fn new(page_info: PageInfo, search_filters: StoresSearchFilters) -> Self {
+        Self {
+            has_next_page: page_info.has_next_page,
+            has_previous_page: page_info.has_previous_page,
+            start_cursor: page_info.start_cursor,
+            end_cursor: page_info.end_cursor,
+            search_filters,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PageInfoProductsSearch {
     pub has_next_page: bool,
@@ -53,14 +294,106 @@ pub struct PageInfoProductsSearch {
     pub end_cursor: Option<juniper::ID>,
 }
 
+impl PageInfoProductsSearch {
+    pub fn new(page_info: PageInfo, search_filters: Option<ProductsSearchFilters>) -> Self {
+        Self {
+            has_next_page: page_info.has_next_page,
+            has_previous_page: page_info.has_previous_page,
+            search_filters,
+            start_cursor: page_info.start_cursor,
+            end_cursor: page_info.end_cursor,
+        }
+    }
+}
+
+/// Source of the total number of nodes in a connection, requested only when `totalCount` is selected
+#[derive(Clone, Debug)]
+pub enum TotalCount {
+    /// Already known, e.g. from a search response
+    Known(i32),
+    /// Count endpoint of a microservice
+    Request { method: Method, url: String, body: Option<String> },
+}
+
+impl TotalCount {
+    pub fn get(url: String) -> Self {
+        TotalCount::Request {
+            method: Method::Get,
+            url,
+            body: None,
+        }
+    }
+
+    pub fn post(url: String, body: String) -> Self {
+        TotalCount::Request {
+            method: Method::Post,
+            url,
+            body: Some(body),
+        }
+    }
+
+    pub fn resolve(&self, context: &Context) -> FieldResult<i32> {
+        match *self {
+            TotalCount::Known(count) => Ok(count),
+            TotalCount::Request {
+                ref method,
+                ref url,
+                ref body,
+            } => context.request::<i32>(method.clone(), url.clone(), body.clone()).wait(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Connection<T, P> {
     pub edges: Vec<Edge<T>>,
     pub page_info: P,
+    pub total_count: Option<TotalCount>,
 }
 
 impl<T, P> Connection<T, P> {
     pub fn new(edges: Vec<Edge<T>>, page_info: P) -> Self {
-        Self { edges, page_info }
+        Self {
+            edges,
+            page_info,
+            total_count: None,
+        }
+    }
+
+    pub fn with_total_count(mut self, total_count: TotalCount) -> Self {
+        self.total_count = Some(total_count);
+        self
+    }
+
+    /// Total number of nodes, `None` if the connection has no way to count them
+    pub fn resolve_total_count(&self, context: &Context) -> FieldResult<Option<i32>> {
+        match self.total_count {
+            Some(ref total_count) => total_count.resolve(context).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> Connection<T, PageInfo> {
+    pub fn from_edges(edges: Vec<Edge<T>>, has_next_page: bool, has_previous_page: bool) -> Self {
+        let page_info = PageInfo {
+            has_next_page,
+            has_previous_page,
+            start_cursor: edges.first().map(|e| e.cursor.clone()),
+            end_cursor: edges.last().map(|e| e.cursor.clone()),
+        };
+        Self::new(edges, page_info)
+    }
+
+    /// Replaces page info with an extended one, e.g. with search filters
+    pub fn with_page_info<P, F>(self, f: F) -> Connection<T, P>
+    where
+        F: FnOnce(PageInfo) -> P,
+    {
+        Connection {
+            edges: self.edges,
+            page_info: f(self.page_info),
+            total_count: self.total_count,
+        }
     }
 }
@@ -1,11 +1,12 @@
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
-use chrono::prelude::*;
 use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
 
 use stq_types::{BaseProductId, CouponCode, CouponId, StoreId};
 
+use super::GraphQLDateTime;
+
 /// Payload for coupon
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Coupon {
@@ -70,7 +71,7 @@ pub struct NewCouponInput {
     #[graphql(description = "quantity")]
     pub quantity: i32,
     #[graphql(description = "expired at")]
-    pub expired_at: Option<DateTime<Utc>>,
+    pub expired_at: Option<GraphQLDateTime>,
 }
 
 /// Input Object for updating coupon
@@ -88,7 +89,7 @@ pub struct UpdateCouponInput {
     #[graphql(description = "quantity")]
     pub quantity: Option<i32>,
     #[graphql(description = "quantity")]
-    pub expired_at: Option<DateTime<Utc>>,
+    pub expired_at: Option<GraphQLDateTime>,
     #[graphql(description = "is active")]
     pub is_active: Option<bool>,
 }
@@ -162,7 +163,7 @@ impl From<NewCouponInput> for NewCoupon {
             scope: input.scope,
             percent: input.percent,
             quantity: input.quantity,
-            expired_at: input.expired_at.map(into_system_time),
+            expired_at: input.expired_at.map(SystemTime::from),
         }
     }
 }
@@ -172,16 +173,12 @@ impl From<UpdateCouponInput> for UpdateCoupon {
         Self {
             percent: input.percent,
             quantity: input.quantity,
-            expired_at: input.expired_at.map(into_system_time),
+            expired_at: input.expired_at.map(SystemTime::from),
             is_active: input.is_active,
         }
     }
 }
 
-fn into_system_time(datetime: DateTime<Utc>) -> SystemTime {
-    SystemTime::UNIX_EPOCH + Duration::new(datetime.timestamp() as u64, 0)
-}
-
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CouponsSearchCodePayload {
     pub code: CouponCode,
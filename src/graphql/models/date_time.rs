@@ -0,0 +1,63 @@
+use std::time::SystemTime;
+
+use chrono::prelude::*;
+use juniper::Value;
+
+/// Point in time exposed as `DateTime` scalar.
+///
+/// Every timestamp is converted to UTC and written in RFC 3339 (ISO 8601) format, e.g. `2018-10-01T12:30:00Z`.
+/// Input is accepted with any offset and normalized to UTC, timestamps without offset are stored by microservices in UTC.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GraphQLDateTime(pub DateTime<Utc>);
+
+impl GraphQLDateTime {
+    pub fn from_timestamp(secs: i64) -> Self {
+        GraphQLDateTime(Utc.timestamp(secs, 0))
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|datetime| GraphQLDateTime(datetime.with_timezone(&Utc)))
+    }
+
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+}
+
+impl From<DateTime<Utc>> for GraphQLDateTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        GraphQLDateTime(datetime)
+    }
+}
+
+impl From<NaiveDateTime> for GraphQLDateTime {
+    fn from(datetime: NaiveDateTime) -> Self {
+        GraphQLDateTime(DateTime::from_utc(datetime, Utc))
+    }
+}
+
+impl From<SystemTime> for GraphQLDateTime {
+    fn from(time: SystemTime) -> Self {
+        GraphQLDateTime(time.into())
+    }
+}
+
+impl From<GraphQLDateTime> for SystemTime {
+    fn from(datetime: GraphQLDateTime) -> Self {
+        datetime.0.into()
+    }
+}
+
+graphql_scalar!(GraphQLDateTime as "DateTime" {
+    description: "Point in time in RFC 3339 (ISO 8601) format. Output is always in UTC, e.g. '2018-10-01T12:30:00Z', input may have any offset."
+
+    resolve(&self) -> Value {
+        Value::string(&self.to_rfc3339())
+    }
+
+    from_input_value(v: &InputValue) -> Option<GraphQLDateTime> {
+        v.as_string_value().and_then(GraphQLDateTime::parse)
+    }
+});
@@ -0,0 +1,10 @@
+#[derive(GraphQLObject, Clone, Debug, PartialEq)]
+#[graphql(description = "Number of operations a client used deprecated field or argument in")]
+pub struct DeprecatedFieldUsage {
+    #[graphql(description = "Field as `Type.field` or argument as `Type.field(argument)`")]
+    pub field: String,
+    #[graphql(description = "Client name from X-Client-Name header, `user:<id>` from JWT or `anonymous`")]
+    pub client: String,
+    #[graphql(description = "Number of operations since gateway start")]
+    pub count: i32,
+}
@@ -5,13 +5,166 @@ use base64::{decode, encode};
 use juniper::FieldError;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
+use uuid::Uuid;
 
 use config::Config;
 
+/// Key of a model inside its microservice
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawId {
+    Int(i32),
+    Uuid(Uuid),
+    /// Several keys identifying one model together, e.g. warehouse and product of a stock
+    Composite(Vec<RawId>),
+}
+
+impl RawId {
+    pub fn int(&self) -> Result<i32, FieldError> {
+        match *self {
+            RawId::Int(raw_id) => Ok(raw_id),
+            _ => Err(unexpected_key("integer", self)),
+        }
+    }
+
+    pub fn uuid(&self) -> Result<Uuid, FieldError> {
+        match *self {
+            RawId::Uuid(uuid) => Ok(uuid),
+            _ => Err(unexpected_key("uuid", self)),
+        }
+    }
+
+    pub fn composite(&self) -> Result<&[RawId], FieldError> {
+        match *self {
+            RawId::Composite(ref keys) => Ok(keys),
+            _ => Err(unexpected_key("composite", self)),
+        }
+    }
+}
+
+impl fmt::Display for RawId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RawId::Int(raw_id) => write!(f, "{}", raw_id),
+            RawId::Uuid(uuid) => write!(f, "{}", uuid),
+            RawId::Composite(ref keys) => {
+                let keys = keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", keys.join(":"))
+            }
+        }
+    }
+}
+
+impl FromStr for RawId {
+    type Err = FieldError;
+
+    fn from_str(raw_id: &str) -> Result<Self, Self::Err> {
+        if raw_id.contains(':') {
+            return raw_id
+                .split(':')
+                .map(parse_single_key)
+                .collect::<Result<Vec<_>, _>>()
+                .map(RawId::Composite);
+        }
+        parse_single_key(raw_id)
+    }
+}
+
+fn parse_single_key(raw_id: &str) -> Result<RawId, FieldError> {
+    if let Ok(raw_id) = raw_id.parse::<i32>() {
+        return Ok(RawId::Int(raw_id));
+    }
+    Uuid::parse_str(raw_id)
+        .map(RawId::Uuid)
+        .map_err(|err| FieldError::new("Id parsing error", graphql_value!({ "code": 300, "details": { err.to_string() }})))
+}
+
+fn unexpected_key(expected: &str, raw_id: &RawId) -> FieldError {
+    FieldError::new(
+        "Id parsing error",
+        graphql_value!({ "code": 300, "details": { format!("expected {} key, got {}", expected, raw_id) }}),
+    )
+}
+
+/// Models of gateway schema which microservice routes have no model for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GatewayModel {
+    Payout,
+    StoreSubscription,
+    Subscription,
+}
+
+impl GatewayModel {
+    const PAYOUT_STR: &'static str = "payout";
+    const STORE_SUBSCRIPTION_STR: &'static str = "store_subscription";
+    const SUBSCRIPTION_STR: &'static str = "subscription";
+
+    /// Route of the model in its microservice
+    pub fn to_url(&self) -> &'static str {
+        match *self {
+            GatewayModel::Payout => "payouts",
+            GatewayModel::StoreSubscription => "store_subscription/by-store-id",
+            GatewayModel::Subscription => "subscription",
+        }
+    }
+}
+
+impl fmt::Display for GatewayModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            GatewayModel::Payout => GatewayModel::PAYOUT_STR,
+            GatewayModel::StoreSubscription => GatewayModel::STORE_SUBSCRIPTION_STR,
+            GatewayModel::Subscription => GatewayModel::SUBSCRIPTION_STR,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for GatewayModel {
+    type Err = FieldError;
+
+    fn from_str(model: &str) -> Result<Self, Self::Err> {
+        match model {
+            GatewayModel::PAYOUT_STR => Ok(GatewayModel::Payout),
+            GatewayModel::STORE_SUBSCRIPTION_STR => Ok(GatewayModel::StoreSubscription),
+            GatewayModel::SUBSCRIPTION_STR => Ok(GatewayModel::Subscription),
+            other => Err(FieldError::new(
+                "Id parsing error",
+                graphql_value!({ "code": 300, "details": { format!("unknown model {}", other) }}),
+            )),
+        }
+    }
+}
+
+/// Model part of a global id
+pub enum IdModel {
+    /// Model of microservice routes
+    Route(Model),
+    /// Model known only to gateway
+    Gateway(GatewayModel),
+}
+
+impl IdModel {
+    pub fn to_url(&self) -> String {
+        match *self {
+            IdModel::Route(ref model) => model.to_url().to_string(),
+            IdModel::Gateway(ref model) => model.to_url().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for IdModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IdModel::Route(ref model) => write!(f, "{}", model),
+            IdModel::Gateway(ref model) => write!(f, "{}", model),
+        }
+    }
+}
+
 pub struct ID {
     pub service: Service,
-    pub model: Model,
-    pub raw_id: i32,
+    pub model: IdModel,
+    pub raw_id: RawId,
 }
 
 impl fmt::Display for ID {
@@ -39,20 +192,46 @@ impl FromStr for ID {
         }
 
         let service = Service::from_str(v[0])?;
-        let model = Model::from_str(v[1])?;
-        let raw_id = v[2]
-            .parse::<i32>()
-            .map_err(|err| FieldError::new("Id parsing error", graphql_value!({ "code": 300, "details": { err.to_string() }})))?;
-        Ok(ID::new(service, model, raw_id))
+        let model = match GatewayModel::from_str(v[1]) {
+            Ok(model) => IdModel::Gateway(model),
+            Err(_) => IdModel::Route(Model::from_str(v[1])?),
+        };
+        let raw_id = RawId::from_str(v[2])?;
+        Ok(ID { service, model, raw_id })
     }
 }
 
 impl ID {
     pub fn new(service: Service, model: Model, raw_id: i32) -> ID {
-        ID { service, model, raw_id }
+        ID::with_key(service, model, RawId::Int(raw_id))
+    }
+
+    pub fn with_uuid(service: Service, model: Model, uuid: Uuid) -> ID {
+        ID::with_key(service, model, RawId::Uuid(uuid))
+    }
+
+    pub fn with_key(service: Service, model: Model, raw_id: RawId) -> ID {
+        ID {
+            service,
+            model: IdModel::Route(model),
+            raw_id,
+        }
+    }
+
+    pub fn with_gateway_model(service: Service, model: GatewayModel, raw_id: RawId) -> ID {
+        ID {
+            service,
+            model: IdModel::Gateway(model),
+            raw_id,
+        }
     }
 
     pub fn url(self, config: &Config) -> String {
         format!("{}/{}/{}", config.service_url(self.service), self.model.to_url(), self.raw_id)
     }
 }
+
+/// Parses uuid given either raw or as a global id, raw uuids were the only ids of such models before
+pub fn parse_uuid(id: &str) -> Result<Uuid, FieldError> {
+    Uuid::parse_str(id).or_else(|_| ID::from_str(id).and_then(|id| id.raw_id.uuid()))
+}
@@ -2,8 +2,9 @@ use std::fmt;
 use std::fmt::Display;
 
 use juniper::Value;
+use uuid::Uuid;
 
-use graphql::models::{NewUserAdditionalData, NewUserAdditionalDataInput};
+use graphql::models::{NewUserAdditionalData, NewUserAdditionalDataInput, NewUserSession};
 use stq_static_resources::Provider;
 use stq_types::UserId;
 
@@ -40,6 +41,15 @@ pub enum UserStatus {
 pub struct ProviderOauth {
     pub token: String,
     pub additional_data: Option<NewUserAdditionalData>,
+    pub session: Option<NewUserSession>,
+}
+
+/// Payload for creating JWT token by email
+#[derive(Serialize, Clone, Debug)]
+pub struct EmailCredentials {
+    pub email: String,
+    pub password: String,
+    pub session: NewUserSession,
 }
 
 #[derive(GraphQLInputObject, Serialize, Deserialize, Debug, Clone)]
@@ -67,11 +77,42 @@ pub struct CreateJWTProviderInput {
     pub additional_data: Option<NewUserAdditionalDataInput>,
 }
 
+/// Lifetime of a token issued for impersonation
+pub const IMPERSONATION_JWT_TTL_SEC: i64 = 900;
+
+#[derive(GraphQLInputObject, Debug, Clone)]
+#[graphql(description = "Impersonate user input object")]
+pub struct ImpersonateUserInput {
+    #[graphql(description = "Client mutation id.")]
+    pub client_mutation_id: String,
+    #[graphql(description = "Raw id of a user to impersonate.")]
+    pub user_id: i32,
+}
+
+/// Payload for creating impersonation JWT token
+#[derive(Serialize, Clone, Debug)]
+pub struct ImpersonationPayload {
+    pub user_id: UserId,
+    pub ttl_sec: i64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct JWTPayload {
     pub user_id: UserId,
     pub exp: i64,
     pub provider: Provider,
+    /// Admin acting on behalf of `user_id` in impersonation context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<UserId>,
+    /// Device session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_session_id: Option<Uuid>,
+}
+
+impl JWTPayload {
+    pub fn is_impersonated(&self) -> bool {
+        self.actor.is_some()
+    }
 }
 
 impl Display for JWTPayload {
@@ -12,7 +12,9 @@ pub mod country;
 pub mod coupon;
 pub mod currency_exchange;
 pub mod custom_attribute;
+pub mod date_time;
 pub mod delivery;
+pub mod deprecated_field_usage;
 pub mod email_template;
 pub mod email_verify;
 pub mod fee;
@@ -20,6 +22,8 @@ pub mod id;
 pub mod invoice;
 pub mod jwt;
 pub mod moderator_comment;
+pub mod money;
+pub mod operation_statistics;
 pub mod order;
 pub mod order_billing;
 pub mod package;
@@ -36,6 +40,7 @@ pub mod subscription;
 pub mod user;
 pub mod user_delivery_address;
 pub mod user_role;
+pub mod user_session;
 pub mod visibility;
 pub mod warehouse;
 pub mod wizard_store;
@@ -54,14 +59,21 @@ pub use self::country::*;
 pub use self::coupon::*;
 pub use self::currency_exchange::*;
 pub use self::custom_attribute::*;
+pub use self::date_time::*;
 pub use self::delivery::*;
+pub use self::deprecated_field_usage::*;
 pub use self::email_template::*;
 pub use self::email_verify::*;
 pub use self::fee::*;
-pub use self::id::ID;
+pub use self::id::{parse_uuid, GatewayModel, IdModel, RawId, ID};
 pub use self::invoice::*;
-pub use self::jwt::{CreateJWTEmailInput, CreateJWTProviderInput, ProviderOauth, UserStatus, JWT};
+pub use self::jwt::{
+    CreateJWTEmailInput, CreateJWTProviderInput, EmailCredentials, ImpersonateUserInput, ImpersonationPayload, ProviderOauth, UserStatus,
+    IMPERSONATION_JWT_TTL_SEC, JWT,
+};
 pub use self::moderator_comment::*;
+pub use self::money::*;
+pub use self::operation_statistics::*;
 pub use self::order::*;
 pub use self::order_billing::*;
 pub use self::package::*;
@@ -78,6 +90,7 @@ pub use self::subscription::*;
 pub use self::user::*;
 pub use self::user_delivery_address::*;
 pub use self::user_role::*;
+pub use self::user_session::*;
 pub use self::visibility::*;
 pub use self::warehouse::*;
 pub use self::wizard_store::*;
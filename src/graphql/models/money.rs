@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use juniper::{FieldError, FieldResult};
+
+use stq_static_resources::{Currency, CurrencyType};
+
+/// Decimal places kept after currency conversion, enough for both fiat and crypto amounts
+pub const EXCHANGE_SCALE: i64 = 8;
+/// Decimal places of fiat amounts returned to clients
+pub const FIAT_SCALE: i64 = 2;
+
+/// Amount of money kept as decimal, so sums do not pick up float rounding errors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Money {
+    pub amount: BigDecimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: BigDecimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub fn from_f64(amount: f64, currency: Currency) -> FieldResult<Self> {
+        decimal(amount).map(|amount| Self::new(amount, currency))
+    }
+
+    /// Amount rounded half up to `currency_scale`, amounts with fewer decimal places are kept as is
+    pub fn rounded_amount(&self) -> BigDecimal {
+        let scale = currency_scale(self.currency);
+        let (_, amount_scale) = self.amount.as_bigint_and_exponent();
+        if amount_scale > scale {
+            round_with_scale(self.amount.clone(), scale)
+        } else {
+            self.amount.clone()
+        }
+    }
+}
+
+/// Decimal places of amounts in `currency`: cents for fiat, `EXCHANGE_SCALE` for crypto
+pub fn currency_scale(currency: Currency) -> i64 {
+    match currency.currency_type() {
+        CurrencyType::Fiat => FIAT_SCALE,
+        CurrencyType::Crypto => EXCHANGE_SCALE,
+    }
+}
+
+/// Converts a float amount given by a microservice to decimal using its shortest round-trip representation
+pub fn decimal(value: f64) -> FieldResult<BigDecimal> {
+    BigDecimal::from_str(&value.to_string()).map_err(|_| {
+        FieldError::new(
+            "Amount parsing error",
+            graphql_value!({ "code": 300, "details": { format!("{} is not a valid amount", value) }}),
+        )
+    })
+}
+
+/// Converts `amount` with a float exchange `rate`, keeping `EXCHANGE_SCALE` decimal places of the quotient
+pub fn exchange(amount: BigDecimal, rate: f64) -> FieldResult<BigDecimal> {
+    let rate = decimal(rate)?;
+    if rate == BigDecimal::default() {
+        return Err(FieldError::new(
+            "Currency exchange error",
+            graphql_value!({ "code": 300, "details": { "Exchange rate is zero." }}),
+        ));
+    }
+    Ok((amount / rate).with_scale(EXCHANGE_SCALE))
+}
+
+/// Rounds `amount` up to `scale` decimal places
+pub fn ceil_with_scale(amount: BigDecimal, scale: i64) -> BigDecimal {
+    let truncated = amount.with_scale(scale);
+    if truncated < amount {
+        truncated + BigDecimal::from(1i64) / BigDecimal::from(10i64.pow(scale as u32))
+    } else {
+        truncated
+    }
+}
+
+/// Rounds `amount` half away from zero to `scale` decimal places
+pub fn round_with_scale(amount: BigDecimal, scale: i64) -> BigDecimal {
+    let half = BigDecimal::from(5i64) / BigDecimal::from(10i64.pow(scale as u32 + 1));
+    if amount < BigDecimal::default() {
+        (amount - half).with_scale(scale)
+    } else {
+        (amount + half).with_scale(scale)
+    }
+}
+
+/// Value of deprecated float fields, decimal text always parses as f64
+pub fn decimal_to_f64(value: &BigDecimal) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0)
+}
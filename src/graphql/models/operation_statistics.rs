@@ -0,0 +1,27 @@
+#[derive(GraphQLObject, Clone, Debug, PartialEq)]
+#[graphql(description = "Statistics of operation by client since gateway start")]
+pub struct OperationStatistics {
+    #[graphql(description = "Operation name from request or `unknown`")]
+    pub operation_name: String,
+    #[graphql(description = "Client name from X-Client-Name header or `unknown`")]
+    pub client_name: String,
+    #[graphql(description = "Client version from X-Client-Version header or `unknown`")]
+    pub client_version: String,
+    #[graphql(description = "Number of operations")]
+    pub count: i32,
+    #[graphql(description = "Share of operations with errors in response, from 0 to 1")]
+    pub error_rate: f64,
+    #[graphql(description = "Median latency of latest operations in milliseconds")]
+    pub p50_latency_ms: f64,
+    #[graphql(description = "95th percentile latency of latest operations in milliseconds")]
+    pub p95_latency_ms: f64,
+    #[graphql(description = "Microservice calls made by operations")]
+    pub upstream_calls: Vec<UpstreamCallsCount>,
+}
+
+#[derive(GraphQLObject, Clone, Debug, PartialEq)]
+#[graphql(description = "Number of calls to microservice")]
+pub struct UpstreamCallsCount {
+    pub microservice: String,
+    pub count: i32,
+}
@@ -252,9 +252,9 @@ pub struct SearchOrderOptionInput {
     #[graphql(description = "Customer email")]
     pub email: Option<String>,
     #[graphql(description = "Min Date")]
-    pub created_from: Option<String>,
+    pub created_from: Option<GraphQLDateTime>,
     #[graphql(description = "Max Date")]
-    pub created_to: Option<String>,
+    pub created_to: Option<GraphQLDateTime>,
     #[graphql(description = "Payment status")]
     pub payment_status: Option<bool>,
     #[graphql(description = "Order status")]
@@ -288,9 +288,9 @@ pub struct SearchOrderOption {
     #[graphql(description = "Customer email")]
     pub email: Option<String>,
     #[graphql(description = "Min Date")]
-    pub created_from: Option<String>,
+    pub created_from: Option<GraphQLDateTime>,
     #[graphql(description = "Max Date")]
-    pub created_to: Option<String>,
+    pub created_to: Option<GraphQLDateTime>,
     #[graphql(description = "Payment status")]
     pub payment_status: Option<bool>,
     #[graphql(description = "Order status")]
@@ -2,11 +2,11 @@ use std::collections::HashMap;
 
 use bigdecimal::BigDecimal;
 use chrono::NaiveDateTime;
-use uuid::Uuid;
 
 use stq_static_resources::Currency;
 use stq_types::{OrderId, PayoutId, StoreId, UserId};
 
+use super::parse_uuid;
 use graphql::microservice::{self, CryptoPaymentDetails, PayOutToSellerPayload, PaymentDetails};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +20,14 @@ pub struct Payout {
     pub order_ids: Vec<OrderId>,
 }
 
+impl Payout {
+    pub fn currency(&self) -> Currency {
+        match &self.target {
+            PayoutTarget::CryptoWallet(target) => target.currency,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PayoutCalculation {
     pub order_ids: Vec<OrderId>,
@@ -156,7 +164,7 @@ impl PayOutCryptoToSellerInput {
 
         let order_ids = order_ids
             .into_iter()
-            .map(|order_id| order_id.parse::<Uuid>().map(OrderId).map_err(|_| ()))
+            .map(|order_id| parse_uuid(&order_id).map(OrderId).map_err(|_| ()))
             .collect::<Result<Vec<_>, ()>>()
             .map_err(|_| PayOutCryptoInputConversionError::InvalidOrderIdFormat)?;
 
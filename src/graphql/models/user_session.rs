@@ -0,0 +1,33 @@
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+use stq_types::UserId;
+
+/// Session issued along with JWT for a single device
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: SystemTime,
+    pub last_seen_at: SystemTime,
+    pub revoked_at: Option<SystemTime>,
+}
+
+/// Device info sent to users microservice on JWT issue
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewUserSession {
+    pub device: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(GraphQLInputObject, Debug, Clone)]
+#[graphql(description = "Revoke session input object")]
+pub struct RevokeSessionInput {
+    #[graphql(description = "Client mutation id.")]
+    pub client_mutation_id: String,
+    #[graphql(description = "Id of a session.")]
+    pub id: String,
+}
@@ -0,0 +1,91 @@
+//! Per field resilience policy. Fields wrapped with `Context::resilient` do not fail the whole
+//! response when microservice is down: they either serve last known good value or return null with typed error.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use juniper::{FieldError, Value};
+
+use config::Resilience;
+
+/// Entries are cleaned up after this amount is reached
+const MAX_ENTRIES: usize = 100_000;
+
+type Entry = (Instant, Box<Any + Send>);
+
+pub struct LastKnownGood {
+    stale_fields: Vec<String>,
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl LastKnownGood {
+    pub fn new(config: Option<&Resilience>) -> Self {
+        Self {
+            stale_fields: config.map(|config| config.stale_fields.clone()).unwrap_or_default(),
+            ttl: Duration::from_secs(config.map(|config| config.stale_ttl_sec).unwrap_or_default()),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn serves_stale(&self, field: &str) -> bool {
+        self.stale_fields.iter().any(|stale_field| stale_field == field)
+    }
+
+    pub fn put<T>(&self, field: &str, key: &str, value: T)
+    where
+        T: Send + 'static,
+    {
+        if let Ok(mut entries) = self.entries.lock() {
+            let now = Instant::now();
+            if entries.len() > MAX_ENTRIES {
+                let ttl = self.ttl;
+                entries.retain(|_, &mut (updated_at, _)| now.duration_since(updated_at) < ttl);
+            }
+            entries.insert((field.to_string(), key.to_string()), (now, Box::new(value)));
+        }
+    }
+
+    pub fn get<T>(&self, field: &str, key: &str) -> Option<T>
+    where
+        T: Clone + Send + 'static,
+    {
+        let entries = self.entries.lock().ok()?;
+        let &(updated_at, ref value) = entries.get(&(field.to_string(), key.to_string()))?;
+        if updated_at.elapsed() >= self.ttl {
+            return None;
+        }
+        value.downcast_ref::<T>().cloned()
+    }
+}
+
+/// Microservice is down or too slow: network error or timeout, open circuit breaker, exceeded deadline
+/// or 5xx response. Other errors, like validation ones, are not hidden by resilience policy.
+pub fn is_upstream_failure_error(e: &FieldError) -> bool {
+    let data = match *e.data() {
+        Value::Object(ref data) => data,
+        _ => return false,
+    };
+    match data.get("code") {
+        Some(&Value::Int(200)) | Some(&Value::Int(201)) | Some(&Value::Int(202)) => true,
+        // error response of microservice
+        Some(&Value::Int(100)) => match data.get("details") {
+            Some(&Value::Object(ref details)) => match details.get("status") {
+                Some(&Value::String(ref status)) => status.starts_with('5'),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+pub fn partial_result_error(field: &str) -> FieldError {
+    let field = field.to_string();
+    FieldError::new(
+        "Field is temporarily unavailable.",
+        graphql_value!({ "code": 203, "type": "PARTIAL_RESULT", "details": { "field": field }}),
+    )
+}
@@ -4,7 +4,6 @@ use std::str::FromStr;
 
 use futures::Future;
 use hyper::Method;
-use juniper;
 use juniper::FieldResult;
 use juniper::ID as GraphqlID;
 use serde_json;
@@ -30,6 +29,22 @@ graphql_object!(Admin: Context as "Admin" |&self| {
             .wait()
     }
 
+    field deprecated_field_usage(&executor) -> FieldResult<Vec<DeprecatedFieldUsage>>
+        as "Usage of deprecated fields and arguments by client since gateway start. Available for superusers only." {
+        let context = executor.context();
+        context.permissions().require_superuser()?;
+
+        Ok(context.deprecations.usage())
+    }
+
+    field operation_statistics(&executor) -> FieldResult<Vec<OperationStatistics>>
+        as "Count, error rate, latency and microservice calls of operations by client since gateway start. Available for superusers only." {
+        let context = executor.context();
+        context.permissions().require_superuser()?;
+
+        Ok(context.analytics.statistics())
+    }
+
     field deprecated "use usersSearchPages" users_search(&executor,
         first = None : Option<i32> as "First edges",
         after = None : Option<GraphqlID>  as "Base64 Id of a user",
@@ -38,43 +53,20 @@ graphql_object!(Admin: Context as "Admin" |&self| {
             -> FieldResult<Option<Connection<User, PageInfo>>> as "Searching for users using relay connection." {
         let context = executor.context();
 
-        let raw_id = match after {
-            Some(val) => ID::from_str(&*val)?.raw_id + 1,
-            None => MIN_ID
-        };
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
         let url = format!("{}/{}/search?offset={}&count={}",
             context.config.service_url(Service::Users),
             Model::User.to_url(),
-            raw_id,
-            first + 1);
+            window.after.map(|raw_id| raw_id + 1).unwrap_or(MIN_ID),
+            window.count);
 
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<UserSearchResults>(Method::Post, url, Some(body))
-            .map (|UserSearchResults { users, .. }| {
-                let mut user_edges: Vec<Edge<User>> = users
-                    .into_iter()
-                    .map(|user| Edge::new(
-                        juniper::ID::from(ID::new(Service::Users, Model::User, user.id.0).to_string()),
-                        user.clone()
-                    )).collect();
-                let has_next_page = user_edges.len() as i32 == first + 1;
-                if has_next_page {
-                    user_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  user_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = user_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(user_edges, page_info)
+            .map(|UserSearchResults { users, total_count }| {
+                window.connection(users, |user| user.id.0).with_total_count(TotalCount::Known(total_count as i32))
             })
             .wait()
             .map(Some)
@@ -107,17 +99,13 @@ graphql_object!(Admin: Context as "Admin" |&self| {
         context.request::<UserSearchResults>(Method::Post, url, Some(body))
             .map(|UserSearchResults { total_count, users }| {
                 let total_pages = cmp::max(0, total_count as i32 - 1) / items_count + 1;
-                let mut user_edges: Vec<Edge<User>> = users
-                    .into_iter()
-                    .map(|user| Edge::new(
-                        juniper::ID::from(ID::new(Service::Users, Model::User, user.id.0).to_string()), user.clone()
-                    )).collect();
+                let user_edges = Edge::create_vec(users, skip);
                 let page_info = PageInfoSegments {
                     current_page,
                     page_items_count: items_count,
                     total_pages,
                 };
-                Connection::new(user_edges, page_info)
+                Connection::new(user_edges, page_info).with_total_count(TotalCount::Known(total_count as i32))
             })
             .wait()
             .map(Some)
@@ -150,41 +138,18 @@ graphql_object!(Admin: Context as "Admin" |&self| {
 
         let body = serde_json::to_string(&term)?;
 
-        let raw_id = match after {
-            Some(val) => ID::from_str(&*val)?.raw_id,
-            None => MIN_ID
-        };
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
         let url = format!("{}/{}/moderator_search?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::Store.to_url(),
-            raw_id,
-            first);
+            window.after.unwrap_or(MIN_ID),
+            window.count);
 
         context.request::<StoreSearchResults>(Method::Post, url, Some(body))
-            .map(|StoreSearchResults { stores, .. }| {
-                let mut store_edges: Vec<Edge<Store>> = stores
-                    .into_iter()
-                    .map(|store| Edge::new(
-                        juniper::ID::from(ID::new(Service::Stores, Model::Store, store.id.0).to_string()),
-                        store.clone()
-                    )).collect();
-                let has_next_page = store_edges.len() as i32 == first + 1;
-                if has_next_page {
-                    store_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  store_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = store_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(store_edges, page_info)
+            .map(|StoreSearchResults { stores, total_count }| {
+                window.connection(stores, |store| store.id.0).with_total_count(TotalCount::Known(total_count as i32))
             })
             .wait()
             .map(Some)
@@ -233,18 +198,13 @@ graphql_object!(Admin: Context as "Admin" |&self| {
         context.request::<StoreSearchResults>(Method::Post, url, Some(body))
             .map(|StoreSearchResults { stores, total_count }| {
                 let total_pages = cmp::max(0, total_count as i32 - 1) / items_count + 1;
-                let mut store_edges: Vec<Edge<Store>> = stores
-                    .into_iter()
-                    .map(|store| Edge::new(
-                        juniper::ID::from(ID::new(Service::Stores, Model::Store, store.id.0).to_string()),
-                        store.clone()
-                    )).collect();
+                let store_edges = Edge::create_vec(stores, skip);
                 let page_info = PageInfoSegments {
                     current_page,
                     page_items_count: items_count,
                     total_pages,
                 };
-                Connection::new(store_edges, page_info)
+                Connection::new(store_edges, page_info).with_total_count(TotalCount::Known(total_count as i32))
             })
             .wait()
             .map(Some)
@@ -275,49 +235,29 @@ pub fn base_products_search(
 ) -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> {
     let body = serde_json::to_string(&search_term)?;
 
-    let raw_id = match after {
-        Some(val) => ID::from_str(&*val)?.raw_id,
-        None => MIN_ID,
-    };
-
     let records_limit = context.config.gateway.records_limit;
-    let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+    let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
     let url = format!(
         "{}/{}/moderator_search?offset={}&count={}",
         context.config.service_url(Service::Stores),
         Model::BaseProduct.to_url(),
-        raw_id,
-        first
+        window.after.unwrap_or(MIN_ID),
+        window.count
     );
 
     context
         .request::<BaseProductSearchResults>(Method::Post, url, Some(body))
-        .map(|BaseProductSearchResults { base_products, .. }| {
-            let mut base_product_edges: Vec<Edge<BaseProduct>> = base_products
-                .into_iter()
-                .map(|base_product| {
-                    Edge::new(
-                        juniper::ID::from(ID::new(Service::Stores, Model::BaseProduct, base_product.id.0).to_string()),
-                        base_product.clone(),
-                    )
-                })
-                .collect();
-            let has_next_page = base_product_edges.len() as i32 == first + 1;
-            if has_next_page {
-                base_product_edges.pop();
-            };
-            let has_previous_page = true;
-            let start_cursor = base_product_edges.get(0).map(|e| e.cursor.clone());
-            let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-            let page_info = PageInfo {
-                has_next_page,
-                has_previous_page,
-                start_cursor,
-                end_cursor,
-            };
-            Connection::new(base_product_edges, page_info)
-        })
+        .map(
+            |BaseProductSearchResults {
+                 base_products,
+                 total_count,
+             }| {
+                window
+                    .connection(base_products, |base_product| base_product.id.0)
+                    .with_total_count(TotalCount::Known(total_count as i32))
+            },
+        )
         .wait()
         .map(Some)
 }
@@ -353,21 +293,13 @@ pub fn base_products_search_pages(
                  total_count,
              }| {
                 let total_pages = cmp::max(0, total_count as i32 - 1) / items_count + 1;
-                let base_product_edges: Vec<Edge<BaseProduct>> = base_products
-                    .into_iter()
-                    .map(|base_product| {
-                        Edge::new(
-                            juniper::ID::from(ID::new(Service::Stores, Model::BaseProduct, base_product.id.0).to_string()),
-                            base_product.clone(),
-                        )
-                    })
-                    .collect();
+                let base_product_edges = Edge::create_vec(base_products, skip);
                 let page_info = PageInfoSegments {
                     current_page,
                     page_items_count: items_count,
                     total_pages,
                 };
-                Connection::new(base_product_edges, page_info)
+                Connection::new(base_product_edges, page_info).with_total_count(TotalCount::Known(total_count as i32))
             },
         )
         .wait()
@@ -0,0 +1,20 @@
+//! File containing anonymous session object of graphql schema
+use controller::anonymous_session::SessionToken;
+use graphql::context::Context;
+use graphql::models::GraphQLDateTime;
+
+graphql_object!(SessionToken: Context as "AnonymousSession" |&self| {
+    description: "Session of anonymous user, signed by gateway."
+
+    field token() -> &str as "Signed session token to be sent in SessionId header" {
+        &self.token
+    }
+
+    field session_id() -> String as "Session id" {
+        self.session_id.to_string()
+    }
+
+    field expires_at() -> GraphQLDateTime as "Session expires at unless used before" {
+        GraphQLDateTime::from_timestamp(self.exp)
+    }
+});
@@ -1,15 +1,12 @@
 //! File containing product object of graphql schema
-use std::cmp;
 use std::str::FromStr;
 
-use chrono::prelude::*;
 use futures::Future;
 use hyper::Method;
 use juniper;
 use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
 
-use stq_api::types::ApiFutureExt;
 use stq_api::warehouses::WarehouseClient;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
@@ -17,7 +14,6 @@ use stq_static_resources::{Currency, ModerationStatus, Translation};
 use stq_types::{BaseProductId, ProductId};
 
 use super::*;
-use errors::into_graphql;
 use graphql::context::Context;
 use graphql::models::*;
 
@@ -78,14 +74,12 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
         &self.category_id.0
     }
 
-    field created_at() -> String as "Created at" {
-        let datetime: DateTime<Utc> = self.created_at.into();
-        datetime.to_rfc3339()
+    field created_at() -> GraphQLDateTime as "Created at" {
+        self.created_at.into()
     }
 
-    field updated_at() -> String as "Updated at" {
-        let datetime: DateTime<Utc> = self.updated_at.into();
-        datetime.to_rfc3339()
+    field updated_at() -> GraphQLDateTime as "Updated at" {
+        self.updated_at.into()
     }
 
     field length_cm() -> Option<i32> as "Length (cm)" {
@@ -185,39 +179,17 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
 
     field products(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset from begining")
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before")
             -> FieldResult<Option<Connection<Product, PageInfo>>> as "Fetches products using relay connection." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let args = ConnectionArgs::new(first, after, last, before);
 
         if let Some(ref variants) = self.variants {
-            let mut product_edges: Vec<Edge<Product>> = variants.clone()
-                .into_iter()
-                .skip(offset as usize)
-                .take(first as usize)
-                .map(|product| Edge::new(
-                            juniper::ID::from(ID::new(Service::Stores, Model::Product, product.id.0).to_string()),
-                            product.clone()
-                        ))
-                .collect();
-            let has_next_page = product_edges.len() as i32 > first;
-            let has_previous_page = true;
-            let start_cursor =  product_edges.get(0).map(|e| e.cursor.clone());
-            let end_cursor = product_edges.iter().last().map(|e| e.cursor.clone());
-            let page_info = PageInfo {
-                has_next_page,
-                has_previous_page,
-                start_cursor,
-                end_cursor};
-            Ok(Some(Connection::new(product_edges, page_info)))
+            args.slice(variants.clone(), records_limit as i32).map(Some)
         } else {
             let url = format!("{}/{}/by_base_product/{}",
                 context.config.service_url(Service::Stores),
@@ -225,29 +197,9 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
                 self.id);
 
             context.request::<Vec<Product>>(Method::Get, url, None)
-            .map (|products| {
-                let mut product_edges: Vec<Edge<Product>> = products
-                    .into_iter()
-                    .skip(offset as usize)
-                    .take(first as usize)
-                    .map(|product| Edge::new(
-                                juniper::ID::from(ID::new(Service::Stores, Model::Product, product.id.0).to_string()),
-                                product.clone()
-                            ))
-                    .collect();
-                let has_next_page = product_edges.len() as i32 > first;
-                let has_previous_page = true;
-                let start_cursor =  product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(product_edges, page_info)
-            })
-            .wait()
-            .map(Some)
+                .wait()
+                .and_then(|products| args.slice(products, records_limit as i32))
+                .map(Some)
         }
     }
 
@@ -276,10 +228,7 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
     field available_packages(&executor) -> FieldResult<AvailablePackagesOutput> as "Available Packages" {
         let context = executor.context();
 
-        let rpc_client = context.get_rest_api_client(Service::Warehouses);
-        let warehouses = rpc_client.get_warehouses_for_store(self.store_id)
-            .sync()
-            .map_err(into_graphql)?;
+        let warehouses = context.call_rest_api(Service::Warehouses, |client| client.get_warehouses_for_store(self.store_id))?;
 
         let Measurements { volume_cubic_cm, weight_g } = self.get_measurements();
 
@@ -310,7 +259,7 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
         }
     }
 
-    field shipping(&executor) -> FieldResult<ShippingOutput> as "Shipping" {
+    field shipping(&executor) -> FieldResult<Option<ShippingOutput>> as "Shipping. Remote." {
         let context = executor.context();
         let url = format!("{}/{}/{}",
             context.config.service_url(Service::Delivery),
@@ -318,12 +267,14 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
             self.id.0
         );
 
-        context.request::<Shipping>(Method::Get, url, None)
-            .map(From::from)
-            .wait()
+        context.resilient("BaseProduct.shipping", &self.id.0.to_string(), || {
+            context.request::<Shipping>(Method::Get, url, None)
+                .map(From::from)
+                .wait()
+        })
     }
 
-    field is_shipping_available(&executor)-> FieldResult<bool> as "Is shipping available" {
+    field is_shipping_available(&executor)-> FieldResult<Option<bool>> as "Is shipping available. Remote." {
         let context = executor.context();
         let url = format!("{}/{}/{}",
             context.config.service_url(Service::Delivery),
@@ -331,10 +282,12 @@ graphql_object!(BaseProduct: Context as "BaseProduct" |&self| {
             self.id,
         );
 
-        let available_shipping = context.request::<Shipping>(Method::Get, url, None)
-            .wait()?;
+        context.resilient("BaseProduct.isShippingAvailable", &self.id.0.to_string(), || {
+            let available_shipping = context.request::<Shipping>(Method::Get, url, None)
+                .wait()?;
 
-        Ok(!available_shipping.items.is_empty())
+            Ok(!available_shipping.items.is_empty())
+        })
     }
 
 });
@@ -366,6 +319,10 @@ graphql_object!(Connection<BaseProduct, PageInfo>: Context as "BaseProductsConne
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Connection<BaseProduct, PageInfoSegments>: Context as "BaseProductsConnectionPages" |&self| {
@@ -378,6 +335,10 @@ graphql_object!(Connection<BaseProduct, PageInfoSegments>: Context as "BaseProdu
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<BaseProduct>: Context as "BaseProductsEdge" |&self| {
@@ -402,6 +363,10 @@ graphql_object!(Connection<BaseProduct, PageInfoProductsSearch>: Context as "Bas
     field page_info() -> &PageInfoProductsSearch {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 pub fn try_get_base_product(context: &Context, base_product_id: BaseProductId, visibility: Visibility) -> FieldResult<Option<BaseProduct>> {
@@ -487,7 +452,7 @@ fn send_to_moderation(context: &Context, base_product_id: BaseProductId) -> Fiel
 
 pub fn run_moderation_status_base_product(context: &Context, input: BaseProductModerateInput) -> FieldResult<BaseProduct> {
     let identifier = ID::from_str(&*input.id)?;
-    let base_product_id = BaseProductId(identifier.raw_id);
+    let base_product_id = BaseProductId(identifier.raw_id.int()?);
 
     let payload = BaseProductModerate {
         base_product_id,
@@ -557,11 +522,8 @@ pub fn get_base_product_shipping_details(
         graphql_value!({ "code": 300, "details": { "Base product not found." }}),
     ))?;
 
-    let rpc_client = context.get_rest_api_client(Service::Warehouses);
-    let warehouse = rpc_client
-        .get_warehouses_for_store(base_product.store_id)
-        .sync()
-        .map_err(into_graphql)?
+    let warehouse = context
+        .call_rest_api(Service::Warehouses, |client| client.get_warehouses_for_store(base_product.store_id))?
         .into_iter()
         .next()
         .ok_or(FieldError::new(
@@ -584,7 +546,7 @@ pub fn get_base_product_shipping_details(
 
 pub fn run_update_base_product(context: &Context, input: UpdateBaseProductInput) -> FieldResult<BaseProduct> {
     let identifier = ID::from_str(&*input.id)?;
-    let base_product_id = BaseProductId(identifier.raw_id);
+    let base_product_id = BaseProductId(identifier.raw_id.int()?);
 
     if input.is_none() {
         return Err(FieldError::new(
@@ -1,5 +1,6 @@
 //! File containing buy now values object of graphql schema
 
+use bigdecimal::BigDecimal;
 use juniper::{FieldError, FieldResult};
 
 use stq_static_resources::{Currency, CurrencyType};
@@ -25,39 +26,72 @@ graphql_object!(BuyNowCheckout: Context as "BuyNowCheckout" |&self| {
         &self.coupon
     }
 
-    field coupons_discounts() -> f64 as "Coupons discounts" {
-        calculate_coupon_discount(&self)
+    field deprecated "Use couponsDiscountsMoney" coupons_discounts() -> FieldResult<f64> as "Coupons discounts" {
+        calculate_coupon_discount(&self).map(|discount| decimal_to_f64(&discount))
     }
 
-    field total_cost(&executor) -> FieldResult<f64> as "Total cost" {
+    field coupons_discounts_money() -> FieldResult<Money> as "Coupons discounts" {
+        calculate_coupon_discount(&self).map(|discount| Money::new(discount, self.product.customer_price.currency))
+    }
+
+    field deprecated "Use totalCostMoney" total_cost(&executor) -> FieldResult<f64> as "Total cost" {
+        let context = executor.context();
+        calculate_total_cost(context, &self).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field total_cost_money(&executor) -> FieldResult<Money> as "Total cost" {
         let context = executor.context();
-        Ok(calculate_cost(&self) + calculate_delivery_cost(context, &self.package, self.quantity, self.product.currency)?)
+        calculate_total_cost(context, &self).map(|cost| Money::new(cost, self.product.customer_price.currency))
     }
 
-    field total_cost_without_discounts(&executor) -> FieldResult<f64> as "Total without cost" {
+    field deprecated "Use totalCostWithoutDiscountsMoney" total_cost_without_discounts(&executor) -> FieldResult<f64>
+        as "Total without cost" {
         let context = executor.context();
-        Ok(calculate_cost_without_discounts(&self) + calculate_delivery_cost(context, &self.package, self.quantity, self.product.currency)?)
+        calculate_total_cost_without_discounts(context, &self).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field total_cost_without_discounts_money(&executor) -> FieldResult<Money> as "Total without cost" {
+        let context = executor.context();
+        calculate_total_cost_without_discounts(context, &self).map(|cost| Money::new(cost, self.product.customer_price.currency))
     }
 
     field total_count() -> &i32 as "Total products count" {
         &self.quantity.0
     }
 
-    field price() -> &f64 as "Price" {
+    field deprecated "Use priceMoney" price() -> &f64 as "Price" {
         &self.product.customer_price.price.0
     }
 
-    field subtotal() -> f64 as "Subtotal with discounts" {
-        calculate_cost(&self)
+    field price_money() -> FieldResult<Money> as "Price" {
+        Money::from_f64(self.product.customer_price.price.0, self.product.customer_price.currency)
+    }
+
+    field deprecated "Use subtotalMoney" subtotal() -> FieldResult<f64> as "Subtotal with discounts" {
+        calculate_cost(&self).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field subtotal_money() -> FieldResult<Money> as "Subtotal with discounts" {
+        calculate_cost(&self).map(|cost| Money::new(cost, self.product.customer_price.currency))
+    }
+
+    field deprecated "Use subtotalWithoutDiscountsMoney" subtotal_without_discounts() -> FieldResult<f64> as "Subtotal without discounts" {
+        calculate_cost_without_discounts(&self).map(|cost| decimal_to_f64(&cost))
     }
 
-    field subtotal_without_discounts() -> f64 as "Subtotal without discounts" {
-        calculate_cost_without_discounts(&self)
+    field subtotal_without_discounts_money() -> FieldResult<Money> as "Subtotal without discounts" {
+        calculate_cost_without_discounts(&self).map(|cost| Money::new(cost, self.product.customer_price.currency))
+    }
+
+    field deprecated "Use deliveryCostMoney" delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+        let context = executor.context();
+        calculate_delivery_cost(context, &self.package, self.quantity, self.product.currency).map(|cost| decimal_to_f64(&cost))
     }
 
-    field delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+    field delivery_cost_money(&executor) -> FieldResult<Money> as "Delivery cost" {
         let context = executor.context();
         calculate_delivery_cost(context, &self.package, self.quantity, self.product.currency)
+            .map(|cost| Money::new(cost, self.product.customer_price.currency))
     }
 
     field package() -> &Option<AvailablePackageForUser> as "Select delivery package" {
@@ -65,46 +99,57 @@ graphql_object!(BuyNowCheckout: Context as "BuyNowCheckout" |&self| {
     }
 });
 
-fn calculate_cost(buy_now: &BuyNowCheckout) -> f64 {
+fn calculate_cost(buy_now: &BuyNowCheckout) -> FieldResult<BigDecimal> {
     if buy_now.quantity.0 <= 0 {
-        return 0f64;
+        return Ok(BigDecimal::default());
     }
 
+    let price = decimal(buy_now.product.customer_price.price.0)?;
+    let quantity = BigDecimal::from(i64::from(buy_now.quantity.0));
+
     if let Some(discount) = buy_now.product.discount.filter(|discount| *discount > ZERO_DISCOUNT) {
-        let calc_cost = (buy_now.product.customer_price.price.0 * (f64::from(buy_now.quantity.0))) * (1.0f64 - discount);
+        let calc_cost = price * quantity * (BigDecimal::from(1i64) - decimal(discount)?);
 
-        return calc_cost;
+        return Ok(calc_cost);
     } else {
         if buy_now.coupon.is_some() {
             // set discount only 1 product
-            let product_cost_with_coupon_discount = buy_now.product.customer_price.price.0 - calculate_coupon_discount(buy_now);
-            let calc_cost =
-                product_cost_with_coupon_discount + (buy_now.product.customer_price.price.0 * (f64::from(buy_now.quantity.0 - 1)));
+            let calc_cost = price * quantity - calculate_coupon_discount(buy_now)?;
 
-            return calc_cost;
+            return Ok(calc_cost);
         }
     }
 
-    buy_now.product.customer_price.price.0 * f64::from(buy_now.quantity.0)
+    Ok(price * quantity)
 }
 
-fn calculate_cost_without_discounts(buy_now: &BuyNowCheckout) -> f64 {
+fn calculate_cost_without_discounts(buy_now: &BuyNowCheckout) -> FieldResult<BigDecimal> {
     if buy_now.quantity.0 <= 0 {
-        return 0f64;
+        return Ok(BigDecimal::default());
     }
 
-    buy_now.product.customer_price.price.0 * f64::from(buy_now.quantity.0)
+    Ok(decimal(buy_now.product.customer_price.price.0)? * BigDecimal::from(i64::from(buy_now.quantity.0)))
 }
 
-fn calculate_coupon_discount(buy_now: &BuyNowCheckout) -> f64 {
+fn calculate_coupon_discount(buy_now: &BuyNowCheckout) -> FieldResult<BigDecimal> {
     if let Some(coupon) = buy_now.coupon.as_ref() {
         // set discount only 1 product
-        let discount = (buy_now.product.customer_price.price.0 / 100f64) * f64::from(coupon.percent);
+        let discount =
+            decimal(buy_now.product.customer_price.price.0)? * BigDecimal::from(i64::from(coupon.percent)) / BigDecimal::from(100i64);
 
-        return discount;
+        return Ok(discount);
     }
 
-    0.0f64
+    Ok(BigDecimal::default())
+}
+
+fn calculate_total_cost(context: &Context, buy_now: &BuyNowCheckout) -> FieldResult<BigDecimal> {
+    Ok(calculate_cost(buy_now)? + calculate_delivery_cost(context, &buy_now.package, buy_now.quantity, buy_now.product.currency)?)
+}
+
+fn calculate_total_cost_without_discounts(context: &Context, buy_now: &BuyNowCheckout) -> FieldResult<BigDecimal> {
+    Ok(calculate_cost_without_discounts(buy_now)?
+        + calculate_delivery_cost(context, &buy_now.package, buy_now.quantity, buy_now.product.currency)?)
 }
 
 fn calculate_delivery_cost(
@@ -112,9 +157,9 @@ fn calculate_delivery_cost(
     package: &Option<AvailablePackageForUser>,
     quantity: Quantity,
     currency: Currency,
-) -> FieldResult<f64> {
+) -> FieldResult<BigDecimal> {
     if quantity.0 <= 0 {
-        return Ok(0f64);
+        return Ok(BigDecimal::default());
     };
 
     if let Some(package) = package {
@@ -130,10 +175,11 @@ fn calculate_delivery_cost(
             1.0
         };
 
-        return Ok(package.price.0 / exch_rate * quantity.0 as f64);
+        let price = exchange(decimal(package.price.0)?, exch_rate)?;
+        return Ok(price * BigDecimal::from(i64::from(quantity.0)));
     }
 
-    Ok(0.0f64)
+    Ok(BigDecimal::default())
 }
 
 pub fn run_buy_now_mutation(context: &Context, input: BuyNowInputV2) -> FieldResult<CreateOrdersOutput> {
@@ -1,8 +1,6 @@
 //! File containing Cart object of graphql schema
 
-use std::cmp;
-use std::str::FromStr;
-
+use bigdecimal::BigDecimal;
 use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
 
@@ -14,18 +12,17 @@ use stq_routes::service::Service;
 use stq_types::{CartCustomer, CartItem, DeliveryMethodId, ProductId, Quantity, ShippingId, UserId};
 
 use stq_api::orders::{CartClient, UserCountryCodeUpdater};
-use stq_api::types::ApiFutureExt;
 
-use stq_static_resources::CurrencyType;
+use stq_static_resources::{Currency, CurrencyType};
 
 use super::*;
 use graphql::context::Context;
 use graphql::models::*;
 use graphql::schema::cart_store::{
     calculate_coupons_discount, calculate_products_delivery_cost, calculate_products_price, calculate_products_price_without_discounts,
+    delivery_cost_key, products_currency,
 };
 
-use errors::into_graphql;
 use graphql::schema::available_packages;
 use graphql::schema::base_product as base_product_module;
 use graphql::schema::product as product_module;
@@ -56,71 +53,104 @@ graphql_object!(Cart: Context as "Cart" |&self| {
 
     field stores(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset")
-            -> Connection<CartStore, PageInfo> as "Fetches stores using relay connection." {
+        after = None : Option<GraphqlID>  as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before")
+            -> FieldResult<Connection<CartStore, PageInfo>> as "Fetches stores using relay connection." {
         let context = executor.context();
+        let records_limit = context.config.gateway.records_limit;
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
+        ConnectionArgs::new(first, after, last, before).slice(self.inner.clone(), records_limit as i32)
+    }
 
-        let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+    field deprecated "Use productsCostMoney" products_cost(&executor) -> FieldResult<f64> as "Products cost" {
+        let context = executor.context();
 
-        let mut cart_stores: Vec<CartStore> = self.inner.clone()
-            .into_iter()
-            .skip(offset as usize)
-            .take(count as usize)
-            .collect();
-        let mut store_edges = Edge::create_vec(cart_stores, offset);
-        let has_next_page = store_edges.len() as i32 > count;
-        let has_previous_page = true;
-        let start_cursor =  store_edges.get(0).map(|e| e.cursor.clone());
-        let end_cursor = store_edges.iter().last().map(|e| e.cursor.clone());
-        let page_info = PageInfo {
-            has_next_page,
-            has_previous_page,
-            start_cursor,
-            end_cursor};
-        Connection::new(store_edges, page_info)
+        calculate_cart_price(context, &self.inner).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field products_cost_money(&executor) -> FieldResult<Money> as "Products cost" {
+        let context = executor.context();
+        let currency = cart_currency(context, self);
+
+        calculate_cart_price(context, &self.inner).map(|cost| Money::new(cost, currency))
+    }
+
+    field deprecated "Use productsCostWithoutDiscountsMoney" products_cost_without_discounts() -> FieldResult<f64>
+        as "Products without cost" {
+        calculate_cart_price_without_discounts(&self.inner).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field products_cost_without_discounts_money(&executor) -> FieldResult<Money> as "Products without cost" {
+        let context = executor.context();
+        let currency = cart_currency(context, self);
+
+        calculate_cart_price_without_discounts(&self.inner).map(|cost| Money::new(cost, currency))
+    }
+
+    field deprecated "Use couponsDiscountsMoney" coupons_discounts(&executor) -> FieldResult<f64> as "Coupons discounts" {
+        let context = executor.context();
+
+        calculate_cart_coupons_discount(context, &self.inner).map(|discount| decimal_to_f64(&discount))
+    }
+
+    field coupons_discounts_money(&executor) -> FieldResult<Money> as "Coupons discounts" {
+        let context = executor.context();
+        let currency = cart_currency(context, self);
+
+        calculate_cart_coupons_discount(context, &self.inner).map(|discount| Money::new(discount, currency))
     }
 
-    field products_cost(&executor) -> FieldResult<f64> as "Products cost" {
+    field deprecated "Use deliveryCostMoney" delivery_cost(&executor) -> FieldResult<Option<f64>> as "Delivery cost. Remote." {
         let context = executor.context();
 
-        calculate_cart_price(context, &self.inner)
+        context.resilient("Cart.deliveryCost", &cart_delivery_cost_key(&self.inner), || {
+            calculate_cart_delivery_cost(context, &self.inner)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
     }
 
-    field products_cost_without_discounts(&executor) -> f64 as "Products without cost" {
+    field delivery_cost_money(&executor) -> FieldResult<Option<Money>> as "Delivery cost. Remote." {
         let context = executor.context();
+        let currency = cart_currency(context, self);
 
-        calculate_cart_price_without_discounts(&self.inner)
+        context.resilient("Cart.deliveryCostMoney", &cart_delivery_cost_key(&self.inner), || {
+            calculate_cart_delivery_cost(context, &self.inner)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
-    field coupons_discounts(&executor) -> FieldResult<f64> as "Coupons discounts" {
+    field deprecated "Use totalCostMoney" total_cost(&executor) -> FieldResult<Option<f64>> as "Total cost. Remote." {
         let context = executor.context();
 
-        calculate_cart_coupons_discount(context, &self.inner)
+        context.resilient("Cart.totalCost", &cart_delivery_cost_key(&self.inner), || {
+            Ok(calculate_cart_price(context, &self.inner)? + calculate_cart_delivery_cost(context, &self.inner)?)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
     }
 
-    field delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+    field total_cost_money(&executor) -> FieldResult<Option<Money>> as "Total cost. Remote." {
         let context = executor.context();
+        let currency = cart_currency(context, self);
 
-        calculate_cart_delivery_cost(context, &self.inner)
+        context.resilient("Cart.totalCostMoney", &cart_delivery_cost_key(&self.inner), || {
+            Ok(calculate_cart_price(context, &self.inner)? + calculate_cart_delivery_cost(context, &self.inner)?)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
-    field total_cost(&executor) -> FieldResult<f64> as "Total cost" {
+    field deprecated "Use totalCostWithoutDiscountsMoney" total_cost_without_discounts(&executor) -> FieldResult<Option<f64>>
+        as "Total without cost. Remote." {
         let context = executor.context();
 
-        Ok(calculate_cart_price(context, &self.inner)? + calculate_cart_delivery_cost(context, &self.inner)?)
+        context.resilient("Cart.totalCostWithoutDiscounts", &cart_delivery_cost_key(&self.inner), || {
+            Ok(calculate_cart_price_without_discounts(&self.inner)? + calculate_cart_delivery_cost(context, &self.inner)?)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
     }
 
-    field total_cost_without_discounts(&executor) -> FieldResult<f64> as "Total without cost" {
+    field total_cost_without_discounts_money(&executor) -> FieldResult<Option<Money>> as "Total without cost. Remote." {
         let context = executor.context();
+        let currency = cart_currency(context, self);
 
-        Ok(calculate_cart_price_without_discounts(&self.inner) + calculate_cart_delivery_cost(context, &self.inner)?)
+        context.resilient("Cart.totalCostWithoutDiscountsMoney", &cart_delivery_cost_key(&self.inner), || {
+            Ok(calculate_cart_price_without_discounts(&self.inner)? + calculate_cart_delivery_cost(context, &self.inner)?)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
     field total_count() -> i32 as "Total products count" {
@@ -159,44 +189,48 @@ graphql_object!(CartProductStore: Context as "CartProductStore" |&self| {
 
 });
 
-pub fn calculate_cart_price(context: &Context, stores: &[CartStore]) -> FieldResult<f64> {
-    let cost = stores.iter().try_fold(0.0, |acc, store| {
-        let store_products_cost = calculate_products_price(context, &store.products)?;
-
-        Ok(acc + store_products_cost)
-    });
-
-    cost
+pub fn calculate_cart_price(context: &Context, stores: &[CartStore]) -> FieldResult<BigDecimal> {
+    stores.iter().try_fold(BigDecimal::default(), |acc, store| {
+        Ok(acc + calculate_products_price(context, &store.products)?)
+    })
 }
 
-pub fn calculate_cart_price_without_discounts(stores: &[CartStore]) -> f64 {
-    let cost = stores.iter().fold(0.0, |acc, store| {
-        let store_products_cost = calculate_products_price_without_discounts(&store.products);
-
-        acc + store_products_cost
-    });
-
-    cost
+pub fn calculate_cart_price_without_discounts(stores: &[CartStore]) -> FieldResult<BigDecimal> {
+    stores.iter().try_fold(BigDecimal::default(), |acc, store| {
+        Ok(acc + calculate_products_price_without_discounts(&store.products)?)
+    })
 }
 
-pub fn calculate_cart_coupons_discount(context: &Context, stores: &[CartStore]) -> FieldResult<f64> {
-    let cost = stores.iter().try_fold(0.0, |acc, store| {
-        let store_coupons_discount = calculate_coupons_discount(context, &store.products)?;
-
-        Ok(acc + store_coupons_discount)
-    });
-
-    cost
+pub fn calculate_cart_coupons_discount(context: &Context, stores: &[CartStore]) -> FieldResult<BigDecimal> {
+    stores.iter().try_fold(BigDecimal::default(), |acc, store| {
+        Ok(acc + calculate_coupons_discount(context, &store.products)?)
+    })
 }
 
-pub fn calculate_cart_delivery_cost(context: &Context, stores: &[CartStore]) -> FieldResult<f64> {
-    let cost = stores.iter().try_fold(0.0, |acc, store| {
-        let store_products_cost = calculate_products_delivery_cost(context, &store.products)?;
+pub fn calculate_cart_delivery_cost(context: &Context, stores: &[CartStore]) -> FieldResult<BigDecimal> {
+    stores.iter().try_fold(BigDecimal::default(), |acc, store| {
+        Ok(acc + calculate_products_delivery_cost(context, &store.products)?)
+    })
+}
 
-        Ok(acc + store_products_cost)
-    });
+/// Currency of the cart totals
+fn cart_currency(context: &Context, cart: &Cart) -> Currency {
+    let products = cart
+        .inner
+        .iter()
+        .find(|store| !store.products.is_empty())
+        .map(|store| store.products.as_slice())
+        .unwrap_or(&[]);
+    products_currency(context, products, cart.currency_type)
+}
 
-    cost
+/// Cache key of values depending on delivery cost of the whole cart
+fn cart_delivery_cost_key(stores: &[CartStore]) -> String {
+    stores
+        .iter()
+        .map(|store| delivery_cost_key(&store.products))
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
 pub fn run_set_delivery_method_in_cart(context: &Context, input: SetDeliveryMethodInCartInputV2) -> FieldResult<Cart> {
@@ -225,13 +259,12 @@ pub fn run_set_delivery_method_in_cart(context: &Context, input: SetDeliveryMeth
         "Could not set delivery method in cart.",
     )?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
     let delivery_method_id = DeliveryMethodId::ShippingPackage { id: shipping_id };
 
-    let products = rpc_client
-        .set_delivery_method(customer, product_id, delivery_method_id)
-        .sync()
-        .map_err(into_graphql)?
+    let products = context
+        .call_rest_api(Service::Orders, |client| {
+            client.set_delivery_method(customer, product_id, delivery_method_id)
+        })?
         .into_iter()
         .collect::<Vec<_>>();
 
@@ -258,13 +291,12 @@ pub fn run_set_delivery_method_in_cart_v1(context: &Context, input: SetDeliveryM
 
     let _select_package: AvailablePackageForUser = available_packages::get_available_package_for_user_by_id_v1(context, shipping_id)?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
     let delivery_method_id = DeliveryMethodId::ShippingPackage { id: shipping_id };
 
-    let products = rpc_client
-        .set_delivery_method(customer, product_id, delivery_method_id)
-        .sync()
-        .map_err(into_graphql)?
+    let products = context
+        .call_rest_api(Service::Orders, |client| {
+            client.set_delivery_method(customer, product_id, delivery_method_id)
+        })?
         .into_iter()
         .collect::<Vec<_>>();
 
@@ -288,11 +320,10 @@ pub fn run_remove_delivery_method_from_cart_v1(context: &Context, input: RemoveD
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let products = rpc_client
-        .delete_delivery_method_by_product(customer, ProductId(input.product_id))
-        .sync()
-        .map_err(into_graphql)?
+    let products = context
+        .call_rest_api(Service::Orders, |client| {
+            client.delete_delivery_method_by_product(customer, product_id)
+        })?
         .into_iter()
         .collect::<Vec<_>>();
 
@@ -316,11 +347,10 @@ pub fn run_remove_delivery_method_from_cart(context: &Context, input: RemoveDeli
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let products = rpc_client
-        .delete_delivery_method_by_product(customer, ProductId(input.product_id))
-        .sync()
-        .map_err(into_graphql)?
+    let products = context
+        .call_rest_api(Service::Orders, |client| {
+            client.delete_delivery_method_by_product(customer, product_id)
+        })?
         .into_iter()
         .collect::<Vec<_>>();
 
@@ -346,40 +376,35 @@ pub fn run_increment_in_cart_v1(context: &Context, input: IncrementInCartInput)
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let init_quantity = rpc_client
-        .get_cart(customer, Some(base_product.currency.currency_type()))
-        .sync()
-        .map_err(into_graphql)?
+    let init_quantity = context
+        .call_rest_api(Service::Orders, |client| {
+            client.get_cart(customer, Some(base_product.currency.currency_type()))
+        })?
         .into_iter()
         .find(|product| product.product_id == product_id)
         .map(|product| product.quantity.0)
         .unwrap_or(0i32);
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-
-    let mut products: Vec<_> = rpc_client
-        .increment_item(
-            customer,
-            input.product_id.into(),
-            base_product.store_id,
-            product.pre_order,
-            product.pre_order_days,
-            base_product.currency.currency_type(),
-            None,
-        )
-        .sync()
-        .map_err(into_graphql)?
+    let mut products: Vec<_> = context
+        .call_rest_api(Service::Orders, |client| {
+            client.increment_item(
+                customer,
+                input.product_id.into(),
+                base_product.store_id,
+                product.pre_order,
+                product.pre_order_days,
+                base_product.currency.currency_type(),
+                None,
+            )
+        })?
         .into_iter()
         .collect();
-    // drop previous rpc_client
-    let rpc_client = context.get_rest_api_client(Service::Orders);
     if let Some(value) = input.value {
         let quantity = Quantity(init_quantity + value);
-        products = rpc_client
-            .set_quantity(customer, input.product_id.into(), quantity)
-            .sync()
-            .map_err(into_graphql)?
+        products = context
+            .call_rest_api(Service::Orders, |client| {
+                client.set_quantity(customer, input.product_id.into(), quantity)
+            })?
             .into_iter()
             .collect();
     }
@@ -406,44 +431,38 @@ pub fn run_increment_in_cart(context: &Context, input: IncrementInCartInputV2) -
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let init_quantity = rpc_client
-        .get_cart(customer, Some(base_product.currency.currency_type()))
-        .sync()
-        .map_err(into_graphql)?
+    let init_quantity = context
+        .call_rest_api(Service::Orders, |client| {
+            client.get_cart(customer, Some(base_product.currency.currency_type()))
+        })?
         .into_iter()
         .find(|product| product.product_id == product_id)
         .map(|product| product.quantity.0)
         .unwrap_or(0i32);
 
-    // drop previous rpc_client
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-
-    let mut products: Vec<_> = rpc_client
-        .increment_item(
-            customer,
-            input.product_id.into(),
-            base_product.store_id,
-            product.pre_order,
-            product.pre_order_days,
-            base_product.currency.currency_type(),
-            Some(UserCountryCodeUpdater::Set {
-                value: input.user_country_code.clone().into(),
-            }),
-        )
-        .sync()
-        .map_err(into_graphql)?
+    let mut products: Vec<_> = context
+        .call_rest_api(Service::Orders, |client| {
+            client.increment_item(
+                customer,
+                input.product_id.into(),
+                base_product.store_id,
+                product.pre_order,
+                product.pre_order_days,
+                base_product.currency.currency_type(),
+                Some(UserCountryCodeUpdater::Set {
+                    value: input.user_country_code.clone().into(),
+                }),
+            )
+        })?
         .into_iter()
         .collect();
 
-    // drop previous rpc_client
-    let rpc_client = context.get_rest_api_client(Service::Orders);
     if let Some(value) = input.value {
         let quantity = Quantity(init_quantity + value);
-        products = rpc_client
-            .set_quantity(customer, input.product_id.into(), quantity)
-            .sync()
-            .map_err(into_graphql)?
+        products = context
+            .call_rest_api(Service::Orders, |client| {
+                client.set_quantity(customer, input.product_id.into(), quantity)
+            })?
             .into_iter()
             .collect();
     }
@@ -509,29 +528,33 @@ pub fn convert_products_to_cart(context: &Context, products: &[CartItem], user_c
 }
 
 pub fn get_cart(context: &Context, currency_type: Option<CurrencyType>) -> FieldResult<Cart> {
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let fut = if let Some(session_id) = context.session_id {
-        if let Some(ref user) = context.user {
-            rpc_client.merge(session_id.into(), user.user_id.into(), currency_type)
-        } else {
-            rpc_client.get_cart(session_id.into(), currency_type)
-        }
-    } else if let Some(ref user) = context.user {
-        rpc_client.get_cart(user.user_id.into(), currency_type)
-    } else {
-        return Err(FieldError::new(
-            "Could not get users cart.",
-            graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
-        ));
-    };
-
-    let products: Vec<_> = fut.sync().map_err(into_graphql)?.into_iter().collect();
+    let products = get_cart_products(context, currency_type)?;
 
     let mut cart = convert_products_to_cart(context, &products, None)?;
     cart.currency_type = currency_type;
     Ok(cart)
 }
 
+/// Products in cart of user or anonymous session, cart of session is merged into users one
+pub fn get_cart_products(context: &Context, currency_type: Option<CurrencyType>) -> FieldResult<Vec<CartItem>> {
+    let user_id = context.user.as_ref().map(|user| user.user_id);
+    let products = match (context.session_id, user_id) {
+        (Some(session_id), Some(user_id)) => context.call_rest_api(Service::Orders, |client| {
+            client.merge(session_id.into(), user_id.into(), currency_type)
+        })?,
+        (Some(session_id), None) => context.call_rest_api(Service::Orders, |client| client.get_cart(session_id.into(), currency_type))?,
+        (None, Some(user_id)) => context.call_rest_api(Service::Orders, |client| client.get_cart(user_id.into(), currency_type))?,
+        (None, None) => {
+            return Err(FieldError::new(
+                "Could not get users cart.",
+                graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
+            ))
+        }
+    };
+
+    Ok(products.into_iter().collect())
+}
+
 pub fn get_customer(context: &Context) -> Option<CartCustomer> {
     if let Some(ref user) = context.user {
         Some(user.user_id.into())
@@ -1,6 +1,7 @@
 //! File containing PageInfo object of graphql schema
 use std::collections::{HashMap, HashSet};
 
+use bigdecimal::BigDecimal;
 use futures::Future;
 use hyper::Method;
 use juniper::ID as GraphqlID;
@@ -41,23 +42,42 @@ graphql_object!(CartProduct: Context as "CartProduct" |&self| {
         &self.quantity.0
     }
 
-    field price() -> &f64 as "Price" {
+    field deprecated "Use priceMoney" price() -> &f64 as "Price" {
         &self.customer_price.0
     }
 
-    field subtotal(&executor) -> FieldResult<f64> as "Subtotal with discounts" {
+    field price_money() -> FieldResult<Money> as "Price" {
+        Money::from_f64(self.customer_price.0, self.customer_currency)
+    }
+
+    field deprecated "Use subtotalMoney" subtotal(&executor) -> FieldResult<f64> as "Subtotal with discounts" {
+        let context = executor.context();
+        calculate_product_price(context, &self).map(|price| decimal_to_f64(&price))
+    }
+
+    field subtotal_money(&executor) -> FieldResult<Money> as "Subtotal with discounts" {
         let context = executor.context();
-        calculate_product_price(context, &self)
+        calculate_product_price(context, &self).map(|price| Money::new(price, self.customer_currency))
+    }
+
+    field deprecated "Use subtotalWithoutDiscountsMoney" subtotal_without_discounts() -> FieldResult<f64> as "Subtotal without discounts" {
+        calculate_product_price_without_discounts(&self).map(|price| decimal_to_f64(&price))
+    }
+
+    field subtotal_without_discounts_money() -> FieldResult<Money> as "Subtotal without discounts" {
+        calculate_product_price_without_discounts(&self).map(|price| Money::new(price, self.customer_currency))
     }
 
-    field subtotal_without_discounts() -> f64 as "Subtotal without discounts" {
-        self.customer_price.0 * f64::from(self.quantity.0)
+    field deprecated "Use deliveryCostMoney" delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+        let context = executor.context();
+
+        calculate_delivery_cost(context, &self).map(|cost| decimal_to_f64(&cost))
     }
 
-    field delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+    field delivery_cost_money(&executor) -> FieldResult<Money> as "Delivery cost" {
         let context = executor.context();
 
-        calculate_delivery_cost(context, &self)
+        calculate_delivery_cost(context, &self).map(|cost| Money::new(cost, self.customer_currency))
     }
 
     field photo_main() -> &Option<String> as "Photo main" {
@@ -134,10 +154,16 @@ graphql_object!(CartProduct: Context as "CartProduct" |&self| {
         }
     }
 
-    field coupon_discount(&executor) -> FieldResult<f64> as "Coupon discount" {
+    field deprecated "Use couponDiscountMoney" coupon_discount(&executor) -> FieldResult<f64> as "Coupon discount" {
+        let context = executor.context();
+
+        calculate_coupon_discount(context, &self).map(|discount| decimal_to_f64(&discount))
+    }
+
+    field coupon_discount_money(&executor) -> FieldResult<Money> as "Coupon discount" {
         let context = executor.context();
 
-        calculate_coupon_discount(context, &self)
+        calculate_coupon_discount(context, &self).map(|discount| Money::new(discount, self.customer_currency))
     }
 
     field base_product(&executor,
@@ -176,48 +202,49 @@ graphql_object!(CartProduct: Context as "CartProduct" |&self| {
     }
 });
 
-pub fn calculate_product_price(context: &Context, cart_product: &CartProduct) -> FieldResult<f64> {
+pub fn calculate_product_price(context: &Context, cart_product: &CartProduct) -> FieldResult<BigDecimal> {
     if cart_product.quantity.0 <= 0 {
-        return Ok(0f64);
+        return Ok(BigDecimal::default());
     }
 
+    let price = decimal(cart_product.customer_price.0)?;
+    let quantity = BigDecimal::from(i64::from(cart_product.quantity.0));
+
     if let Some(discount) = cart_product.discount.filter(|discount| *discount > ZERO_DISCOUNT) {
-        let calc_price = (cart_product.customer_price.0 * (f64::from(cart_product.quantity.0))) * (1.0f64 - discount);
+        let calc_price = price * quantity * (BigDecimal::from(1i64) - decimal(discount)?);
 
         return Ok(calc_price);
     } else {
         if cart_product.coupon_id.is_some() {
             // set discount only 1 product
-            let product_price_with_coupon_discount = cart_product.customer_price.0 - calculate_coupon_discount(context, cart_product)?;
-            let calc_price =
-                product_price_with_coupon_discount + (cart_product.customer_price.0 * (f64::from(cart_product.quantity.0 - 1)));
+            let calc_price = price * quantity - calculate_coupon_discount(context, cart_product)?;
 
             return Ok(calc_price);
         }
     }
 
-    Ok(cart_product.customer_price.0 * f64::from(cart_product.quantity.0))
+    Ok(price * quantity)
 }
 
-pub fn calculate_product_price_without_discounts(cart_product: &CartProduct) -> f64 {
+pub fn calculate_product_price_without_discounts(cart_product: &CartProduct) -> FieldResult<BigDecimal> {
     if cart_product.quantity.0 <= 0 {
-        return 0f64;
+        return Ok(BigDecimal::default());
     }
 
-    cart_product.customer_price.0 * f64::from(cart_product.quantity.0)
+    Ok(decimal(cart_product.customer_price.0)? * BigDecimal::from(i64::from(cart_product.quantity.0)))
 }
 
-pub fn calculate_coupon_discount(context: &Context, cart_product: &CartProduct) -> FieldResult<f64> {
+pub fn calculate_coupon_discount(context: &Context, cart_product: &CartProduct) -> FieldResult<BigDecimal> {
     if let Some(coupon_id) = cart_product.coupon_id {
         if let Some(coupon) = try_get_coupon(context, coupon_id)? {
             // set discount only 1 product
-            let discount = (cart_product.customer_price.0 / 100f64) * f64::from(coupon.percent);
+            let discount = decimal(cart_product.customer_price.0)? * BigDecimal::from(i64::from(coupon.percent)) / BigDecimal::from(100i64);
 
             return Ok(discount);
         }
     }
 
-    Ok(0.0f64)
+    Ok(BigDecimal::default())
 }
 
 pub fn get_cart_product_base_product(context: &Context, product: &CartProduct) -> FieldResult<Option<BaseProduct>> {
@@ -252,7 +279,7 @@ pub fn get_exchange_rate(context: &Context, product: &CartProduct) -> FieldResul
     Ok(currency_map.get(&user_currency).cloned().unwrap_or(ExchangeRate(1.0)))
 }
 
-pub fn calculate_delivery_cost(context: &Context, product: &CartProduct) -> FieldResult<f64> {
+pub fn calculate_delivery_cost(context: &Context, product: &CartProduct) -> FieldResult<BigDecimal> {
     calculate_delivery_cost_with_exchange_rate(context, product, get_exchange_rate(context, product)?)
 }
 
@@ -260,15 +287,16 @@ pub fn calculate_delivery_cost_with_exchange_rate(
     context: &Context,
     product: &CartProduct,
     exchange_rate: ExchangeRate,
-) -> FieldResult<f64> {
+) -> FieldResult<BigDecimal> {
     match product.delivery_method_id {
-        None => Ok(0.0f64),
+        None => Ok(BigDecimal::default()),
         Some(delivery_method_id) => {
             let package = match product.user_country_code.clone() {
                 None => get_select_package_v1(context, delivery_method_id)?,
                 Some(user_country_code) => get_select_package(context, product.base_product_id, user_country_code, delivery_method_id)?,
             };
-            Ok(package.price.0 / exchange_rate.0 * f64::from(product.quantity.0))
+            let price = exchange(decimal(package.price.0)?, exchange_rate.0)?;
+            Ok(price * BigDecimal::from(i64::from(product.quantity.0)))
         }
     }
 }
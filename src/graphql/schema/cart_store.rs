@@ -1,18 +1,21 @@
 //! File containing node object of graphql schema
 //! File containing store object of graphql schema
+use bigdecimal::BigDecimal;
 use juniper;
 use juniper::FieldResult;
 use juniper::ID as GraphqlID;
 
 use stq_routes::model::Model;
 use stq_routes::service::Service;
-use stq_static_resources::Translation;
+use stq_static_resources::{Currency, CurrencyType, Translation};
 
 use super::*;
+use config::FIAT_SELLER_CURRENCY;
 use graphql::context::Context;
 use graphql::models::*;
 use graphql::schema::cart_product::{
-    calculate_coupon_discount, calculate_delivery_cost_with_exchange_rate, calculate_product_price, get_exchange_rate,
+    calculate_coupon_discount, calculate_delivery_cost_with_exchange_rate, calculate_product_price,
+    calculate_product_price_without_discounts, get_exchange_rate,
 };
 use graphql::schema::coupon::get_coupon;
 
@@ -52,14 +55,29 @@ graphql_object!(CartStore: Context as "CartStore" |&self| {
         &self.cover
     }
 
-    field products_cost(&executor) -> FieldResult<f64> as "Products cost" {
+    field deprecated "Use productsCostMoney" products_cost(&executor) -> FieldResult<f64> as "Products cost" {
         let context = executor.context();
 
-        calculate_products_price(context, &self.products)
+        calculate_products_price(context, &self.products).map(|cost| decimal_to_f64(&cost))
     }
 
-    field products_cost_without_discounts() -> f64 as "Products without cost" {
-        calculate_products_price_without_discounts(&self.products)
+    field products_cost_money(&executor) -> FieldResult<Money> as "Products cost" {
+        let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
+
+        calculate_products_price(context, &self.products).map(|cost| Money::new(cost, currency))
+    }
+
+    field deprecated "Use productsCostWithoutDiscountsMoney" products_cost_without_discounts() -> FieldResult<f64>
+        as "Products without cost" {
+        calculate_products_price_without_discounts(&self.products).map(|cost| decimal_to_f64(&cost))
+    }
+
+    field products_cost_without_discounts_money(&executor) -> FieldResult<Money> as "Products without cost" {
+        let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
+
+        calculate_products_price_without_discounts(&self.products).map(|cost| Money::new(cost, currency))
     }
 
     field coupons(&executor) -> FieldResult<Vec<Coupon>> as "Coupons added user" {
@@ -75,28 +93,69 @@ graphql_object!(CartStore: Context as "CartStore" |&self| {
         })
     }
 
-    field coupons_discount(&executor) -> FieldResult<f64> as "Coupons discount" {
+    field deprecated "Use couponsDiscountMoney" coupons_discount(&executor) -> FieldResult<f64> as "Coupons discount" {
+        let context = executor.context();
+
+        calculate_coupons_discount(context, &self.products).map(|discount| decimal_to_f64(&discount))
+    }
+
+    field coupons_discount_money(&executor) -> FieldResult<Money> as "Coupons discount" {
+        let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
+
+        calculate_coupons_discount(context, &self.products).map(|discount| Money::new(discount, currency))
+    }
+
+    field deprecated "Use deliveryCostMoney" delivery_cost(&executor) -> FieldResult<Option<f64>> as "Delivery cost. Remote." {
+        let context = executor.context();
+
+        context.resilient("CartStore.deliveryCost", &delivery_cost_key(&self.products), || {
+            calculate_products_delivery_cost(context, &self.products)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
+    }
+
+    field delivery_cost_money(&executor) -> FieldResult<Option<Money>> as "Delivery cost. Remote." {
         let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
 
-        calculate_coupons_discount(context, &self.products)
+        context.resilient("CartStore.deliveryCostMoney", &delivery_cost_key(&self.products), || {
+            calculate_products_delivery_cost(context, &self.products)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
-    field delivery_cost(&executor) -> FieldResult<f64> as "Delivery cost" {
+    field deprecated "Use totalCostMoney" total_cost(&executor) -> FieldResult<Option<f64>> as "Total cost. Remote." {
         let context = executor.context();
 
-        calculate_products_delivery_cost(context, &self.products)
+        context.resilient("CartStore.totalCost", &delivery_cost_key(&self.products), || {
+            Ok(calculate_products_price(context, &self.products)? + calculate_products_delivery_cost(context, &self.products)?)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
     }
 
-    field total_cost(&executor) -> FieldResult<f64> as "Total cost" {
+    field total_cost_money(&executor) -> FieldResult<Option<Money>> as "Total cost. Remote." {
         let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
 
-        Ok(calculate_products_price(context, &self.products)? + calculate_products_delivery_cost(context, &self.products)?)
+        context.resilient("CartStore.totalCostMoney", &delivery_cost_key(&self.products), || {
+            Ok(calculate_products_price(context, &self.products)? + calculate_products_delivery_cost(context, &self.products)?)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
-    field total_cost_without_discounts(&executor) -> FieldResult<f64> as "Total without cost" {
+    field deprecated "Use totalCostWithoutDiscountsMoney" total_cost_without_discounts(&executor) -> FieldResult<Option<f64>>
+        as "Total without cost. Remote." {
         let context = executor.context();
 
-        Ok(calculate_products_price_without_discounts(&self.products) + calculate_products_delivery_cost(context, &self.products)?)
+        context.resilient("CartStore.totalCostWithoutDiscounts", &delivery_cost_key(&self.products), || {
+            Ok(calculate_products_price_without_discounts(&self.products)? + calculate_products_delivery_cost(context, &self.products)?)
+        }).map(|cost| cost.map(|cost| decimal_to_f64(&cost)))
+    }
+
+    field total_cost_without_discounts_money(&executor) -> FieldResult<Option<Money>> as "Total without cost. Remote." {
+        let context = executor.context();
+        let currency = products_currency(context, &self.products, self.currency_type);
+
+        context.resilient("CartStore.totalCostWithoutDiscountsMoney", &delivery_cost_key(&self.products), || {
+            Ok(calculate_products_price_without_discounts(&self.products)? + calculate_products_delivery_cost(context, &self.products)?)
+        }).map(|cost| cost.map(|cost| Money::new(cost, currency)))
     }
 
     field total_count() -> i32 as "Total products count" {
@@ -125,6 +184,10 @@ graphql_object!(Connection<CartStore, PageInfo>: Context as "CartStoresConnectio
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<CartStore>: Context as "CartStoresEdge" |&self| {
@@ -139,8 +202,8 @@ graphql_object!(Edge<CartStore>: Context as "CartStoresEdge" |&self| {
     }
 });
 
-pub fn calculate_products_price(context: &Context, products: &[CartProduct]) -> FieldResult<f64> {
-    products.iter().try_fold(0.0, |acc, x| {
+pub fn calculate_products_price(context: &Context, products: &[CartProduct]) -> FieldResult<BigDecimal> {
+    products.iter().try_fold(BigDecimal::default(), |acc, x| {
         if x.selected {
             Ok(acc + calculate_product_price(context, &x)?)
         } else {
@@ -149,18 +212,18 @@ pub fn calculate_products_price(context: &Context, products: &[CartProduct]) ->
     })
 }
 
-pub fn calculate_products_price_without_discounts(products: &[CartProduct]) -> f64 {
-    products.iter().fold(0.0, |acc, x| {
+pub fn calculate_products_price_without_discounts(products: &[CartProduct]) -> FieldResult<BigDecimal> {
+    products.iter().try_fold(BigDecimal::default(), |acc, x| {
         if x.selected {
-            acc + x.customer_price.0 * f64::from(x.quantity.0)
+            Ok(acc + calculate_product_price_without_discounts(&x)?)
         } else {
-            acc
+            Ok(acc)
         }
     })
 }
 
-pub fn calculate_coupons_discount(context: &Context, products: &[CartProduct]) -> FieldResult<f64> {
-    products.iter().try_fold(0.0, |acc, x| {
+pub fn calculate_coupons_discount(context: &Context, products: &[CartProduct]) -> FieldResult<BigDecimal> {
+    products.iter().try_fold(BigDecimal::default(), |acc, x| {
         if x.selected {
             Ok(acc + calculate_coupon_discount(context, &x)?)
         } else {
@@ -169,8 +232,42 @@ pub fn calculate_coupons_discount(context: &Context, products: &[CartProduct]) -
     })
 }
 
-pub fn calculate_products_delivery_cost(context: &Context, products: &[CartProduct]) -> FieldResult<f64> {
-    products.iter().try_fold(0.0, |acc, x| {
+/// Currency the customer pays for `products` in, for no products the customer's currency of `currency_type`
+pub fn products_currency(context: &Context, products: &[CartProduct], currency_type: Option<CurrencyType>) -> Currency {
+    if let Some(product) = products.first() {
+        return product.customer_currency;
+    }
+    match currency_type {
+        Some(CurrencyType::Crypto) => context.currency,
+        _ => context.fiat_currency,
+    }
+    .unwrap_or(FIAT_SELLER_CURRENCY)
+}
+
+/// Cache key of values depending on delivery cost, prices and applied coupons of `products`
+pub fn delivery_cost_key(products: &[CartProduct]) -> String {
+    products
+        .iter()
+        .map(|p| {
+            format!(
+                "{}:{:?}:{}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}",
+                p.id.0,
+                p.quantity,
+                p.selected,
+                p.delivery_method_id,
+                p.user_country_code,
+                p.customer_currency,
+                p.customer_price,
+                p.discount,
+                p.coupon_id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn calculate_products_delivery_cost(context: &Context, products: &[CartProduct]) -> FieldResult<BigDecimal> {
+    products.iter().try_fold(BigDecimal::default(), |acc, x| {
         if x.selected {
             let exchange_rate = get_exchange_rate(context, x)?;
             Ok(acc + calculate_delivery_cost_with_exchange_rate(context, &x, exchange_rate)?)
@@ -1,5 +1,4 @@
 //! File containing Coupon object of graphql schema
-use chrono::prelude::*;
 use futures::Future;
 use graphql::context::Context;
 use graphql::models::*;
@@ -13,9 +12,13 @@ use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_types::{CouponCode, CouponId, StoreId};
 
+use super::*;
+
 graphql_object!(Coupon: Context as "Coupon" |&self| {
     description: "Coupon info."
 
+    interfaces: [&Node]
+
     field id() -> GraphqlID as "Base64 Unique id"{
         ID::new(Service::Stores, Model::Coupon, self.id.0).to_string().into()
     }
@@ -56,24 +59,20 @@ graphql_object!(Coupon: Context as "Coupon" |&self| {
         0 // TODO: get from stores
     }
 
-    field expired_at() -> Option<String> as "Expired at" {
-        self.expired_at
-            .map(DateTime::<Utc>::from)
-            .map(|datetime| datetime.to_rfc3339())
+    field expired_at() -> Option<GraphQLDateTime> as "Expired at" {
+        self.expired_at.map(GraphQLDateTime::from)
     }
 
     field is_active() -> bool as "Is active" {
         self.is_active
     }
 
-    field created_at() -> String as "Created at" {
-        let datetime: DateTime<Utc> = self.created_at.into();
-        datetime.to_rfc3339()
+    field created_at() -> GraphQLDateTime as "Created at" {
+        self.created_at.into()
     }
 
-    field updated_at() -> String as "Updated at" {
-        let datetime: DateTime<Utc> = self.updated_at.into();
-        datetime.to_rfc3339()
+    field updated_at() -> GraphQLDateTime as "Updated at" {
+        self.updated_at.into()
     }
 
     field base_products(&executor) -> FieldResult<Vec<BaseProduct>> as "Base products coupon can be applied to" {
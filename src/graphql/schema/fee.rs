@@ -1,6 +1,7 @@
 //! File containing Fee object of graphql schema
 use graphql::context::Context;
 use graphql::models::*;
+use juniper::FieldResult;
 use juniper::ID as GraphqlID;
 
 use stq_routes::model::Model;
@@ -18,10 +19,14 @@ graphql_object!(Fee: Context as "Fee" |&self| {
         self.order_id.to_string()
     }
 
-    field amount() -> &f64 as "Amount" {
+    field deprecated "Use amountMoney" amount() -> &f64 as "Amount" {
         &self.amount
     }
 
+    field amount_money() -> FieldResult<Money> as "Amount" {
+        Money::from_f64(self.amount, self.currency)
+    }
+
     field status() -> &FeeStatus as "Status" {
         &self.status
     }
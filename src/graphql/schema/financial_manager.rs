@@ -1,6 +1,5 @@
 //! File containing financial manager object of graphql schema
 use juniper::FieldResult;
-use juniper::ID as GraphqlID;
 
 use graphql::context::Context;
 use graphql::models::*;
@@ -36,16 +35,13 @@ fn find_orders(
     let orders = context
         .get_billing_microservice()
         .orders_billing_info(skip, items_count, search_term)?;
-    let total_pages = std::cmp::max(0, orders.total_count as i32 - 1) / items_count + 1;
-    let orders_edges: Vec<Edge<OrderBillingInfo>> = orders
-        .orders
-        .into_iter()
-        .map(|order| Edge::new(GraphqlID::from(order.order.id.0.to_string()), order))
-        .collect();
+    let total_count = orders.total_count as i32;
+    let total_pages = std::cmp::max(0, total_count - 1) / items_count + 1;
+    let orders_edges = Edge::create_vec(orders.orders, skip);
     let page_info = PageInfoSegments {
         current_page,
         page_items_count: items_count,
         total_pages,
     };
-    Ok(Connection::new(orders_edges, page_info))
+    Ok(Connection::new(orders_edges, page_info).with_total_count(TotalCount::Known(total_count)))
 }
@@ -1,5 +1,4 @@
 //! File containing wizard store object of graphql schema
-use chrono::prelude::*;
 use futures::Future;
 use hyper::Method;
 use juniper::ID as GraphqlID;
@@ -26,18 +25,21 @@ graphql_object!(Invoice: Context as "Invoice" |&self| {
         self.get_orders(context)
     }
 
-    field amount() -> f64 as "amount"{
+    field deprecated "Use amountMoney" amount() -> f64 as "amount"{
         let multiplier = 10i64.pow(8 as u32) as f64;
 	    (self.amount.0 * multiplier).ceil() / multiplier
     }
 
+    field amount_money() -> FieldResult<Money> as "amount"{
+        Ok(Money::new(ceil_with_scale(decimal(self.amount.0)?, currency_scale(self.currency)), self.currency))
+    }
+
     field currency() -> &Currency as "currency"{
         &self.currency
     }
 
-    field price_reserved_due_date_time() -> String as "price reserved due to date time"{
-        let datetime: DateTime<Utc> = self.price_reserved.into();
-        datetime.to_rfc3339()
+    field price_reserved_due_date_time() -> GraphQLDateTime as "price reserved due to date time"{
+        self.price_reserved.into()
     }
 
     field state() -> &OrderState as "order state"{
@@ -52,10 +54,14 @@ graphql_object!(Invoice: Context as "Invoice" |&self| {
         &self.transactions
     }
 
-    field amount_captured() -> &f64 as "amount captured"{
+    field deprecated "Use amountCapturedMoney" amount_captured() -> &f64 as "amount captured"{
         &self.amount_captured.0
     }
 
+    field amount_captured_money() -> FieldResult<Money> as "amount captured"{
+        Money::from_f64(self.amount_captured.0, self.currency)
+    }
+
     field payment_intent(&executor) -> FieldResult<Option<PaymentIntent>> as "Stripe payment intent" {
         let context = executor.context();
 
@@ -1,6 +1,4 @@
 //! File containing search object of graphql schema
-use std::cmp;
-use std::str::FromStr;
 
 use futures::Future;
 use hyper::Method;
@@ -20,25 +18,21 @@ graphql_object!(MainPage: Context as "MainPage" |&self| {
 
     field find_most_viewed_products(&executor,
         first = None : Option<i32> as "First edges", 
-        after = None : Option<GraphqlID>  as "Offset from begining", 
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before", 
         search_term : MostViewedProductsInput as "Most viewed search pattern") 
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Find most viewed base products each one contains one variant." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/most_viewed?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1
+            window.offset,
+            window.count
             );
 
         let options = if let Some(mut options) = search_term.options.clone() {
@@ -57,22 +51,7 @@ graphql_object!(MainPage: Context as "MainPage" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
-            .map (|base_products| {
-                let mut base_product_edges = Edge::create_vec(base_products, offset);
-                let has_next_page = base_product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    base_product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(base_product_edges, page_info)
-            })
+            .map(|base_products| window.connection(base_products))
             .wait()
             .map(Some)
     }
@@ -80,25 +59,21 @@ graphql_object!(MainPage: Context as "MainPage" |&self| {
 
     field find_most_discount_products(&executor,
         first = None : Option<i32> as "First edges", 
-        after = None : Option<GraphqlID>  as "Offset from begining", 
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before", 
         search_term : MostDiscountProductsInput as "Most discount search pattern") 
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Find base products each one with most discount variant." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/most_discount?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1
+            window.offset,
+            window.count
             );
 
         let options = if let Some(mut options) = search_term.options.clone() {
@@ -117,22 +92,7 @@ graphql_object!(MainPage: Context as "MainPage" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
-            .map (|base_products| {
-                let mut base_product_edges = Edge::create_vec(base_products, offset);
-                let has_next_page = base_product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    base_product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(base_product_edges, page_info)
-            })
+            .map(|base_products| window.connection(base_products))
             .wait()
             .map(Some)
     }
@@ -1,5 +1,6 @@
 //! Mod containing all graphql schema
 pub mod admin;
+pub mod anonymous_session;
 pub mod attribute;
 pub mod available_packages;
 pub mod base_product;
@@ -19,6 +20,7 @@ pub mod financial_manager;
 pub mod invoice;
 pub mod main_page;
 pub mod moderator_comment;
+pub mod money;
 pub mod mutations;
 pub mod node;
 pub mod order;
@@ -41,6 +43,7 @@ pub mod subscription;
 pub mod user;
 pub mod user_delivery_address;
 pub mod user_role;
+pub mod user_session;
 pub mod warehouse;
 pub mod wizard_store;
 
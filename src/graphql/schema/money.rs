@@ -0,0 +1,17 @@
+//! File containing money object of graphql schema
+use stq_static_resources::Currency;
+
+use graphql::context::Context;
+use graphql::models::*;
+
+graphql_object!(Money: Context as "Money" |&self| {
+    description: "Amount of money in a currency."
+
+    field amount() -> String as "Decimal amount as a string, e.g. \"10.25\", rounded to cents for fiat currencies" {
+        self.rounded_amount().to_string()
+    }
+
+    field currency() -> Currency as "Currency" {
+        self.currency
+    }
+});
@@ -13,14 +13,13 @@ use serde_json;
 use uuid::Uuid;
 
 use stq_api::orders::{CartClient, Order};
-use stq_api::types::ApiFutureExt;
 use stq_api::warehouses::WarehouseClient;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_static_resources::{CurrencyType, Provider};
 use stq_types::{BaseProductId, CartItem, CouponCode, CouponId, ProductId, SagaId, StoreId, UserId, WarehouseId};
 
-use errors::into_graphql;
+use controller::anonymous_session::{self, SessionToken};
 use graphql::schema::base_product as base_product_module;
 use graphql::schema::buy_now;
 use graphql::schema::cart as cart_module;
@@ -162,6 +161,7 @@ graphql_object!(Mutation: Context |&self| {
 
     field changePassword(&executor, input: ChangePasswordInput as "Password change input.") -> FieldResult<ResetApplyActionOutput>  as "Changes user password." {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
         let url = format!("{}/{}/password_change",
             context.config.service_url(Service::Users),
             Model::User.to_url());
@@ -473,10 +473,19 @@ graphql_object!(Mutation: Context |&self| {
             context.config.service_url(Service::Users),
             Model::JWT.to_url());
 
-        let body: String = serde_json::to_string(&input)?.to_string();
+        let credentials = EmailCredentials {
+            email: input.email,
+            password: input.password,
+            session: context.new_user_session(),
+        };
+        let body: String = serde_json::to_string(&credentials)?.to_string();
 
-        context.request::<JWT>(Method::Post, url, Some(body))
-            .wait()
+        let jwt = context.request::<JWT>(Method::Post, url, Some(body))
+            .wait()?;
+
+        user_module::set_jwt_cookies(context, &jwt);
+
+        Ok(jwt)
     }
 
     field getJWTByProvider(&executor, input: CreateJWTProviderInput as "Create jwt input.") -> FieldResult<JWT> as "Get JWT Token by provider." {
@@ -489,15 +498,20 @@ graphql_object!(Mutation: Context |&self| {
         let mut additional_data = input.additional_data.unwrap_or_default().into();
         user_module::change_alpha2_to_alpha3(&context, &mut additional_data);
 
-        let oauth = ProviderOauth { token: input.token, additional_data: Some(additional_data)};
+        let oauth = ProviderOauth { token: input.token, additional_data: Some(additional_data), session: Some(context.new_user_session()) };
         let body: String = serde_json::to_string(&oauth)?;
 
-        context.request::<JWT>(Method::Post, url, Some(body))
-            .wait()
+        let jwt = context.request::<JWT>(Method::Post, url, Some(body))
+            .wait()?;
+
+        user_module::set_jwt_cookies(context, &jwt);
+
+        Ok(jwt)
     }
 
     field deprecated "do not use" renewJWT(&executor) -> FieldResult<JWT> as "Get JWT Token by email." {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
         let url = format!("{}/{}/renew",
             context.config.service_url(Service::Users),
             Model::JWT.to_url());
@@ -720,10 +734,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.set_quantity(customer, input.product_id.into(), input.value.into())
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_quantity(customer, input.product_id.into(), input.value.into())
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
@@ -747,10 +760,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.set_quantity(customer, input.product_id.into(), input.value.into())
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_quantity(customer, input.product_id.into(), input.value.into())
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code)).map(Some)
@@ -786,8 +798,7 @@ graphql_object!(Mutation: Context |&self| {
             return Ok(None);
         }
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let current_cart = rpc_client.get_cart(customer, currency_type).sync()?;
+        let current_cart = context.call_rest_api(Service::Orders, |client| client.get_cart(customer, currency_type))?;
 
         // validate used coupon
         let coupon_apply = current_cart.iter().any(|c| {
@@ -836,13 +847,10 @@ graphql_object!(Mutation: Context |&self| {
         }
 
         for product_id in products_for_cart {
-            let rpc_client = context.get_rest_api_client(Service::Orders);
-            rpc_client.add_coupon(customer, product_id, coupon.id).sync()?;
+            context.call_rest_api(Service::Orders, |client| client.add_coupon(customer, product_id, coupon.id))?;
         }
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products: Vec<_> = rpc_client.get_cart(customer, currency_type).sync()
-            .map_err(into_graphql)?
+        let products: Vec<_> = context.call_rest_api(Service::Orders, |client| client.get_cart(customer, currency_type))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
@@ -878,8 +886,7 @@ graphql_object!(Mutation: Context |&self| {
             return Ok(None);
         }
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let current_cart = rpc_client.get_cart(customer, currency_type).sync()?;
+        let current_cart = context.call_rest_api(Service::Orders, |client| client.get_cart(customer, currency_type))?;
 
         // validate used coupon
         let coupon_apply = current_cart.iter().any(|c| {
@@ -928,11 +935,10 @@ graphql_object!(Mutation: Context |&self| {
         }
 
         for product_id in products_for_cart {
-            rpc_client.add_coupon(customer, product_id, coupon.id).sync()?;
+            context.call_rest_api(Service::Orders, |client| client.add_coupon(customer, product_id, coupon.id))?;
         }
 
-        let products: Vec<_> = rpc_client.get_cart(customer, currency_type).sync()
-            .map_err(into_graphql)?
+        let products: Vec<_> = context.call_rest_api(Service::Orders, |client| client.get_cart(customer, currency_type))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code)).map(Some)
@@ -966,8 +972,7 @@ graphql_object!(Mutation: Context |&self| {
             ))
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products: Vec<CartItem> = rpc_client.delete_coupon(customer, coupon_id).sync()?
+        let products: Vec<CartItem> = context.call_rest_api(Service::Orders, |client| client.delete_coupon(customer, coupon_id))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
@@ -1001,8 +1006,7 @@ graphql_object!(Mutation: Context |&self| {
             ))
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products: Vec<CartItem> = rpc_client.delete_coupon(customer, coupon_id).sync()?
+        let products: Vec<CartItem> = context.call_rest_api(Service::Orders, |client| client.delete_coupon(customer, coupon_id))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code)).map(Some)
@@ -1025,10 +1029,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products: Vec<_> = rpc_client.set_selection(customer, input.product_id.into(), input.value)
-            .sync()
-            .map_err(into_graphql)?
+        let products: Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_selection(customer, input.product_id.into(), input.value)
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
@@ -1051,10 +1054,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products: Vec<_> = rpc_client.set_selection(customer, input.product_id.into(), input.value)
-            .sync()
-            .map_err(into_graphql)?
+        let products: Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_selection(customer, input.product_id.into(), input.value)
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code)).map(Some)
@@ -1077,10 +1079,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.set_comment(customer, input.product_id.into(), input.value)
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_comment(customer, input.product_id.into(), input.value)
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
@@ -1102,10 +1103,9 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.set_comment(customer, input.product_id.into(), input.value)
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| {
+                client.set_comment(customer, input.product_id.into(), input.value)
+            })?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code)).map(Some)
@@ -1166,10 +1166,7 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.delete_item(customer, input.product_id.into())
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| client.delete_item(customer, input.product_id.into()))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, None)
@@ -1192,10 +1189,7 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let products:Vec<_> = rpc_client.delete_item(customer, input.product_id.into())
-            .sync()
-            .map_err(into_graphql)?
+        let products:Vec<_> = context.call_rest_api(Service::Orders, |client| client.delete_item(customer, input.product_id.into()))?
             .into_iter().collect();
 
         cart_module::convert_products_to_cart(context, &products, Some(input.user_country_code))
@@ -1215,10 +1209,7 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        rpc_client.clear_cart(customer)
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Orders, |client| client.clear_cart(customer))
             .map(|_| convert_to_cart(vec![], &[], None))
     }
 
@@ -1236,10 +1227,7 @@ graphql_object!(Mutation: Context |&self| {
             ));
         };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        rpc_client.clear_cart(customer)
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Orders, |client| client.clear_cart(customer))
             .map(|_| convert_to_cart(vec![], &[], Some(user_country_code)))
     }
 
@@ -1388,10 +1376,7 @@ graphql_object!(Mutation: Context |&self| {
 
     field createWarehouse(&executor, input: CreateWarehouseInput as "Create warehouse input.") -> FieldResult<GraphQLWarehouse> as "Creates new warehouse." {
         let context = executor.context();
-        let rpc_client = context.get_rest_api_client(Service::Warehouses);
-        rpc_client.create_warehouse(input.into())
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Warehouses, |client| client.create_warehouse(input.into()))
             .map(GraphQLWarehouse)
     }
 
@@ -1409,8 +1394,7 @@ graphql_object!(Mutation: Context |&self| {
             ));
         }
 
-        let rpc_client = context.get_rest_api_client(Service::Warehouses);
-        Uuid::parse_str(&input.id)
+        parse_uuid(&input.id)
             .map_err(|_|
                 FieldError::new(
                     "Given id can not be parsed as Uuid",
@@ -1418,16 +1402,14 @@ graphql_object!(Mutation: Context |&self| {
                 )
             )
             .and_then(|id|{
-                rpc_client.update_warehouse(WarehouseId(id).into(), input.into())
-                    .sync()
-                    .map_err(into_graphql)
+                context.call_rest_api(Service::Warehouses, |client| client.update_warehouse(WarehouseId(id).into(), input.into()))
                     .map(|res| res.map(GraphQLWarehouse))
             })
     }
 
     field deleteWarehouse(&executor, id: String) -> FieldResult<Option<GraphQLWarehouse>>  as "Delete existing Warehouse." {
         let context = executor.context();
-        Uuid::parse_str(&id)
+        parse_uuid(&id)
             .map_err(|_|
                 FieldError::new(
                     "Given id can not be parsed as Uuid",
@@ -1435,26 +1417,20 @@ graphql_object!(Mutation: Context |&self| {
                 )
             )
             .and_then(|id|{
-                let rpc_client = context.get_rest_api_client(Service::Warehouses);
-                rpc_client.delete_warehouse(WarehouseId(id).into())
-                    .sync()
-                    .map_err(into_graphql)
+                context.call_rest_api(Service::Warehouses, |client| client.delete_warehouse(WarehouseId(id).into()))
                     .map(|res| res.map(GraphQLWarehouse))
             })
     }
 
     field deleteAllWarehouses(&executor) -> FieldResult<Vec<GraphQLWarehouse>>  as "Delete all Warehouses." {
         let context = executor.context();
-        let rpc_client = context.get_rest_api_client(Service::Warehouses);
-        rpc_client.delete_all_warehouses()
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Warehouses, |client| client.delete_all_warehouses())
             .map(|res| res.into_iter().map(GraphQLWarehouse).collect())
     }
 
     field setProductQuantityInWarehouse(&executor, input: ProductQuantityInput as "set Product Quantity In Warehouse input.") -> FieldResult<GraphQLStock> as "Set Product Quantity In Warehouse" {
         let context = executor.context();
-        Uuid::parse_str(&input.warehouse_id)
+        parse_uuid(&input.warehouse_id)
             .map_err(|_|
                 FieldError::new(
                     "Given id can not be parsed as Uuid",
@@ -1462,10 +1438,9 @@ graphql_object!(Mutation: Context |&self| {
                 )
             )
             .and_then(|id|{
-                let rpc_client = context.get_rest_api_client(Service::Warehouses);
-                rpc_client.set_product_in_warehouse(WarehouseId(id), input.product_id.into(), input.quantity.into())
-                    .sync()
-                    .map_err(into_graphql)
+                context.call_rest_api(Service::Warehouses, |client| {
+                        client.set_product_in_warehouse(WarehouseId(id), input.product_id.into(), input.quantity.into())
+                    })
                     .map(GraphQLStock)
             })
     }
@@ -1582,10 +1557,7 @@ graphql_object!(Mutation: Context |&self| {
     field upsertShipping(&executor, input: NewShippingInput as "New shipping input.") -> FieldResult<ShippingOutput> as "Upsert shipping for base product." {
         let context = executor.context();
 
-        let rpc_client = context.get_rest_api_client(Service::Warehouses);
-        let warehouses = rpc_client.get_warehouses_for_store(input.store_id.into())
-            .sync()
-            .map_err(into_graphql)?;
+        let warehouses = context.call_rest_api(Service::Warehouses, |client| client.get_warehouses_for_store(input.store_id.into()))?;
 
         let delivery_from = warehouses.into_iter().nth(0)
             .map(|warehouse|
@@ -1879,6 +1851,7 @@ graphql_object!(Mutation: Context |&self| {
     }
     field refreshJWT(&executor) -> FieldResult<String> as "Refresh JWT Token." {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
         let url = format!("{}/{}/refresh",
             context.config.service_url(Service::Users),
             Model::JWT.to_url());
@@ -1906,8 +1879,12 @@ graphql_object!(Mutation: Context |&self| {
         if let Some(ref payload) = context.user {
             let body: String = serde_json::to_string(payload)?.to_string();
 
-            context.request::<String>(Method::Post, url, Some(body))
-                .wait()
+            let result = context.request::<String>(Method::Post, url, Some(body))
+                .wait()?;
+
+            user_module::clear_jwt_cookies(context);
+
+            Ok(result)
         } else {
              return Err(FieldError::new(
                 "No jwt token in request header",
@@ -1916,6 +1893,81 @@ graphql_object!(Mutation: Context |&self| {
         }
     }
 
+    field startSession(&executor) -> FieldResult<SessionToken> as "Starts anonymous session or prolongs the current one." {
+        let context = executor.context();
+
+        if let Some(ref session_config) = context.config.anonymous_session {
+            let session_token = anonymous_session::issue(session_config, context.session_id)?;
+            anonymous_session::set_session_token(&context.response_cookies, session_config, &session_token);
+
+            Ok(session_token)
+        } else {
+            Err(FieldError::new(
+                "Anonymous sessions are not issued by gateway",
+                graphql_value!({ "code": 300, "details": { "Session id can be sent in SessionId header as is." }}),
+            ))
+        }
+    }
+
+    field revokeSession(&executor, input: RevokeSessionInput as "Revoke session input.") -> FieldResult<UserSession>
+        as "Revokes JWT tokens issued for a single device. Users can revoke their own sessions, superusers any session." {
+        let context = executor.context();
+        let user_id = match context.user {
+            Some(ref payload) => payload.user_id,
+            None => return Err(FieldError::new(
+                "Could not revoke session for unauthorized user.",
+                graphql_value!({ "code": 100, "details": { "No user id in request header." }}),
+            )),
+        };
+        let user_session_id = Uuid::parse_str(&input.id)?;
+        let url = format!("{}/user_sessions/{}",
+            context.config.service_url(Service::Users),
+            user_session_id.hyphenated());
+
+        // sessions of other users are reported as missing, so that their ids can not be probed
+        let session = context.request::<Option<UserSession>>(Method::Get, url.clone(), None)
+            .wait()?;
+        match session {
+            Some(ref session) if session.user_id == user_id || context.permissions().is_superuser()? => {}
+            _ => return Err(FieldError::new(
+                "Session not found.",
+                graphql_value!({ "code": 100, "details": { "Current user has no session with such id." }}),
+            )),
+        }
+
+        let session = context.request::<UserSession>(Method::Delete, url, None)
+            .wait()?;
+        context.active_sessions.forget(session.id);
+
+        if context.user.as_ref().and_then(|payload| payload.user_session_id) == Some(session.id) {
+            user_module::clear_jwt_cookies(context);
+        }
+
+        Ok(session)
+    }
+
+    field impersonateUser(&executor, input: ImpersonateUserInput as "Impersonate user input.") -> FieldResult<JWT> as "Get short-lived JWT Token to act on behalf of a user. Available for superusers only." {
+        let context = executor.context();
+        context.permissions().deny_impersonated()?;
+
+        if !context.permissions().is_superuser()? {
+            return Err(FieldError::new(
+                "Impersonation is forbidden.",
+                graphql_value!({ "code": 114, "type": "IMPERSONATION_FORBIDDEN", "details": { "Only superuser can impersonate users." }}),
+            ));
+        }
+
+        let url = format!("{}/{}/impersonate",
+            context.config.service_url(Service::Users),
+            Model::JWT.to_url());
+
+        let payload = ImpersonationPayload { user_id: UserId(input.user_id), ttl_sec: IMPERSONATION_JWT_TTL_SEC };
+        let body: String = serde_json::to_string(&payload)?;
+
+        context.request::<JWT>(Method::Post, url, Some(body))
+            .wait()
+    }
+
     field confirmOrder(&executor,
                         input: OrderConfirmedInput as "Confirm order input object", ) -> FieldResult<Option<GraphQLOrder>> as "Confirm order for seller" {
         let context = executor.context();
@@ -1955,6 +2007,7 @@ graphql_object!(Mutation: Context |&self| {
     field createInternationalBillingInfo(&executor, input: NewInternationalBillingInfoInput as "Create international billing info for a store")
     -> FieldResult<InternationalBillingInfo> as "Created international billing info" {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
 
         let billing = context.get_billing_microservice();
         billing.create_international_billing_info(input)
@@ -1963,6 +2016,7 @@ graphql_object!(Mutation: Context |&self| {
     field updateInternationalBillingInfo(&executor, input: UpdateInternationalBillingInfoInput as "Update international billing info for a store")
     -> FieldResult<InternationalBillingInfo> as "Updated international billing info" {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
 
         let billing = context.get_billing_microservice();
         billing.update_international_billing_info(input)
@@ -1971,6 +2025,7 @@ graphql_object!(Mutation: Context |&self| {
     field createRussiaBillingInfo(&executor, input: NewRussiaBillingInfoInput as "Create russia billing info for a store")
     -> FieldResult<RussiaBillingInfo> as "Created russia billing info" {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
 
         let billing = context.get_billing_microservice();
         billing.create_russia_billing_info(input)
@@ -1979,6 +2034,7 @@ graphql_object!(Mutation: Context |&self| {
     field updateRussiaBillingInfo(&executor, input: UpdateRussiaBillingInfoInput as "Update russia billing info for a store")
     -> FieldResult<RussiaBillingInfo> as "Updated russia billing info" {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
 
         let billing = context.get_billing_microservice();
         billing.update_russia_billing_info(input)
@@ -2018,6 +2074,7 @@ graphql_object!(Mutation: Context |&self| {
         input: PayOutCryptoToSellerInput,
     ) -> FieldResult<Payout> as "Payout info" {
         let context = executor.context();
+        context.permissions().deny_impersonated()?;
 
         payout::run_pay_out_crypto_to_seller_mutation(context, input)
     }
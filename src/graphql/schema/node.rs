@@ -1,12 +1,29 @@
 //! File containing node object of graphql schema
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use futures::Future;
+use hyper::Method;
 use juniper::ID as GraphqlID;
+use juniper::{FieldError, FieldResult};
 
 use stq_routes::model::Model;
 use stq_routes::service::Service;
+use stq_types::{
+    BaseProductId, CategoryId, CouponId, OrderId, OrderIdentifier, OrderSlug, PayoutId, ProductId, WarehouseId, WarehouseIdentifier,
+    WarehouseSlug,
+};
 
 use super::*;
 use graphql::context::Context;
+use graphql::microservice::requests::{GetBaseProductsRequest, GetProductsRequest};
 use graphql::models::*;
+use graphql::schema::cart as cart_module;
+use graphql::schema::category as category_module;
+use graphql::schema::coupon as coupon_module;
+use graphql::schema::order as order_module;
+use graphql::schema::stock as stock_module;
+use graphql::schema::warehouse as warehouse_module;
 
 pub struct StaticNodeIds;
 
@@ -20,7 +37,7 @@ pub enum Node {
     SearchCategory(SearchCategory),
     Attribute(Attribute),
     CustomAttribute(CustomAttribute),
-    Cart,
+    Cart(Box<Cart>),
     CartProduct(CartProduct),
     CartStore(CartStore),
     Warehouse(Box<GraphQLWarehouse>),
@@ -30,6 +47,7 @@ pub enum Node {
     Package(Packages),
     CompanyPackage(CompaniesPackages),
     Payout(Payout),
+    Coupon(Coupon),
 }
 
 graphql_interface!(Node: Context as "Node" |&self| {
@@ -50,14 +68,18 @@ graphql_interface!(Node: Context as "Node" |&self| {
             Node::CustomAttribute(CustomAttribute { ref id, .. })  => ID::new(Service::Stores, Model::CustomAttribute, id.0).to_string().into(),
             Node::CartProduct(CartProduct { ref id, .. })  => ID::new(Service::Orders, Model::CartProduct, id.0).to_string().into(),
             Node::CartStore(CartStore { ref id, .. })  => ID::new(Service::Orders, Model::CartStore, id.0).to_string().into(),
-            Node::Cart => ID::new(Service::Orders, Model::Cart, 0).to_string().into(),
-            Node::Warehouse(ref w)  => w.0.id.to_string().into(),
-            Node::Order(ref o)  => o.0.id.to_string().into(),
-            Node::Stock(ref s)  => format!("{}{}", s.0.warehouse_id, s.0.product_id).into(),
+            Node::Cart(_) => ID::new(Service::Orders, Model::Cart, 0).to_string().into(),
+            Node::Warehouse(ref w)  => ID::with_uuid(Service::Warehouses, Model::Warehouse, w.0.id.0).to_string().into(),
+            Node::Order(ref o)  => ID::with_uuid(Service::Orders, Model::Order, o.0.id.0).to_string().into(),
+            Node::Stock(ref s)  => {
+                let raw_id = RawId::Composite(vec![RawId::Uuid(s.0.warehouse_id.0), RawId::Int(s.0.product_id.0)]);
+                ID::with_key(Service::Warehouses, Model::Stock, raw_id).to_string().into()
+            },
             Node::Company(Company { ref id, .. })  => ID::new(Service::Delivery, Model::Company, id.0).to_string().into(),
             Node::Package(Packages { ref id, .. })  => ID::new(Service::Delivery, Model::Package, id.0).to_string().into(),
             Node::CompanyPackage(CompaniesPackages { ref id, .. })  => ID::new(Service::Delivery, Model::CompanyPackage, id.0).to_string().into(),
-            Node::Payout(Payout { ref id, .. }) => id.to_string().into(),
+            Node::Payout(Payout { ref id, .. }) => ID::with_gateway_model(Service::Billing, GatewayModel::Payout, RawId::Uuid(*id.inner())).to_string().into(),
+            Node::Coupon(Coupon { ref id, .. }) => ID::new(Service::Stores, Model::Coupon, id.0).to_string().into(),
         }
     }
 
@@ -79,8 +101,9 @@ graphql_interface!(Node: Context as "Node" |&self| {
         &Company => match *self { Node::Company(ref h) => Some(h), _ => None },
         &Packages => match *self { Node::Package(ref h) => Some(h), _ => None },
         &CompaniesPackages => match *self { Node::CompanyPackage(ref h) => Some(h), _ => None },
-        &Cart => None::<&Cart>,
+        &Cart => match *self { Node::Cart(ref h) => Some(&**h), _ => None },
         &Payout => match *self { Node::Payout(ref h) => Some(h), _ => None },
+        &Coupon => match *self { Node::Coupon(ref h) => Some(h), _ => None },
     }
 });
 
@@ -90,3 +113,204 @@ graphql_object!(StaticNodeIds: Context as "StaticNodeIds" |&self| {
         QUERY_NODE_ID
     }
 });
+
+/// Refetches node by its global id
+pub fn resolve_node(context: &Context, id: &str) -> FieldResult<Option<Node>> {
+    if id == QUERY_NODE_ID.to_string() {
+        return Ok(Some(Node::Query(Query {})));
+    }
+    let identifier = ID::from_str(id)?;
+    fetch_node(context, identifier, &mut None)
+}
+
+/// Refetches nodes by global ids keeping their order, missing nodes are `None`, malformed ids and failed fetches are errors of their own.
+/// Base products and products are fetched with one request each, cart nodes share one cart,
+/// other nodes are fetched one by one as microservices have no batch routes for them.
+pub fn resolve_nodes(context: &Context, ids: &[GraphqlID]) -> FieldResult<Vec<FieldResult<Option<Node>>>> {
+    let records_limit = context.config.gateway.records_limit;
+    if ids.len() > records_limit {
+        return Err(FieldError::new(
+            "Too many ids",
+            graphql_value!({ "code": 300, "details": { format!("At most {} nodes can be fetched at once.", records_limit) }}),
+        ));
+    }
+
+    let identifiers = ids
+        .iter()
+        .map(|id| {
+            if **id == QUERY_NODE_ID.to_string() {
+                Ok(None)
+            } else {
+                ID::from_str(id).map(Some)
+            }
+        })
+        .collect::<Vec<FieldResult<Option<ID>>>>();
+
+    let base_product_ids = identifiers
+        .iter()
+        .filter_map(|identifier| match *identifier {
+            Ok(Some(ID {
+                service: Service::Stores,
+                model: IdModel::Route(Model::BaseProduct),
+                raw_id: RawId::Int(raw_id),
+            })) => Some(BaseProductId(raw_id)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let base_products = if base_product_ids.is_empty() {
+        HashMap::new()
+    } else {
+        context
+            .get_stores_microservice()
+            .get_base_products(GetBaseProductsRequest { ids: base_product_ids })?
+            .into_iter()
+            .map(|base_product| (base_product.id.0, base_product))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let product_ids = identifiers
+        .iter()
+        .filter_map(|identifier| match *identifier {
+            Ok(Some(ID {
+                service: Service::Stores,
+                model: IdModel::Route(Model::Product),
+                raw_id: RawId::Int(raw_id),
+            })) => Some(ProductId(raw_id)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let products = if product_ids.is_empty() {
+        HashMap::new()
+    } else {
+        context
+            .get_stores_microservice()
+            .get_products(GetProductsRequest { ids: product_ids })?
+            .into_iter()
+            .map(|product| (product.id.0, product))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let mut cart = None;
+    let nodes = identifiers
+        .into_iter()
+        .map(|identifier| match identifier {
+            Err(e) => Err(e),
+            Ok(None) => Ok(Some(Node::Query(Query {}))),
+            Ok(Some(ID {
+                service: Service::Stores,
+                model: IdModel::Route(Model::BaseProduct),
+                raw_id: RawId::Int(raw_id),
+            })) => Ok(base_products.get(&raw_id).cloned().map(Node::BaseProduct)),
+            Ok(Some(ID {
+                service: Service::Stores,
+                model: IdModel::Route(Model::Product),
+                raw_id: RawId::Int(raw_id),
+            })) => Ok(products.get(&raw_id).cloned().map(Node::Product)),
+            Ok(Some(identifier)) => fetch_node(context, identifier, &mut cart),
+        })
+        .collect::<Vec<_>>();
+    Ok(nodes)
+}
+
+fn fetch_node(context: &Context, identifier: ID, cart: &mut Option<Cart>) -> FieldResult<Option<Node>> {
+    match (&identifier.service, &identifier.model, &identifier.raw_id) {
+        (&Service::Users, &IdModel::Route(Model::User), _) => context
+            .request::<Option<User>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::User)),
+        (&Service::Stores, &IdModel::Route(Model::Store), _) => context
+            .request::<Option<Store>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Box::new).map(Node::Store)),
+        (&Service::Stores, &IdModel::Route(Model::Product), _) => context
+            .request::<Option<Product>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::Product)),
+        (&Service::Stores, &IdModel::Route(Model::BaseProduct), _) => context
+            .request::<Option<BaseProduct>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::BaseProduct)),
+        (&Service::Stores, &IdModel::Route(Model::Category), _) => context
+            .request::<Option<Category>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::Category)),
+        (&Service::Stores, &IdModel::Route(Model::SearchCategory), &RawId::Int(raw_id)) => {
+            category_module::try_get_category(context, CategoryId(raw_id)).map(|res| res.map(SearchCategory).map(Node::SearchCategory))
+        }
+        (&Service::Stores, &IdModel::Route(Model::Attribute), _) => context
+            .request::<Option<Attribute>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::Attribute)),
+        (&Service::Stores, &IdModel::Route(Model::CustomAttribute), _) => context
+            .request::<Option<CustomAttribute>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::CustomAttribute)),
+        (&Service::Stores, &IdModel::Route(Model::Coupon), &RawId::Int(raw_id)) => {
+            coupon_module::try_get_coupon(context, CouponId(raw_id)).map(|res| res.map(Node::Coupon))
+        }
+        (&Service::Orders, &IdModel::Route(Model::Cart), _) => current_cart(context, cart).map(|cart| Some(Node::Cart(Box::new(cart)))),
+        (&Service::Orders, &IdModel::Route(Model::CartStore), &RawId::Int(raw_id)) => {
+            current_cart(context, cart).map(|cart| cart.inner.into_iter().find(|store| store.id.0 == raw_id).map(Node::CartStore))
+        }
+        (&Service::Orders, &IdModel::Route(Model::CartProduct), &RawId::Int(raw_id)) => current_cart(context, cart).map(|cart| {
+            cart.inner
+                .into_iter()
+                .flat_map(|store| store.products)
+                .find(|product| product.id.0 == raw_id)
+                .map(Node::CartProduct)
+        }),
+        (&Service::Orders, &IdModel::Route(Model::Order), &RawId::Uuid(uuid)) => {
+            order_module::try_get_order(context, OrderIdentifier::Id(OrderId(uuid))).map(|res| res.map(Box::new).map(Node::Order))
+        }
+        (&Service::Orders, &IdModel::Route(Model::Order), &RawId::Int(slug)) => {
+            order_module::try_get_order(context, OrderIdentifier::Slug(OrderSlug(slug))).map(|res| res.map(Box::new).map(Node::Order))
+        }
+        (&Service::Warehouses, &IdModel::Route(Model::Warehouse), &RawId::Uuid(uuid)) => {
+            warehouse_module::try_get_warehouse(context, WarehouseIdentifier::Id(WarehouseId(uuid)))
+                .map(|res| res.map(Box::new).map(Node::Warehouse))
+        }
+        (&Service::Warehouses, &IdModel::Route(Model::Warehouse), &RawId::Int(slug)) => {
+            warehouse_module::try_get_warehouse(context, WarehouseIdentifier::Slug(WarehouseSlug(slug)))
+                .map(|res| res.map(Box::new).map(Node::Warehouse))
+        }
+        (&Service::Warehouses, &IdModel::Route(Model::Stock), &RawId::Composite(ref keys)) if keys.len() == 2 => {
+            let warehouse_id = WarehouseId(keys[0].uuid()?);
+            let product_id = ProductId(keys[1].int()?);
+            stock_module::try_get_stock_for_warehouse(context, warehouse_id, product_id).map(|res| res.map(GraphQLStock).map(Node::Stock))
+        }
+        (&Service::Delivery, &IdModel::Route(Model::Company), _) => context
+            .request::<Option<Company>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::Company)),
+        (&Service::Delivery, &IdModel::Route(Model::Package), _) => context
+            .request::<Option<Packages>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::Package)),
+        (&Service::Delivery, &IdModel::Route(Model::CompanyPackage), _) => context
+            .request::<Option<CompaniesPackages>>(Method::Get, identifier.url(&context.config), None)
+            .wait()
+            .map(|res| res.map(Node::CompanyPackage)),
+        (&Service::Billing, &IdModel::Gateway(GatewayModel::Payout), &RawId::Uuid(uuid)) => context
+            .get_billing_microservice()
+            .get_payout(PayoutId::new(uuid))
+            .map(|res| res.map(Node::Payout)),
+        _ => Err(unknown_model(&identifier)),
+    }
+}
+
+fn unknown_model(identifier: &ID) -> FieldError {
+    FieldError::new(
+        format!("Could not get model from {} microservice.", identifier.service),
+        graphql_value!({ "code": 300, "details": { format!("Unknown model {} with id {}", identifier.model, identifier.raw_id) }}),
+    )
+}
+
+/// Cart of the current user or session, fetched once for all cart nodes
+fn current_cart(context: &Context, cache: &mut Option<Cart>) -> FieldResult<Cart> {
+    if let Some(ref cart) = *cache {
+        return Ok(cart.clone());
+    }
+    let cart = cart_module::get_cart(context, None)?;
+    *cache = Some(cart.clone());
+    Ok(cart)
+}
@@ -1,25 +1,19 @@
 //! File containing PageInfo object of graphql schema
-use std::cmp;
 use std::collections::HashMap;
-use std::str::FromStr;
 
+use bigdecimal::BigDecimal;
 use futures::Future;
 use hyper::Method;
 use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
-use uuid::Uuid;
 
-use stq_api::{
-    orders::{CartClient, Order, OrderClient},
-    types::ApiFutureExt,
-};
+use stq_api::orders::{CartClient, Order, OrderClient};
 use stq_routes::{model::Model, service::Service};
 use stq_static_resources::CurrencyType;
 use stq_static_resources::{Currency, OrderState};
 use stq_types::{CouponId, OrderId, OrderIdentifier, ProductSellerPrice};
 
 use super::*;
-use errors::into_graphql;
 use graphql::context::Context;
 use graphql::microservice::requests::*;
 use graphql::models::*;
@@ -35,8 +29,12 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
 
     interfaces: [&Node]
 
-    field id() -> GraphqlID as "Unique id"{
-        self.0.id.to_string().into()
+    field id() -> GraphqlID as "Base64 Unique id"{
+        ID::with_uuid(Service::Orders, Model::Order, self.0.id.0).to_string().into()
+    }
+
+    field raw_id() -> String as "Unique uuid"{
+        self.0.id.to_string()
     }
 
     field state() -> &OrderState as "Order State"{
@@ -97,22 +95,24 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
         &self.0.quantity.0
     }
 
-    field price() -> &f64 as "Price" {
+    field deprecated "Use priceMoney" price() -> &f64 as "Price" {
         &self.0.price.0
     }
 
+    field price_money() -> FieldResult<Money> as "Price" {
+        Money::from_f64(self.0.price.0, self.0.currency)
+    }
+
     field currency() -> &Currency as "Currency" {
         &self.0.currency
     }
 
-    field subtotal() -> f64 as "Subtotal" {
+    field deprecated "Use subtotalMoney" subtotal() -> FieldResult<f64> as "Subtotal" {
+        calculate_subtotal(&self.0).map(|subtotal| decimal_to_f64(&subtotal))
+    }
 
-        // product_discount - here is the already calculated discount amount
-        if let Some(discount) = self.0.product_discount.map(|c| c.0).filter(|discount| *discount > ZERO_DISCOUNT) {
-            (self.0.price.0 * f64::from(self.0.quantity.0)) - discount
-        } else {
-            self.0.price.0 * f64::from(self.0.quantity.0)
-        }
+    field subtotal_money() -> FieldResult<Money> as "Subtotal" {
+        calculate_subtotal(&self.0).map(|subtotal| Money::new(subtotal, self.0.currency))
     }
 
     field coupon(&executor) -> FieldResult<Option<Coupon>> as "Coupon added user" {
@@ -129,18 +129,36 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
         &self.0.coupon_percent
     }
 
-    field coupon_discount() -> Option<f64> as "Coupon discount" {
+    field deprecated "Use couponDiscountMoney" coupon_discount() -> Option<f64> as "Coupon discount" {
         self.0.coupon_discount.map(|c| c.0)
     }
 
-    field product_discount() -> Option<f64> as "Product discount" {
+    field coupon_discount_money() -> FieldResult<Option<Money>> as "Coupon discount" {
+        match self.0.coupon_discount {
+            Some(discount) => Money::from_f64(discount.0, self.0.currency).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    field deprecated "Use productDiscountMoney" product_discount() -> Option<f64> as "Product discount" {
         self.0.product_discount.map(|c| c.0)
     }
 
-    field total_amount() -> f64 as "Total amount" {
+    field product_discount_money() -> FieldResult<Option<Money>> as "Product discount" {
+        match self.0.product_discount {
+            Some(discount) => Money::from_f64(discount.0, self.0.currency).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    field deprecated "Use totalAmountMoney" total_amount() -> f64 as "Total amount" {
         self.0.total_amount.0
     }
 
+    field total_amount_money() -> FieldResult<Money> as "Total amount" {
+        Money::from_f64(self.0.total_amount.0, self.0.currency)
+    }
+
     field slug() -> &i32 as "Slug" {
         &self.0.slug.0
     }
@@ -153,10 +171,14 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
         &self.0.delivery_company
     }
 
-    field delivery_price() -> &f64 as "Delivery price" {
+    field deprecated "Use deliveryPriceMoney" delivery_price() -> &f64 as "Delivery price" {
         &self.0.delivery_price
     }
 
+    field delivery_price_money() -> FieldResult<Money> as "Delivery price" {
+        Money::from_f64(self.0.delivery_price, self.0.currency)
+    }
+
     field deprecated "use deliveryCompany and deliveryPrice" company_package_id() -> Option<i32> as "Selected package raw id" {
         self.0.company_package_id.map(|v| v.0)
     }
@@ -175,8 +197,8 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
         &self.0.track_id
     }
 
-    field created_at() -> String as "Creation time" {
-        self.0.created_at.to_rfc3339()
+    field created_at() -> GraphQLDateTime as "Creation time" {
+        self.0.created_at.into()
     }
 
     field receiver_name() -> &str as "Receiver name" {
@@ -205,46 +227,18 @@ graphql_object!(GraphQLOrder: Context as "Order" |&self| {
 
     field history(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning")
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before")
             -> FieldResult<Option<Connection<OrderHistoryItem, PageInfo>>> as "History" {
 
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
-
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        rpc_client.get_order_diff(self.0.slug.into())
-            .sync()
-            .map_err(into_graphql)
-            .map (|items| {
-                let mut item_edges: Vec<Edge<OrderHistoryItem>> = items
-                    .into_iter()
-                    .skip(offset as usize)
-                    .take(count as usize)
-                    .enumerate()
-                    .map(|(i, item)| Edge::new(juniper::ID::from((i as i32 + offset).to_string()), OrderHistoryItem(item)))
-                    .collect();
-                let has_next_page = item_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    item_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  item_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = item_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(item_edges, page_info)
-            })
+        let args = ConnectionArgs::new(first, after, last, before);
+
+        context.call_rest_api(Service::Orders, |client| client.get_order_diff(self.0.slug.into()))
+            .and_then(|items| args.slice(items.into_iter().map(OrderHistoryItem).collect(), records_limit as i32))
             .map(Some)
     }
 
@@ -294,25 +288,7 @@ graphql_object!(CreateOrdersOutput: Context as "CreateOrdersOutput" |&self| {
     field deprecated "use cartV2" cart(&executor, currency_type: Option<CurrencyType> as "Currency type") -> FieldResult<Option<Cart>> as "Fetches cart products." {
         let context = executor.context();
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let fut = if let Some(session_id) = context.session_id {
-            if let Some(ref user) = context.user {
-                rpc_client.merge(session_id.into(), user.user_id.into(), currency_type)
-            } else {
-                rpc_client.get_cart(session_id.into(), currency_type)
-            }
-        } else if let Some(ref user) = context.user {
-            rpc_client.get_cart(user.user_id.into(), currency_type)
-        }  else {
-            return Err(FieldError::new(
-                "Could not get users cart.",
-                graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
-            ));
-        };
-
-        let products: Vec<_> = fut
-            .sync()
-            .map_err(into_graphql)?.into_iter().collect();
+        let products = cart_module::get_cart_products(context, currency_type)?;
 
         cart_module::convert_products_to_cart(context, &products, None).map(Some)
     }
@@ -320,25 +296,7 @@ graphql_object!(CreateOrdersOutput: Context as "CreateOrdersOutput" |&self| {
     field cart_v2(&executor, user_country_code: String, currency_type: Option<CurrencyType> as "Currency type") -> FieldResult<Option<Cart>> as "Fetches cart products." {
         let context = executor.context();
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let fut = if let Some(session_id) = context.session_id {
-            if let Some(ref user) = context.user {
-                rpc_client.merge(session_id.into(), user.user_id.into(), currency_type)
-            } else {
-                rpc_client.get_cart(session_id.into(), currency_type)
-            }
-        } else if let Some(ref user) = context.user {
-            rpc_client.get_cart(user.user_id.into(), currency_type)
-        }  else {
-            return Err(FieldError::new(
-                "Could not get users cart.",
-                graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
-            ));
-        };
-
-        let products: Vec<_> = fut
-            .sync()
-            .map_err(into_graphql)?.into_iter().collect();
+        let products = cart_module::get_cart_products(context, currency_type)?;
 
         cart_module::convert_products_to_cart(context, &products, Some(user_country_code)).map(Some)
     }
@@ -354,6 +312,10 @@ graphql_object!(Connection<GraphQLOrder, PageInfo>: Context as "OrdersConnection
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<GraphQLOrder>: Context as "OrdersEdge" |&self| {
@@ -378,6 +340,10 @@ graphql_object!(Connection<OrderHistoryItem, PageInfo>: Context as "OrderHistory
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<OrderHistoryItem>: Context as "OrderHistoryItemsEdge" |&self| {
@@ -437,10 +403,14 @@ graphql_object!(OrderProduct: Context as "OrderProduct" |&self| {
         &self.0.cashback
     }
 
-    field price() -> &f64 as "Price" {
+    field deprecated "Use priceMoney" price() -> &f64 as "Price" {
         &self.0.price.0
     }
 
+    field price_money() -> FieldResult<Money> as "Price" {
+        Money::from_f64(self.0.price.0, self.0.currency)
+    }
+
     field pre_order() -> &bool as "Pre-order" {
         &self.0.pre_order
     }
@@ -498,6 +468,10 @@ graphql_object!(Connection<OrderProduct, PageInfo>: Context as "OrderProductsCon
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<OrderProduct>: Context as "OrderProductsEdge" |&self| {
@@ -525,11 +499,9 @@ pub fn run_create_orders_mutation_v1(context: &Context, input: CreateOrderInput)
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let current_cart = rpc_client
-        .get_cart(user.user_id.into(), Some(input.currency.currency_type()))
-        .sync()
-        .map_err(into_graphql)?;
+    let current_cart = context.call_rest_api(Service::Orders, |client| {
+        client.get_cart(user.user_id.into(), Some(input.currency.currency_type()))
+    })?;
 
     if let Some(cart_item) = current_cart.iter().find(|p| p.delivery_method_id.is_none()) {
         return Err(FieldError::new(
@@ -616,11 +588,9 @@ pub fn run_create_orders_mutation(context: &Context, input: CreateOrderInputV2)
         )
     })?;
 
-    let rpc_client = context.get_rest_api_client(Service::Orders);
-    let current_cart = rpc_client
-        .get_cart(user.user_id.into(), Some(input.currency.currency_type()))
-        .sync()
-        .map_err(into_graphql)?;
+    let current_cart = context.call_rest_api(Service::Orders, |client| {
+        client.get_cart(user.user_id.into(), Some(input.currency.currency_type()))
+    })?;
 
     if let Some(cart_item) = current_cart.iter().find(|p| p.delivery_method_id.is_none()) {
         return Err(FieldError::new(
@@ -727,6 +697,15 @@ pub fn get_order(context: &Context, order_id: OrderIdentifier) -> FieldResult<Gr
     })
 }
 
+/// Subtotal of the order, `product_discount` is the already calculated discount amount
+fn calculate_subtotal(order: &Order) -> FieldResult<BigDecimal> {
+    let subtotal = decimal(order.price.0)? * BigDecimal::from(i64::from(order.quantity.0));
+    match order.product_discount.map(|c| c.0).filter(|discount| *discount > ZERO_DISCOUNT) {
+        Some(discount) => Ok(subtotal - decimal(discount)?),
+        None => Ok(subtotal),
+    }
+}
+
 pub fn validate_products_fiat<'a>(products: impl Iterator<Item = &'a ProductSellerPrice>) -> FieldResult<()> {
     let mut currencies = products.map(|p| p.currency);
 
@@ -757,7 +736,7 @@ pub fn run_confirm_order_mutation(context: &Context, input: OrderConfirmedInput)
 
 pub fn run_set_paid_to_seller_order_state_mutation(context: &Context, input: PaidToSellerOrderStateInput) -> FieldResult<()> {
     let saga = context.get_saga_microservice();
-    let order_id = Uuid::parse_str(&input.order_id).map(OrderId)?;
+    let order_id = parse_uuid(&input.order_id).map(OrderId)?;
     let state = OrderPaymentState {
         state: PaymentState::PaidToSeller,
     };
@@ -771,7 +750,7 @@ pub fn run_create_dispute_mutation(context: &Context, input: CreateDisputeInput)
 
 pub fn run_charge_fee_mutation(context: &Context, input: ChargeFeeInput) -> FieldResult<Fee> {
     let billing = context.get_billing_microservice();
-    let order_id = Uuid::parse_str(&input.order_id).map(OrderId)?;
+    let order_id = parse_uuid(&input.order_id).map(OrderId)?;
     billing.create_charge_fee_by_oder(order_id)
 }
 
@@ -779,7 +758,7 @@ pub fn run_charge_fees_mutation(context: &Context, input: ChargeFeesInput) -> Fi
     let order_ids = input
         .order_ids
         .into_iter()
-        .map(|order_id| Uuid::parse_str(&order_id).map(OrderId))
+        .map(|order_id| parse_uuid(&order_id).map(OrderId))
         .collect::<Result<_, _>>()?;
     let input = FeesPayByOrdersRequest { order_ids };
     context.get_billing_microservice().create_charge_fee_by_oders(input)
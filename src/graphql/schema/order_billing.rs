@@ -27,14 +27,22 @@ graphql_object!(OrderBillingInfo: Context as "OrderBillingInfo" |&self| {
         self.order.seller_currency
     }
 
-    field total_amount() -> f64 {
+    field deprecated "Use totalAmountMoney" total_amount() -> f64 {
         self.order.total_amount
     }
 
-    field cashback_amount() -> f64 {
+    field total_amount_money() -> FieldResult<Money> as "Total amount in seller currency" {
+        Money::from_f64(self.order.total_amount, self.order.seller_currency)
+    }
+
+    field deprecated "Use cashbackAmountMoney" cashback_amount() -> f64 {
         self.order.cashback_amount
     }
 
+    field cashback_amount_money() -> FieldResult<Money> as "Cashback amount in seller currency" {
+        Money::from_f64(self.order.cashback_amount, self.order.seller_currency)
+    }
+
     field invoice_id() -> GraphqlID as "Base64 invoice id" {
         self.order.invoice_id.to_string().into()
     }
@@ -43,10 +51,17 @@ graphql_object!(OrderBillingInfo: Context as "OrderBillingInfo" |&self| {
         self.order.store_id.0
     }
 
-    field stripe_fee() -> &Option<f64> as "Stripe fee" {
+    field deprecated "Use stripeFeeMoney" stripe_fee() -> &Option<f64> as "Stripe fee" {
         &self.order.stripe_fee
     }
 
+    field stripe_fee_money() -> FieldResult<Option<Money>> as "Stripe fee in seller currency" {
+        match self.order.stripe_fee {
+            Some(fee) => Money::from_f64(fee, self.order.seller_currency).map(Some),
+            None => Ok(None),
+        }
+    }
+
     field store(&executor) -> FieldResult<Option<Store>> as "Store" {
          executor
         .context()
@@ -92,6 +107,10 @@ graphql_object!(Connection<OrderBillingInfo, PageInfoSegments>: Context as "Orde
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<OrderBillingInfo>: Context as "OrderBillingInfoEdge" |&self| {
@@ -116,6 +135,10 @@ graphql_object!(Connection<OrderBilling, PageInfoSegments>: Context as "OrderBil
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<OrderBilling>: Context as "OrderBillingEdge" |&self| {
@@ -139,14 +162,22 @@ graphql_object!(OrderBilling: Context as "OrderBilling" |&self| {
         self.seller_currency
     }
 
-    field total_amount() -> f64 {
+    field deprecated "Use totalAmountMoney" total_amount() -> f64 {
         self.total_amount
     }
 
-    field cashback_amount() -> f64 {
+    field total_amount_money() -> FieldResult<Money> as "Total amount in seller currency" {
+        Money::from_f64(self.total_amount, self.seller_currency)
+    }
+
+    field deprecated "Use cashbackAmountMoney" cashback_amount() -> f64 {
         self.cashback_amount
     }
 
+    field cashback_amount_money() -> FieldResult<Money> as "Cashback amount in seller currency" {
+        Money::from_f64(self.cashback_amount, self.seller_currency)
+    }
+
     field invoice_id() -> GraphqlID as "Base64 invoice id" {
         self.invoice_id.to_string().into()
     }
@@ -173,10 +204,17 @@ graphql_object!(OrderBilling: Context as "OrderBilling" |&self| {
         .get_fee_by_order_id(self.id)
     }
 
-    field stripe_fee() -> &Option<f64> as "Stripe fee" {
+    field deprecated "Use stripeFeeMoney" stripe_fee() -> &Option<f64> as "Stripe fee" {
         &self.stripe_fee
     }
 
+    field stripe_fee_money() -> FieldResult<Option<Money>> as "Stripe fee in seller currency" {
+        match self.stripe_fee {
+            Some(fee) => Money::from_f64(fee, self.seller_currency).map(Some),
+            None => Ok(None),
+        }
+    }
+
 });
 
 graphql_object!(ProxyCompanyBillingInfo: Context as "ProxyCompanyBillingInfo" |&self| {
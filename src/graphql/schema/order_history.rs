@@ -40,8 +40,8 @@ graphql_object!(OrderHistoryItem: Context as "OrderHistoryItem" |&self| {
             .wait()
     }
 
-    field committed_at() -> String as "Committed at time" {
-        self.0.committed_at.to_rfc3339()
+    field committed_at() -> GraphQLDateTime as "Committed at time" {
+        self.0.committed_at.into()
     }
 
     field comment() -> Option<String> as "Comment" {
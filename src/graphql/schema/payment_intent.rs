@@ -1,6 +1,7 @@
 //! File containing PaymentIntent object of graphql schema
 use graphql::context::Context;
 use graphql::models::*;
+use juniper::FieldResult;
 use juniper::ID as GraphqlID;
 
 use stq_static_resources::Currency;
@@ -12,14 +13,22 @@ graphql_object!(PaymentIntent: Context as "PaymentIntent" |&self| {
         self.id.to_string().into()
     }
 
-    field amount() -> f64 as "Amount" {
+    field deprecated "Use amountMoney" amount() -> f64 as "Amount" {
         self.amount
     }
 
-    field amount_received() -> f64 as "Amount received" {
+    field amount_money() -> FieldResult<Money> as "Amount" {
+        Money::from_f64(self.amount, self.currency)
+    }
+
+    field deprecated "Use amountReceivedMoney" amount_received() -> f64 as "Amount received" {
         self.amount_received
     }
 
+    field amount_received_money() -> FieldResult<Money> as "Amount received" {
+        Money::from_f64(self.amount_received, self.currency)
+    }
+
     field client_secret() -> &Option<String> as "Client secret" {
         &self.client_secret
     }
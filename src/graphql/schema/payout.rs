@@ -1,4 +1,5 @@
 use juniper::{FieldError, FieldResult, ID as GraphqlID};
+use stq_routes::service::Service;
 use stq_static_resources::Currency;
 
 use super::*;
@@ -10,22 +11,32 @@ graphql_object!(Payout: Context as "Payout" |&self| {
 
     interfaces: [&Node]
 
-    field id() -> GraphqlID as "Unique id" {
-        self.id.to_string().into()
+    field id() -> GraphqlID as "Base64 Unique id" {
+        ID::with_gateway_model(Service::Billing, GatewayModel::Payout, RawId::Uuid(*self.id.inner())).to_string().into()
     }
 
-    field gross_amount() -> f64 as "Gross payout amount without fees" {
-        self.gross_amount.to_string().parse::<f64>().unwrap_or(0.0)
+    field raw_id() -> String as "Unique uuid" {
+        self.id.to_string()
     }
 
-    field net_amount() -> f64 as "Net payout amount with fees subtracted" {
-        self.net_amount.to_string().parse::<f64>().unwrap_or(0.0)
+    field deprecated "Use grossAmountMoney" gross_amount() -> f64 as "Gross payout amount without fees" {
+        decimal_to_f64(&self.gross_amount)
+    }
+
+    field gross_amount_money() -> Money as "Gross payout amount without fees" {
+        Money::new(self.gross_amount.clone(), self.currency())
+    }
+
+    field deprecated "Use netAmountMoney" net_amount() -> f64 as "Net payout amount with fees subtracted" {
+        decimal_to_f64(&self.net_amount)
+    }
+
+    field net_amount_money() -> Money as "Net payout amount with fees subtracted" {
+        Money::new(self.net_amount.clone(), self.currency())
     }
 
     field currency() -> Currency as "Currency of the payout" {
-        match &self.target {
-            PayoutTarget::CryptoWallet(target) => target.currency,
-        }
+        self.currency()
     }
 
     field wallet_address() -> Option<String> as "Target wallet address" {
@@ -34,23 +45,29 @@ graphql_object!(Payout: Context as "Payout" |&self| {
         }
     }
 
-    field blockchain_fee() -> Option<f64> as "Blockchain fee for the transaction" {
+    field deprecated "Use blockchainFeeMoney" blockchain_fee() -> Option<f64> as "Blockchain fee for the transaction" {
         match &self.target {
-            PayoutTarget::CryptoWallet(target) => Some(target.blockchain_fee.to_string().parse::<f64>().unwrap_or(0.0)),
+            PayoutTarget::CryptoWallet(target) => Some(decimal_to_f64(&target.blockchain_fee)),
         }
     }
 
-    field initiated_at() -> String as "Payout initiation time" {
+    field blockchain_fee_money() -> Option<Money> as "Blockchain fee for the transaction" {
+        match &self.target {
+            PayoutTarget::CryptoWallet(target) => Some(Money::new(target.blockchain_fee.clone(), target.currency)),
+        }
+    }
+
+    field initiated_at() -> GraphQLDateTime as "Payout initiation time" {
         match &self.status {
-            PayoutStatus::Processing { initiated_at } => initiated_at.format("%+").to_string(),
-            PayoutStatus::Completed { initiated_at, .. } => initiated_at.format("%+").to_string(),
+            PayoutStatus::Processing { initiated_at } => (*initiated_at).into(),
+            PayoutStatus::Completed { initiated_at, .. } => (*initiated_at).into(),
         }
     }
 
-    field completed_at() -> Option<String> as "Payout completion time" {
+    field completed_at() -> Option<GraphQLDateTime> as "Payout completion time" {
         match &self.status {
             PayoutStatus::Processing { .. } => None,
-            PayoutStatus::Completed { completed_at, .. } => Some(completed_at.format("%+").to_string()),
+            PayoutStatus::Completed { completed_at, .. } => Some((*completed_at).into()),
         }
     }
 
@@ -70,8 +87,12 @@ graphql_object!(PayoutCalculation: Context as "PayoutCalculation" |&self| {
         self.currency
     }
 
-    field gross_amount() -> f64 as "Gross amount of the payout calculation" {
-        self.gross_amount.to_string().parse::<f64>().unwrap_or(0.0)
+    field deprecated "Use grossAmountMoney" gross_amount() -> f64 as "Gross amount of the payout calculation" {
+        decimal_to_f64(&self.gross_amount)
+    }
+
+    field gross_amount_money() -> Money as "Gross amount of the payout calculation" {
+        Money::new(self.gross_amount.clone(), self.currency)
     }
 
     field blockchain_fee_options() -> &[BlockchainFeeOption] as "Available blockchain fee options to select" {
@@ -98,28 +119,24 @@ graphql_object!(PayoutsByStoreId: Context as "PayoutsByStoreId" |&self| {
 graphql_object!(Balances: Context as "StoreBalance" |&self| {
     description: "Store billing balance"
 
-    field stq() -> f64 as "STQ Balance" {
-        self.currencies.get(&Currency::STQ)
-        .map(|amount| amount.to_string().parse::<f64>().unwrap_or(0.0))
-        .unwrap_or(0.0)
+    field deprecated "Use amounts" stq() -> f64 as "STQ Balance" {
+        self.currencies.get(&Currency::STQ).map(decimal_to_f64).unwrap_or(0.0)
+    }
+
+    field deprecated "Use amounts" btc(&executor) -> f64 as "BTC Balance" {
+        self.currencies.get(&Currency::BTC).map(decimal_to_f64).unwrap_or(0.0)
     }
 
-    field btc(&executor) -> f64 as "BTC Balance" {
-        self.currencies.get(&Currency::BTC)
-        .map(|amount| amount.to_string().parse::<f64>().unwrap_or(0.0))
-        .unwrap_or(0.0)
+    field deprecated "Use amounts" eth(&executor) -> f64 as "ETH Balance" {
+        self.currencies.get(&Currency::ETH).map(decimal_to_f64).unwrap_or(0.0)
     }
 
-    field eth(&executor) -> f64 as "ETH Balance" {
-        self.currencies.get(&Currency::ETH)
-        .map(|amount| amount.to_string().parse::<f64>().unwrap_or(0.0))
-        .unwrap_or(0.0)
+    field deprecated "Use amounts" eur(&executor) -> f64 as "EUR Balance" {
+        self.currencies.get(&Currency::EUR).map(decimal_to_f64).unwrap_or(0.0)
     }
 
-    field eur(&executor) -> f64 as "EUR Balance" {
-        self.currencies.get(&Currency::EUR)
-        .map(|amount| amount.to_string().parse::<f64>().unwrap_or(0.0))
-        .unwrap_or(0.0)
+    field amounts() -> Vec<Money> as "Balances in every currency of the store" {
+        self.currencies.iter().map(|(currency, amount)| Money::new(amount.clone(), *currency)).collect()
     }
 
 });
@@ -141,6 +141,10 @@ graphql_object!(Connection<Product, PageInfo>: Context as "ProductsConnection" |
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<Product>: Context as "ProductsEdge" |&self| {
@@ -215,7 +219,7 @@ pub fn get_seller_price(context: &Context, product_id: ProductId) -> FieldResult
 
 pub fn run_update_product_mutation(context: &Context, input: UpdateProductWithAttributesInput) -> FieldResult<Product> {
     let identifier = ID::from_str(&*input.id)?;
-    let product_id = ProductId(identifier.raw_id);
+    let product_id = ProductId(identifier.raw_id.int()?);
 
     let url = identifier.url(&context.config);
 
@@ -1,22 +1,16 @@
 //! File containing query object of graphql schema
-use std::str::FromStr;
-
 use futures::Future;
 use hyper::Method;
 use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
-use uuid::Uuid;
 
-use stq_api::orders::CartClient;
-use stq_api::types::ApiFutureExt;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_static_resources::currency::Currency;
 use stq_static_resources::{Language, LanguageGraphQl, OrderState, TemplateVariant};
-use stq_types::{BaseProductId, OrderId, ProductId, WarehouseId};
+use stq_types::{BaseProductId, ProductId};
 
 use super::*;
-use errors::into_graphql;
 use graphql::context::Context;
 use graphql::microservice::requests::{GetBaseProductsRequest, GetProductsRequest};
 use graphql::microservice::CalculatePayoutPayload;
@@ -26,7 +20,6 @@ use graphql::schema::cart as cart_module;
 use graphql::schema::warehouse as warehouse_module;
 use schema::buy_now as buy_now_module;
 use schema::category as category_module;
-use schema::order as order_module;
 use schema::user as user_module;
 
 pub const QUERY_NODE_ID: i32 = 1;
@@ -54,6 +47,12 @@ graphql_object!(Query: Context |&self| {
 
     - 200 - there was a network error while connecting to microservice.
 
+    - 201 - microservice is unavailable, its circuit breaker is open.
+
+    - 202 - operation deadline exceeded.
+
+    - 203 - field is temporarily unavailable, the rest of the response is valid.
+
     - 300 - there was a parse error - that usually means that
     graphql couldn't parse api json response
     (probably because of mismatching types on graphql and microservice)
@@ -85,132 +84,21 @@ graphql_object!(Query: Context |&self| {
     }
 
     field node(&executor, id: GraphqlID as "Base64 Id of a node.") -> FieldResult<Option<Node>> as "Fetches graphql interface node by Base64 id."  {
-        let context = executor.context();
-        if *id == QUERY_NODE_ID.to_string() {
-             Ok(Some(Node::Query(Query{})))
-        } else {
-            let identifier = ID::from_str(&*id)?;
-            match (&identifier.service, &identifier.model) {
-                (&Service::Users, &Model::User) => {
-                    context.request::<Option<User>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::User))
-                },
-                (&Service::Users, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from users microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                },
-                (&Service::Stores, &Model::Store) => {
-                    context.request::<Option<Store>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Box::new).map(Node::Store))
-                },
-                (&Service::Stores, &Model::Product) => {
-                    context.request::<Option<Product>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::Product))
-                },
-                (&Service::Stores, &Model::BaseProduct) => {
-                    context.request::<Option<BaseProduct>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::BaseProduct))
-                },
-                (&Service::Stores, &Model::Category) => {
-                    context.request::<Option<Category>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::Category))
-                },
-                (&Service::Stores, &Model::Attribute) => {
-                    context.request::<Option<Attribute>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::Attribute))
-                },
-                (&Service::Stores, &Model::CustomAttribute) => {
-                    context.request::<Option<CustomAttribute>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::CustomAttribute))
-                },
-                (&Service::Stores, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from stores microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                },
-                (Service::Orders, &Model::Order) => {
-                    Uuid::parse_str(&id.to_string())
-                        .map_err(|_|
-                            FieldError::new(
-                                "Given id can not be parsed as Uuid",
-                                graphql_value!({ "parse_error": "Order id must be uuid" })
-                            )
-                        )
-                        .and_then(|id|{
-                            order_module::try_get_order(context, OrderId(id).into())
-                            .map(|res| res.map(Box::new).map(Node::Order))
-                        })
-                },
-                (Service::Orders, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from orders microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                },
-                (&Service::Warehouses, &Model::Warehouse) => {
-                    Uuid::parse_str(&id.to_string())
-                        .map_err(|_|
-                            FieldError::new(
-                                "Given id can not be parsed as Uuid",
-                                graphql_value!({ "parse_error": "Warehouse id must be uuid" })
-                            )
-                        )
-                        .and_then(|id|{
-                            warehouse_module::try_get_warehouse(context, WarehouseId(id).into())
-                            .map(|res| res.map(Box::new).map(Node::Warehouse))
-                        })
-                },
-                (&Service::Warehouses, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from warehouses microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                }
-                (&Service::Notifications, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from notifications microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                }
-                (&Service::Billing, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from billing microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                }
-                (&Service::Delivery, &Model::Company) => {
-                    context.request::<Option<Company>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::Company))
-                },
-                (&Service::Delivery, &Model::Package) => {
-                    context.request::<Option<Packages>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::Package))
-                },
-                (&Service::Delivery, &Model::CompanyPackage) => {
-                    context.request::<Option<CompaniesPackages>>(Method::Get, identifier.url(&context.config), None)
-                        .wait()
-                        .map(|res| res.map(Node::CompanyPackage))
-                },
-                (&Service::Delivery, _) => {
-                    Err(FieldError::new(
-                        "Could not get model from delivery microservice.",
-                        graphql_value!({ "internal_error": "Unknown model" })
-                    ))
-                }
-            }
-        }
+        resolve_node(executor.context(), &id)
+    }
+
+    field nodes(&executor, ids: Vec<GraphqlID> as "Base64 Ids of nodes.") -> FieldResult<Vec<Option<Node>>>
+        as "Fetches graphql interface nodes by Base64 ids, keeping the order of ids. \
+            Missing nodes are null, malformed ids and failed fetches are null with an error each. \
+            Base products and products are fetched in one request each, other nodes one by one." {
+        let nodes = resolve_nodes(executor.context(), &ids)?;
+        Ok(nodes
+            .into_iter()
+            .map(|node| node.unwrap_or_else(|e| {
+                executor.push_error(e);
+                None
+            }))
+            .collect())
     }
 
     field languages(&executor) -> Vec<LanguageGraphQl> as "Fetches languages." {
@@ -545,25 +433,7 @@ graphql_object!(Query: Context |&self| {
     field cart_v2(&executor, user_country_code: String as "User country code.") -> FieldResult<Option<Cart>> as "Fetches cart with country." {
         let context = executor.context();
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        let fut = if let Some(session_id) = context.session_id {
-            if let Some(ref user) = context.user {
-                rpc_client.merge(session_id.into(), user.user_id.into(), None)
-            } else {
-                rpc_client.get_cart(session_id.into(), None)
-            }
-        } else if let Some(ref user) = context.user {
-            rpc_client.get_cart(user.user_id.into(), None)
-        }  else {
-            return Err(FieldError::new(
-                "Could not get users cart.",
-                graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
-            ));
-        };
-
-        let products: Vec<_> = fut
-            .sync()
-            .map_err(into_graphql)?.into_iter().collect();
+        let products = cart_module::get_cart_products(context, None)?;
 
         cart_module::convert_products_to_cart(context, &products, Some(user_country_code)).map(Some)
     }
@@ -1,6 +1,4 @@
 //! File containing search object of graphql schema
-use std::cmp;
-use std::str::FromStr;
 
 use futures::future;
 use futures::Future;
@@ -22,7 +20,9 @@ graphql_object!(Search: Context as "Search" |&self| {
 
     field find_product(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         search_term : SearchProductInput as "Search pattern",
         visibility: Option<Visibility> as "Specifies allowed visibility of the base product"
     ) -> FieldResult<Option<Connection<BaseProduct, PageInfoProductsSearch>>> as "Find products by name using relay connection." {
@@ -30,21 +30,14 @@ graphql_object!(Search: Context as "Search" |&self| {
         let context = executor.context();
         let visibility = visibility.unwrap_or_default();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/search?offset={}&count={}&visibility={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             visibility
         );
 
@@ -59,25 +52,18 @@ graphql_object!(Search: Context as "Search" |&self| {
 
         let body = serde_json::to_string(&search_term)?;
 
+        let count_url = format!("{}/{}/search/filters/count",
+            context.config.service_url(Service::Stores),
+            Model::BaseProduct.to_url(),
+        );
+        let total_count = TotalCount::post(count_url, body.clone());
+
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
             .map (|products| {
-                let mut product_edges = Edge::create_vec(products, offset);
-
                 let search_filters = ProductsSearchFilters::new(search_term);
-                let has_next_page = product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfoProductsSearch {
-                    has_next_page,
-                    has_previous_page,
-                    search_filters: Some(search_filters),
-                    start_cursor,
-                    end_cursor};
-                Connection::new(product_edges, page_info)
+                window.connection(products)
+                    .with_page_info(|page_info| PageInfoProductsSearch::new(page_info, Some(search_filters)))
+                    .with_total_count(total_count)
             })
             .wait()
             .map(Some)
@@ -85,26 +71,22 @@ graphql_object!(Search: Context as "Search" |&self| {
 
     field auto_complete_product_name(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         name : String as "Name part")
             -> FieldResult<Option<Connection<String, PageInfo>>> as "Finds products full name by part of the name." {
 
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/auto_complete?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             );
 
         let search_term = AutoCompleteProductNameInput {
@@ -116,29 +98,16 @@ graphql_object!(Search: Context as "Search" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<String>>(Method::Post, url, Some(body))
-            .map (|full_names| {
-                let mut full_name_edges = Edge::create_vec(full_names, offset);
-                let has_next_page = full_name_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    full_name_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  full_name_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = full_name_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(full_name_edges, page_info)
-            })
+            .map(|full_names| window.connection(full_names))
             .wait()
             .map(Some)
     }
 
     field find_store(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         search_term : SearchStoreInput as "Search store input",
         visibility: Option<Visibility> as "Specifies allowed visibility of the store"
     ) -> FieldResult<Option<Connection<Store, PageInfoStoresSearch>>> as "Finds stores by name using relay connection." {
@@ -146,14 +115,8 @@ graphql_object!(Search: Context as "Search" |&self| {
         let context = executor.context();
         let visibility = visibility.unwrap_or_default();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let body = serde_json::to_string(&search_term)?;
 
@@ -162,33 +125,25 @@ graphql_object!(Search: Context as "Search" |&self| {
         let url = format!("{}/{}/search?offset={}&count={}&visibility={}",
             context.config.service_url(Service::Stores),
             Model::Store.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             visibility
         );
 
+        let count_url = format!("{}/{}/search/filters/count",
+            context.config.service_url(Service::Stores),
+            Model::Store.to_url(),
+        );
+        let total_count = TotalCount::post(count_url, body.clone());
+
         context.request::<Vec<Store>>(Method::Post, url, Some(body))
             .and_then (|stores| {
-                let mut store_edges = Edge::create_vec(stores, offset);
-                let has_next_page = store_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    store_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  store_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = store_edges.iter().last().map(|e| e.cursor.clone());
-
                 let search_filters = StoresSearchFilters::new(search_term);
-
-                let page_info = PageInfoStoresSearch {
-                        has_next_page,
-                        has_previous_page,
-                        search_filters,
-                        start_cursor,
-                        end_cursor
-                    };
-
-                future::ok(Connection::new(store_edges, page_info))
+                future::ok(
+                    window.connection(stores)
+                        .with_page_info(|page_info| PageInfoStoresSearch::new(page_info, search_filters))
+                        .with_total_count(total_count)
+                )
             })
             .wait()
             .map(Some)
@@ -196,46 +151,25 @@ graphql_object!(Search: Context as "Search" |&self| {
 
     field auto_complete_store_name(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         name : String as "Name part")
             -> FieldResult<Option<Connection<String, PageInfo>>> as "Finds stores full name by part of the name." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/auto_complete?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::Store.to_url(),
-            offset,
-            count + 1
+            window.offset,
+            window.count
             );
 
         context.request::<Vec<String>>(Method::Post, url, Some(name))
-            .map (|full_names| {
-                let mut full_name_edges = Edge::create_vec(full_names, offset);
-                let has_next_page = full_name_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    full_name_edges.pop();
-                };
-                let start_cursor =  full_name_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = full_name_edges.iter().last().map(|e| e.cursor.clone());
-
-                let has_previous_page = true;
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(full_name_edges, page_info)
-            })
+            .map(|full_names| window.connection(full_names))
             .wait()
             .map(Some)
     }
@@ -19,12 +19,9 @@ graphql_object!(GraphQLStock: Context as "Stock" |&self| {
 
     interfaces: [&Node]
 
-    field id(&executor) -> GraphqlID as "Base64 Unique id"{
-        let context = executor.context();
-
-        let id = format!("{}{}", self.0.warehouse_id, self.0.product_id);
-
-        id.into()
+    field id() -> GraphqlID as "Base64 Unique id"{
+        let raw_id = RawId::Composite(vec![RawId::Uuid(self.0.warehouse_id.0), RawId::Int(self.0.product_id.0)]);
+        ID::with_key(Service::Warehouses, Model::Stock, raw_id).to_string().into()
     }
 
     field product_id() -> &i32 as "Product id"{
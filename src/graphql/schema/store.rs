@@ -3,7 +3,6 @@
 use std::cmp;
 use std::str::FromStr;
 
-use chrono::prelude::*;
 use futures::Future;
 use hyper::Method;
 use juniper;
@@ -12,14 +11,12 @@ use juniper::{FieldError, FieldResult};
 use serde_json;
 
 use stq_api::orders::{OrderClient, OrderSearchTerms};
-use stq_api::types::ApiFutureExt;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_static_resources::{Language, ModerationStatus, Translation};
 use stq_types::{OrderIdentifier, OrderSlug, ProductId, StoreId};
 
 use super::*;
-use errors::into_graphql;
 use graphql::context::Context;
 use graphql::models::*;
 use graphql::schema::warehouse as warehouse_module;
@@ -77,14 +74,12 @@ graphql_object!(Store: Context as "Store" |&self| {
         &self.slug
     }
 
-    field created_at() -> String as "Created at" {
-        let datetime: DateTime<Utc> = self.created_at.into();
-        datetime.to_rfc3339()
+    field created_at() -> GraphQLDateTime as "Created at" {
+        self.created_at.into()
     }
 
-    field updated_at() -> String as "Updated at" {
-        let datetime: DateTime<Utc> = self.updated_at.into();
-        datetime.to_rfc3339()
+    field updated_at() -> GraphQLDateTime as "Updated at" {
+        self.updated_at.into()
     }
 
     field cover() -> &Option<String> as "Cover" {
@@ -151,13 +146,11 @@ graphql_object!(Store: Context as "Store" |&self| {
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Fetches base products of the store." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|val| ID::from_str(&*val).map(|id| id.raw_id + 1).ok())
-            .unwrap_or_default();
         let visibility = visibility.unwrap_or(Visibility::Active);
 
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
+        let offset = window.after.map(|raw_id| raw_id + 1).unwrap_or_default();
 
             let url = match skip_base_prod_id {
                 None => format!(
@@ -166,7 +159,7 @@ graphql_object!(Store: Context as "Store" |&self| {
                         Model::Store.to_url(),
                         self.id,
                         offset,
-                        count + 1,
+                        window.count,
                         visibility,
                     ),
                 Some(id) => format!(
@@ -176,34 +169,22 @@ graphql_object!(Store: Context as "Store" |&self| {
                         self.id,
                         id,
                         offset,
-                        count + 1,
+                        window.count,
                         visibility,
                     )
             };
 
+            let count_url = format!(
+                "{}/{}/{}/products/count",
+                &context.config.service_url(Service::Stores),
+                Model::Store.to_url(),
+                self.id,
+            );
+
             context.request::<Vec<BaseProduct>>(Method::Get, url, None)
-                .map (|base_products| {
-                    let mut base_product_edges: Vec<Edge<BaseProduct>> =  vec![];
-                    for base_product in base_products {
-                        let edge = Edge::new(
-                                juniper::ID::from(ID::new(Service::Stores, Model::BaseProduct, base_product.id.0).to_string()),
-                                base_product.clone()
-                            );
-                        base_product_edges.push(edge);
-                    }
-                    let has_next_page = base_product_edges.len() as i32 > count;
-                    if has_next_page {
-                        base_product_edges.pop();
-                    };
-                    let has_previous_page = true;
-                    let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                    let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                    let page_info = PageInfo {
-                        has_next_page,
-                        has_previous_page,
-                        start_cursor,
-                        end_cursor};
-                    Connection::new(base_product_edges, page_info)
+                .map(|base_products| {
+                    window.connection(base_products, |base_product| base_product.id.0)
+                        .with_total_count(TotalCount::get(count_url))
                 })
                 .wait()
                 .map(Some)
@@ -255,32 +236,6 @@ graphql_object!(Store: Context as "Store" |&self| {
         let records_limit = context.config.gateway.records_limit;
         let count = cmp::min(items_count, records_limit as i32);
 
-        let created_from = match search_term_options.created_from.clone() {
-            Some(value) => {
-                match value.parse() {
-                    Ok(v) => Some(v),
-                    Err(_) => return Err(FieldError::new(
-                        "Parsing created_from error",
-                        graphql_value!({ "code": 300, "details": { "created_from has wrong format." }}),
-                    )),
-                }
-            },
-            None => None
-        };
-
-        let created_to = match search_term_options.created_to.clone() {
-            Some(value) => {
-                match value.parse() {
-                    Ok(v) => Some(v),
-                    Err(_) => return Err(FieldError::new(
-                        "Parsing created_to error",
-                        graphql_value!({ "code": 300, "details": { "created_to has wrong format." }}),
-                    )),
-                }
-            },
-            None => None
-        };
-
         let customer = search_term_options.email.clone().and_then(|email| {
             let url = format!("{}/{}/by_email?email={}",
                 context.config.service_url(Service::Users),
@@ -297,30 +252,25 @@ graphql_object!(Store: Context as "Store" |&self| {
                 slug: search_term_options.slug.map(OrderSlug),
                 customer,
                 store: Some(self.id),
-                created_from,
-                created_to,
+                created_from: search_term_options.created_from.map(|datetime| datetime.0),
+                created_to: search_term_options.created_to.map(|datetime| datetime.0),
                 payment_status: search_term_options.payment_status,
                 state: search_term_options.order_status,
                 ..OrderSearchTerms::default()
             };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        rpc_client.search(search_term)
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Orders, |client| client.search(search_term))
             .map(|res| res.into_iter().map(GraphQLOrder).collect())
             .map (move |orders: Vec<GraphQLOrder>| {
-                let total_pages = (orders.iter().count() as f32 / items_count as f32).ceil() as i32;
+                let total_count = orders.len() as i32;
+                let total_pages = (total_count as f32 / items_count as f32).ceil() as i32;
 
-                let mut orders_edges: Vec<Edge<GraphQLOrder>> = orders
+                let orders = orders
                     .into_iter()
                     .skip(offset as usize)
                     .take(count as usize)
-                    .map(|order| Edge::new(
-                                juniper::ID::from(order.0.id.to_string()),
-                                order.clone()
-                            ))
                     .collect();
+                let orders_edges = Edge::create_vec(orders, offset);
 
                 let page_info = PageInfoOrdersSearch {
                     total_pages,
@@ -328,7 +278,7 @@ graphql_object!(Store: Context as "Store" |&self| {
                     page_items_count: items_count,
                     search_term_options: search_term_options.into()
                 };
-                Connection::new(orders_edges, page_info)
+                Connection::new(orders_edges, page_info).with_total_count(TotalCount::Known(total_count))
             })
             .map(Some)
     }
@@ -341,25 +291,21 @@ graphql_object!(Store: Context as "Store" |&self| {
 
     field find_most_viewed_products(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset from beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         search_term : MostViewedProductsInput as "Most viewed search pattern")
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Find most viewed base products each one contains one variant." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/most_viewed?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1
+            window.offset,
+            window.count
             );
 
         let mut options = if let Some(mut options) = search_term.options.clone() {
@@ -380,22 +326,7 @@ graphql_object!(Store: Context as "Store" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
-            .map (|base_products| {
-                let mut base_product_edges = Edge::create_vec(base_products, offset);
-                let has_next_page = base_product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    base_product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(base_product_edges, page_info)
-            })
+            .map(|base_products| window.connection(base_products))
             .wait()
             .map(Some)
     }
@@ -403,25 +334,21 @@ graphql_object!(Store: Context as "Store" |&self| {
 
     field find_most_discount_products(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset from beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         search_term : MostDiscountProductsInput as "Most discount search pattern")
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Find base products each one with most discount variant." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/most_discount?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1
+            window.offset,
+            window.count
             );
 
         let mut options = if let Some(mut options) = search_term.options.clone() {
@@ -442,29 +369,16 @@ graphql_object!(Store: Context as "Store" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
-            .map (|base_products| {
-                let mut base_product_edges = Edge::create_vec(base_products, offset);
-                let has_next_page = base_product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    base_product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(base_product_edges, page_info)
-            })
+            .map(|base_products| window.connection(base_products))
             .wait()
             .map(Some)
     }
 
     field find_product(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         search_term : SearchProductInput as "Search pattern",
         visibility: Option<Visibility> as "Specifies allowed visibility of the base product"
         )
@@ -474,21 +388,14 @@ graphql_object!(Store: Context as "Store" |&self| {
 
         let visibility = visibility.unwrap_or_default();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/search?offset={}&count={}&visibility={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             visibility
             );
 
@@ -505,24 +412,18 @@ graphql_object!(Store: Context as "Store" |&self| {
 
         let body = serde_json::to_string(&search_term)?;
 
+        let count_url = format!("{}/{}/search/filters/count",
+            context.config.service_url(Service::Stores),
+            Model::BaseProduct.to_url(),
+        );
+        let total_count = TotalCount::post(count_url, body.clone());
+
         context.request::<Vec<BaseProduct>>(Method::Post, url, Some(body))
             .map (|products| {
-                let mut product_edges = Edge::create_vec(products, offset);
                 let search_filters = ProductsSearchFilters::new(search_term);
-                let has_next_page = product_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfoProductsSearch {
-                    has_next_page,
-                    has_previous_page,
-                    search_filters: Some(search_filters),
-                    start_cursor,
-                    end_cursor};
-                Connection::new(product_edges, page_info)
+                window.connection(products)
+                    .with_page_info(|page_info| PageInfoProductsSearch::new(page_info, Some(search_filters)))
+                    .with_total_count(total_count)
             })
             .wait()
             .map(Some)
@@ -530,26 +431,22 @@ graphql_object!(Store: Context as "Store" |&self| {
 
     field auto_complete_product_name(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         name : String as "Name part")
             -> FieldResult<Option<Connection<String, PageInfo>>> as "Finds products full name by part of the name." {
 
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/auto_complete?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             );
 
         let search_term = AutoCompleteProductNameInput {
@@ -561,22 +458,7 @@ graphql_object!(Store: Context as "Store" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<String>>(Method::Post, url, Some(body))
-            .map (|full_names| {
-                let mut full_name_edges = Edge::create_vec(full_names, offset);
-                let has_next_page = full_name_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    full_name_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  full_name_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = full_name_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(full_name_edges, page_info)
-            })
+            .map(|full_names| window.connection(full_names))
             .wait()
             .map(Some)
     }
@@ -711,6 +593,10 @@ graphql_object!(Connection<Store, PageInfo>: Context as "StoresConnection" |&sel
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Connection<Store, PageInfoSegments>: Context as "StoresConnectionPages" |&self| {
@@ -723,6 +609,10 @@ graphql_object!(Connection<Store, PageInfoSegments>: Context as "StoresConnectio
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<Store>: Context as "StoresEdge" |&self| {
@@ -747,6 +637,10 @@ graphql_object!(Connection<String, PageInfo>: Context as "FullNameConnection" |&
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Connection<Store, PageInfoStoresSearch>: Context as "StoresWithTotalCountConnection" |&self| {
@@ -759,6 +653,10 @@ graphql_object!(Connection<Store, PageInfoStoresSearch>: Context as "StoresWithT
     field page_info() -> &PageInfoStoresSearch {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<String>: Context as "FullNameEdge" |&self| {
@@ -783,6 +681,10 @@ graphql_object!(Connection<GraphQLOrder, PageInfoOrdersSearch>: Context as "Orde
     field page_info() -> &PageInfoOrdersSearch {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 fn orders_billing(
@@ -797,18 +699,15 @@ fn orders_billing(
     let skip = items_count * (current_page - 1);
     let search_params = convert_search_term(context, search_params)?;
     let orders = context.get_billing_microservice().orders(skip, items_count, search_params)?;
-    let total_pages = std::cmp::max(0, orders.total_count as i32 - 1) / items_count + 1;
-    let orders_edges: Vec<Edge<OrderBilling>> = orders
-        .orders
-        .into_iter()
-        .map(|order| Edge::new(GraphqlID::from(order.id.0.to_string()), order))
-        .collect();
+    let total_count = orders.total_count as i32;
+    let total_pages = std::cmp::max(0, total_count - 1) / items_count + 1;
+    let orders_edges = Edge::create_vec(orders.orders, skip);
     let page_info = PageInfoSegments {
         current_page,
         page_items_count: items_count,
         total_pages,
     };
-    Ok(Connection::new(orders_edges, page_info))
+    Ok(Connection::new(orders_edges, page_info).with_total_count(TotalCount::Known(total_count)))
 }
 
 pub fn get_store_id_by_product(context: &Context, product_id: ProductId) -> FieldResult<StoreId> {
@@ -888,7 +787,7 @@ fn send_to_moderation(context: &Context, store_id: StoreId) -> FieldResult<Store
 
 pub fn run_moderation_status_store(context: &Context, input: StoreModerateInput) -> FieldResult<Store> {
     let identifier = ID::from_str(&*input.id)?;
-    let store_id = StoreId(identifier.raw_id);
+    let store_id = StoreId(identifier.raw_id.int()?);
 
     let payload = StoreModerate {
         store_id,
@@ -942,7 +841,7 @@ pub fn run_send_to_draft_store_mutation(context: &Context, store_id: StoreId) ->
 
 pub fn run_update_store_mutation(context: &Context, input: UpdateStoreInput) -> FieldResult<Store> {
     let identifier = ID::from_str(&*input.id)?;
-    let store_id = StoreId(identifier.raw_id);
+    let store_id = StoreId(identifier.raw_id.int()?);
 
     let url = identifier.url(&context.config);
 
@@ -2,6 +2,7 @@
 use juniper::ID as GraphqlID;
 
 use juniper::FieldResult;
+use stq_routes::service::Service;
 use stq_static_resources::Currency;
 
 use super::*;
@@ -12,7 +13,7 @@ graphql_object!(StoreSubscription: Context as "StoreSubscription" |&self| {
     description: "Store subscription information."
 
     field id() -> GraphqlID as "Base64 Unique id" {
-        format!("{}|store_subscripotion",self.store_id).to_string().into()
+        ID::with_gateway_model(Service::Billing, GatewayModel::StoreSubscription, RawId::Int(self.store_id.0)).to_string().into()
     }
 
     field store_id() ->  i32 as "Store id" {
@@ -23,20 +24,24 @@ graphql_object!(StoreSubscription: Context as "StoreSubscription" |&self| {
         self.currency
     }
 
-    field value() ->  f64 as "Amount per product per day" {
-        self.value.to_string().parse::<f64>().unwrap_or(0.0)
+    field deprecated "Use valueMoney" value() ->  f64 as "Amount per product per day" {
+        decimal_to_f64(&self.value)
+    }
+
+    field value_money() -> Money as "Amount per product per day" {
+        Money::new(self.value.clone(), self.currency)
     }
 
     field wallet_address() ->  Option<&String> as "Wallet address"{
         self.wallet_address.as_ref()
     }
 
-    field trial_start_date() ->  Option<String> as "Trial start date" {
-        self.trial_start_date.map(|date| date.format("%+").to_string())
+    field trial_start_date() -> Option<GraphQLDateTime> as "Trial start date" {
+        self.trial_start_date.map(GraphQLDateTime::from)
     }
 
-    field trial_end_date() ->  Option<String> as "Trial end date" {
-        self.trial_end_date.map(|date| date.format("%+").to_string())
+    field trial_end_date() -> Option<GraphQLDateTime> as "Trial end date" {
+        self.trial_end_date.map(GraphQLDateTime::from)
     }
 
     field status() -> StoreSubscriptionStatus as "Store subscription status" {
@@ -55,8 +60,12 @@ graphql_object!(SubscriptionPayment: Context as "SubscriptionPayment" |&self| {
         self.store_id.0
     }
 
-    field amount() -> f64 as "Total amount" {
-        self.amount.to_string().parse::<f64>().unwrap_or(0.0)
+    field deprecated "Use amountMoney" amount() -> f64 as "Total amount" {
+        decimal_to_f64(&self.amount)
+    }
+
+    field amount_money() -> Money as "Total amount" {
+        Money::new(self.amount.clone(), self.currency)
     }
 
     field currency() -> Currency as "Currency" {
@@ -75,8 +84,8 @@ graphql_object!(SubscriptionPayment: Context as "SubscriptionPayment" |&self| {
         self.status
     }
 
-    field created_at() -> String {
-        self.created_at.format("%+").to_string()
+    field created_at() -> GraphQLDateTime {
+        self.created_at.into()
     }
 
     field subscriptions(&executor) -> FieldResult<Vec<Subscription>> {
@@ -89,7 +98,7 @@ graphql_object!(Subscription: Context as "Subscription" |&self| {
     description: "Subscription information."
 
     field id() -> GraphqlID as "Base64 Unique id" {
-        format!("{}|subscripotion",self.id).to_string().into()
+        ID::with_gateway_model(Service::Billing, GatewayModel::Subscription, RawId::Int(*self.id.inner())).to_string().into()
     }
 
     field store_id() -> i32 as "Store id" {
@@ -104,8 +113,8 @@ graphql_object!(Subscription: Context as "Subscription" |&self| {
         self.subscription_payment_id.map(|id| id.0)
     }
 
-    field created_at() -> String {
-        self.created_at.format("%+").to_string()
+    field created_at() -> GraphQLDateTime {
+        self.created_at.into()
     }
 });
 
@@ -119,6 +128,10 @@ graphql_object!(Connection<SubscriptionPayment, PageInfoSegments>: Context as "S
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<SubscriptionPayment>: Context as "SubscriptionPaymentEdge" |&self| {
@@ -146,21 +159,13 @@ pub fn subscription_payments(
     let result = context
         .get_billing_microservice()
         .subscription_payments(skip, items_count, search_params)?;
-    let total_pages = std::cmp::max(0, result.total_count as i32 - 1) / items_count + 1;
-    let subscription_payments_edges: Vec<Edge<SubscriptionPayment>> = result
-        .subscription_payments
-        .into_iter()
-        .map(|subscription_payment| {
-            Edge::new(
-                GraphqlID::from(format!("{}|subscription_payment", subscription_payment.id)),
-                subscription_payment,
-            )
-        })
-        .collect();
+    let total_count = result.total_count as i32;
+    let total_pages = std::cmp::max(0, total_count - 1) / items_count + 1;
+    let subscription_payments_edges = Edge::create_vec(result.subscription_payments, skip);
     let page_info = PageInfoSegments {
         current_page,
         page_items_count: items_count,
         total_pages,
     };
-    Ok(Connection::new(subscription_payments_edges, page_info))
+    Ok(Connection::new(subscription_payments_edges, page_info).with_total_count(TotalCount::Known(total_count)))
 }
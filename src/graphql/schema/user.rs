@@ -1,8 +1,6 @@
 //! File containing user object of graphql schema
 use std::cmp;
-use std::str::FromStr;
 
-use chrono::prelude::*;
 use futures::Future;
 use hyper::Method;
 use juniper;
@@ -10,7 +8,6 @@ use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
 
 use stq_api::orders::{OrderClient, OrderSearchTerms};
-use stq_api::types::ApiFutureExt;
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_static_resources::{Gender, Provider};
@@ -18,7 +15,7 @@ use stq_types::{OrderIdentifier, OrderSlug};
 use stq_types::{UserId, WarehouseIdentifier, WarehouseSlug};
 
 use super::*;
-use errors::into_graphql;
+use controller::cookies;
 use graphql::context::Context;
 use graphql::models::*;
 use graphql::schema::warehouse as warehouse_module;
@@ -83,14 +80,12 @@ graphql_object!(User: Context as "User" |&self| {
         &self.emarsys_id
     }
 
-    field created_at() -> String as "Created at" {
-        let datetime: DateTime<Utc> = self.created_at.into();
-        datetime.to_rfc3339()
+    field created_at() -> GraphQLDateTime as "Created at" {
+        self.created_at.into()
     }
 
-    field updated_at() -> String as "Updated at" {
-        let datetime: DateTime<Utc> = self.updated_at.into();
-        datetime.to_rfc3339()
+    field updated_at() -> GraphQLDateTime as "Updated at" {
+        self.updated_at.into()
     }
 
     field admin() -> Admin as "Admin routes" {
@@ -181,45 +176,19 @@ graphql_object!(User: Context as "User" |&self| {
         let context = executor.context();
         let visibility = visibility.unwrap_or(Visibility::Active);
 
-        let raw_id = match after {
-            Some(val) => ID::from_str(&*val)?.raw_id,
-            None => MIN_ID
-        };
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
         let url = format!("{}/{}?offset={}&count={}&visibility={}",
             context.config.service_url(Service::Stores),
             Model::Store.to_url(),
-            raw_id,
-            first + 1,
+            window.after.unwrap_or(MIN_ID),
+            window.count,
             visibility
         );
 
         context.request::<Vec<Store>>(Method::Get, url, None)
-            .map (|stores| {
-                let mut store_edges: Vec<Edge<Store>> = stores
-                    .into_iter()
-                    .map(|store| Edge::new(
-                                juniper::ID::from(ID::new(Service::Stores, Model::Store, store.id.0).to_string()),
-                                store.clone()
-                            ))
-                    .collect();
-                let has_next_page = store_edges.len() as i32 == first + 1;
-                if has_next_page {
-                    store_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  store_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = store_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(store_edges, page_info)
-            })
+            .map(|stores| window.connection(stores, |store| store.id.0))
             .wait()
             .map(Some)
     }
@@ -258,43 +227,17 @@ graphql_object!(User: Context as "User" |&self| {
             -> FieldResult<Option<Connection<Product, PageInfo>>> as "Fetches products using relay connection." {
         let context = executor.context();
 
-        let raw_id = match after {
-            Some(val) => ID::from_str(&*val)?.raw_id,
-            None => MIN_ID
-        };
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
         let url = format!("{}/{}?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::Product.to_url(),
-            raw_id,
-            first + 1);
+            window.after.unwrap_or(MIN_ID),
+            window.count);
 
         context.request::<Vec<Product>>(Method::Get, url, None)
-            .map (|products| {
-                let mut product_edges: Vec<Edge<Product>> = products
-                    .into_iter()
-                    .map(|product| Edge::new(
-                                juniper::ID::from(ID::new(Service::Stores, Model::Product, product.id.0).to_string()),
-                                product.clone()
-                            ))
-                    .collect();
-                let has_next_page = product_edges.len() as i32 == first + 1;
-                if has_next_page {
-                    product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(product_edges, page_info)
-            })
+            .map(|products| window.connection(products, |product| product.id.0))
             .wait()
             .map(Some)
     }
@@ -326,45 +269,19 @@ graphql_object!(User: Context as "User" |&self| {
         let context = executor.context();
         let visibility = visibility.unwrap_or(Visibility::Active);
 
-        let raw_id = match after {
-            Some(val) => ID::from_str(&*val)?.raw_id,
-            None => MIN_ID
-        };
-
         let records_limit = context.config.gateway.records_limit;
-        let first = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).key_window(records_limit as i32)?;
 
         let url = format!("{}/{}?offset={}&count={}&visibility={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            raw_id,
-            first + 1,
+            window.after.unwrap_or(MIN_ID),
+            window.count,
             visibility
         );
 
         context.request::<Vec<BaseProduct>>(Method::Get, url, None)
-            .map (|base_products| {
-                let mut base_product_edges: Vec<Edge<BaseProduct>> = base_products
-                    .into_iter()
-                    .map(|base_product| Edge::new(
-                                juniper::ID::from(ID::new(Service::Stores, Model::BaseProduct, base_product.id.0).to_string()),
-                                base_product.clone()
-                            ))
-                    .collect();
-                let has_next_page = base_product_edges.len() as i32 == first + 1;
-                if has_next_page {
-                    base_product_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(base_product_edges, page_info)
-            })
+            .map(|base_products| window.connection(base_products, |base_product| base_product.id.0))
             .wait()
             .map(Some)
     }
@@ -407,6 +324,25 @@ graphql_object!(User: Context as "User" |&self| {
             .map(Some)
     }
 
+    field sessions(&executor) -> FieldResult<Vec<UserSession>> as "Active sessions of the current user." {
+        let context = executor.context();
+
+        if context.user.as_ref().map(|payload| payload.user_id) != Some(self.id) {
+            return Err(FieldError::new(
+                "Sessions are available for the current user only.",
+                graphql_value!({ "code": 100, "details": { "Can not fetch sessions of another user." }}),
+            ));
+        }
+
+        let url = format!("{}/user_sessions/by-user-id/{}",
+            context.config.service_url(Service::Users),
+            self.id);
+
+        context.request::<Vec<UserSession>>(Method::Get, url, None)
+            .wait()
+            .map(|sessions| sessions.into_iter().filter(|session| session.revoked_at.is_none()).collect())
+    }
+
     field orders(&executor,
         current_page : i32 as "Current page",
         items_count : i32 as "Items count",
@@ -419,32 +355,6 @@ graphql_object!(User: Context as "User" |&self| {
         let records_limit = context.config.gateway.records_limit;
         let count = cmp::min(items_count, records_limit as i32);
 
-        let created_from = match search_term_options.created_from.clone() {
-            Some(value) => {
-                match value.parse() {
-                    Ok(v) => Some(v),
-                    Err(_) => return Err(FieldError::new(
-                        "Parsing created_from error",
-                        graphql_value!({ "code": 300, "details": { "created_from has wrong format." }}),
-                    )),
-                }
-            },
-            None => None
-        };
-
-        let created_to = match search_term_options.created_to.clone() {
-            Some(value) => {
-                match value.parse() {
-                    Ok(v) => Some(v),
-                    Err(_) => return Err(FieldError::new(
-                        "Parsing created_to error",
-                        graphql_value!({ "code": 300, "details": { "created_to has wrong format." }}),
-                    )),
-                }
-            },
-            None => None
-        };
-
         let customer = search_term_options.email.clone().and_then(|email| {
             let url = format!("{}/{}/by_email?email={}",
                 context.config.service_url(Service::Users),
@@ -461,30 +371,25 @@ graphql_object!(User: Context as "User" |&self| {
                 slug: search_term_options.slug.map(OrderSlug),
                 customer: Some(self.id),
                 store: None,
-                created_from,
-                created_to,
+                created_from: search_term_options.created_from.map(|datetime| datetime.0),
+                created_to: search_term_options.created_to.map(|datetime| datetime.0),
                 payment_status: search_term_options.payment_status,
                 state: search_term_options.order_status,
                 ..OrderSearchTerms::default()
             };
 
-        let rpc_client = context.get_rest_api_client(Service::Orders);
-        rpc_client.search(search_term)
-            .sync()
-            .map_err(into_graphql)
+        context.call_rest_api(Service::Orders, |client| client.search(search_term))
             .map(|res| res.into_iter().map(GraphQLOrder).collect())
             .map (move |orders: Vec<GraphQLOrder>| {
-                let total_pages = (orders.iter().count() as f32 / items_count as f32).ceil() as i32;
+                let total_count = orders.len() as i32;
+                let total_pages = (total_count as f32 / items_count as f32).ceil() as i32;
 
-                let mut orders_edges: Vec<Edge<GraphQLOrder>> = orders
+                let orders = orders
                     .into_iter()
                     .skip(offset as usize)
                     .take(count as usize)
-                    .map(|order| Edge::new(
-                                juniper::ID::from(order.0.id.to_string()),
-                                order.clone()
-                            ))
                     .collect();
+                let orders_edges = Edge::create_vec(orders, offset);
 
                 let page_info = PageInfoOrdersSearch {
                     total_pages,
@@ -492,7 +397,7 @@ graphql_object!(User: Context as "User" |&self| {
                     page_items_count: items_count,
                     search_term_options: search_term_options.into()
                 };
-                Connection::new(orders_edges, page_info)
+                Connection::new(orders_edges, page_info).with_total_count(TotalCount::Known(total_count))
             })
             .map(Some)
     }
@@ -551,6 +456,10 @@ graphql_object!(Connection<User, PageInfo>: Context as "UsersConnection" |&self|
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Connection<User, PageInfoSegments>: Context as "UsersConnectionPages" |&self| {
@@ -563,6 +472,10 @@ graphql_object!(Connection<User, PageInfoSegments>: Context as "UsersConnectionP
     field page_info() -> &PageInfoSegments {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<User>: Context as "UsersEdge" |&self| {
@@ -587,6 +500,10 @@ graphql_object!(Connection<CartProduct, PageInfo>: Context as "CartProductConnec
     field page_info() -> &PageInfo {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<CartProduct>: Context as "CartProductEdge" |&self| {
@@ -645,6 +562,18 @@ pub fn change_alpha2_to_alpha3(context: &Context, additional_data: &mut NewUserA
     });
 }
 
+pub fn set_jwt_cookies(context: &Context, jwt: &JWT) {
+    if let Some(ref cookie_config) = context.config.jwt_cookie {
+        cookies::set_jwt_cookies(&context.response_cookies, cookie_config, &jwt.token);
+    }
+}
+
+pub fn clear_jwt_cookies(context: &Context) {
+    if let Some(ref cookie_config) = context.config.jwt_cookie {
+        cookies::clear_jwt_cookies(&context.response_cookies, cookie_config);
+    }
+}
+
 pub fn existing_reset_token(context: &Context, input: ExistingResetTokenInput) -> FieldResult<ResetToken> {
     let users_url = context.config.service_url(Service::Users);
     let url = match input.token_type {
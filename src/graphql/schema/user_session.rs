@@ -0,0 +1,35 @@
+//! File containing user session object of graphql schema
+use juniper::ID as GraphqlID;
+
+use graphql::context::Context;
+use graphql::models::*;
+
+graphql_object!(UserSession: Context as "UserSession" |&self| {
+    description: "Session of a user on a single device."
+
+    field id() -> GraphqlID as "Unique id" {
+        self.id.hyphenated().to_string().into()
+    }
+
+    field device() -> &Option<String> as "Device user agent" {
+        &self.device
+    }
+
+    field ip() -> &Option<String> as "IP address of the device" {
+        &self.ip
+    }
+
+    field created_at() -> GraphQLDateTime as "Created at" {
+        self.created_at.into()
+    }
+
+    field last_seen_at() -> GraphQLDateTime as "Last seen at" {
+        self.last_seen_at.into()
+    }
+
+    field current(&executor) -> bool as "Whether the session is the one of current request" {
+        let context = executor.context();
+
+        context.user.as_ref().and_then(|payload| payload.user_session_id) == Some(self.id)
+    }
+});
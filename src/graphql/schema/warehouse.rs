@@ -1,6 +1,5 @@
 //! File containing warehouse object of graphql schema
 use std::cmp;
-use std::str::FromStr;
 
 use futures::Future;
 use hyper::Method;
@@ -8,14 +7,12 @@ use juniper::ID as GraphqlID;
 use juniper::{FieldError, FieldResult};
 use serde_json;
 
-use stq_api::types::ApiFutureExt;
 use stq_api::warehouses::{Stock, Warehouse, WarehouseClient};
 use stq_routes::model::Model;
 use stq_routes::service::Service;
 use stq_types::{ProductId, Quantity, StockId, StoreId, WarehouseIdentifier};
 
 use super::*;
-use errors::into_graphql;
 use graphql::context::Context;
 use graphql::models::*;
 
@@ -24,8 +21,12 @@ graphql_object!(GraphQLWarehouse: Context as "Warehouse" |&self| {
 
     interfaces: [&Node]
 
-    field id() -> GraphqlID as "Unique id"{
-        self.0.id.to_string().into()
+    field id() -> GraphqlID as "Base64 Unique id"{
+        ID::with_uuid(Service::Warehouses, Model::Warehouse, self.0.id.0).to_string().into()
+    }
+
+    field raw_id() -> String as "Unique uuid"{
+        self.0.id.to_string()
     }
 
     field name() -> &Option<String> as "Name"{
@@ -134,10 +135,7 @@ graphql_object!(GraphQLWarehouse: Context as "Warehouse" |&self| {
             .and_then (|products: Vec<ProductId>| {
                 products.into_iter().map(|product_id| {
 
-                    let rpc_client = context.get_rest_api_client(Service::Warehouses);
-                    rpc_client.get_product_in_warehouse(self.0.id, product_id)
-                        .sync()
-                        .map_err(into_graphql)
+                    context.call_rest_api(Service::Warehouses, |client| client.get_product_in_warehouse(self.0.id, product_id))
                         .map (|stock| {
                             if let Some(stock) = stock {
                                 stock
@@ -153,7 +151,7 @@ graphql_object!(GraphQLWarehouse: Context as "Warehouse" |&self| {
                         .map(GraphQLStock)
                 }).collect::<FieldResult<Vec<GraphQLStock>>>()
                 .and_then (|products| {
-                    let mut product_edges = Edge::create_vec(products, offset);
+                    let product_edges = Edge::create_vec(products, offset);
 
                     let body = serde_json::to_string(&search_term)?;
 
@@ -180,26 +178,22 @@ graphql_object!(GraphQLWarehouse: Context as "Warehouse" |&self| {
 
     field auto_complete_product_name(&executor,
         first = None : Option<i32> as "First edges",
-        after = None : Option<GraphqlID>  as "Offset form beginning",
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after",
+        last = None : Option<i32> as "Last edges",
+        before = None : Option<GraphqlID> as "Cursor of the edge to fetch before",
         name : String as "Name part")
             -> FieldResult<Option<Connection<String, PageInfo>>> as "Finds products full name by part of the name." {
 
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::new(first, after, last, before).offset_window(records_limit as i32)?;
 
         let url = format!("{}/{}/auto_complete?offset={}&count={}",
             context.config.service_url(Service::Stores),
             Model::BaseProduct.to_url(),
-            offset,
-            count + 1,
+            window.offset,
+            window.count,
             );
 
         let search_term = AutoCompleteProductNameInput {
@@ -211,22 +205,7 @@ graphql_object!(GraphQLWarehouse: Context as "Warehouse" |&self| {
         let body = serde_json::to_string(&search_term)?;
 
         context.request::<Vec<String>>(Method::Post, url, Some(body))
-            .map (|full_names| {
-                let mut full_name_edges = Edge::create_vec(full_names, offset);
-                let has_next_page = full_name_edges.len() as i32 == count + 1;
-                if has_next_page {
-                    full_name_edges.pop();
-                };
-                let has_previous_page = true;
-                let start_cursor =  full_name_edges.get(0).map(|e| e.cursor.clone());
-                let end_cursor = full_name_edges.iter().last().map(|e| e.cursor.clone());
-                let page_info = PageInfo {
-                    has_next_page,
-                    has_previous_page,
-                    start_cursor,
-                    end_cursor};
-                Connection::new(full_name_edges, page_info)
-            })
+            .map(|full_names| window.connection(full_names))
             .wait()
             .map(Some)
     }
@@ -243,6 +222,10 @@ graphql_object!(Connection<GraphQLStock, PageInfoWarehouseProductSearch>: Contex
     field page_info() -> &PageInfoWarehouseProductSearch {
         &self.page_info
     }
+
+    field total_count(&executor) -> FieldResult<Option<i32>> as "Total number of nodes, null if it can not be counted" {
+        self.resolve_total_count(executor.context())
+    }
 });
 
 graphql_object!(Edge<GraphQLStock>: Context as "StocksEdge" |&self| {
@@ -1,6 +1,4 @@
 //! File containing wizard store object of graphql schema
-use std::cmp;
-use std::str::FromStr;
 
 use futures::Future;
 use hyper::Method;
@@ -114,18 +112,12 @@ graphql_object!(WizardStore: Context as "WizardStore" |&self| {
     }
 
     field deprecated "Use store field and base_products connection inside" step_three(&executor, first = None : Option<i32> as "First edges", 
-        after = None : Option<GraphqlID> as "Offset from begining") 
+        after = None : Option<GraphqlID> as "Cursor of the edge to fetch after") 
             -> FieldResult<Option<Connection<BaseProduct, PageInfo>>> as "Fetches wizard step three." {
         let context = executor.context();
 
-        let offset = after
-            .and_then(|id|{
-                i32::from_str(&id).map(|i| i + 1).ok()
-            })
-            .unwrap_or_default();
-
         let records_limit = context.config.gateway.records_limit;
-        let count = cmp::min(first.unwrap_or(records_limit as i32), records_limit as i32);
+        let window = ConnectionArgs::forward(first, after).offset_window(records_limit as i32)?;
 
         if let Some(ref store_id) = self.store_id {
             let url =  format!(
@@ -133,27 +125,19 @@ graphql_object!(WizardStore: Context as "WizardStore" |&self| {
                         &context.config.service_url(Service::Stores),
                         Model::Store.to_url(),
                         store_id,
-                        offset,
-                        count + 1
+                        window.offset,
+                        window.count
+                    );
+
+            let count_url = format!(
+                        "{}/{}/{}/products/count",
+                        &context.config.service_url(Service::Stores),
+                        Model::Store.to_url(),
+                        store_id,
                     );
 
             context.request::<Vec<BaseProduct>>(Method::Get, url, None)
-                .map (|base_products| {
-                    let mut base_product_edges = Edge::create_vec(base_products, offset);
-                    let has_next_page = base_product_edges.len() as i32 == count + 1;
-                    if has_next_page {
-                        base_product_edges.pop();
-                    };
-                    let has_previous_page = true;
-                    let start_cursor =  base_product_edges.get(0).map(|e| e.cursor.clone());
-                    let end_cursor = base_product_edges.iter().last().map(|e| e.cursor.clone());
-                    let page_info = PageInfo {
-                        has_next_page,
-                        has_previous_page,
-                        start_cursor,
-                        end_cursor};
-                    Connection::new(base_product_edges, page_info)
-                })
+                .map(|base_products| window.connection(base_products).with_total_count(TotalCount::get(count_url)))
                 .wait()
                 .map(Some)
         } else {
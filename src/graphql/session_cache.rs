@@ -0,0 +1,56 @@
+//! JWT sessions recently confirmed as not revoked, so that authenticated requests
+//! do not ask users microservice about the session every time.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+/// Session ids come from JWTs, so their number is bounded
+const MAX_SESSIONS: usize = 100_000;
+
+/// Sessions revoked through this gateway are forgotten at once,
+/// sessions revoked elsewhere are accepted for `ttl` at most
+#[derive(Debug, Default)]
+pub struct ActiveSessions {
+    ttl: Duration,
+    checked_at: Mutex<HashMap<Uuid, Instant>>,
+}
+
+impl ActiveSessions {
+    /// Zero `ttl` disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            checked_at: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_active(&self, session_id: Uuid) -> bool {
+        self.checked_at
+            .lock()
+            .unwrap()
+            .get(&session_id)
+            .map_or(false, |checked_at| checked_at.elapsed() < self.ttl)
+    }
+
+    pub fn mark_active(&self, session_id: Uuid) {
+        if self.ttl == Duration::from_secs(0) {
+            return;
+        }
+        let ttl = self.ttl;
+        let mut checked_at = self.checked_at.lock().unwrap();
+        if checked_at.len() >= MAX_SESSIONS {
+            checked_at.retain(|_, checked_at| checked_at.elapsed() < ttl);
+            if checked_at.len() >= MAX_SESSIONS {
+                checked_at.clear();
+            }
+        }
+        checked_at.insert(session_id, Instant::now());
+    }
+
+    pub fn forget(&self, session_id: Uuid) {
+        self.checked_at.lock().unwrap().remove(&session_id);
+    }
+}
@@ -41,17 +41,19 @@ pub mod controller;
 pub mod errors;
 pub mod graphql;
 pub mod sentry_integration;
+pub mod upstream;
 
 use std::fs::File;
 use std::io::prelude::*;
 use std::process;
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::prelude::*;
 use futures::stream::Stream;
 use futures_cpupool::CpuPool;
-use hyper::header::{AccessControlAllowOrigin, AccessControlMaxAge, ContentType};
-use hyper::server::Http;
+use hyper::header::{AccessControlAllowCredentials, AccessControlAllowOrigin, AccessControlMaxAge, ContentType, SetCookie};
+use hyper::server::{Http, Request, Response, Service};
 use tokio_core::reactor::Core;
 
 use stq_http::controller::Application;
@@ -59,7 +61,7 @@ use stq_http::controller::Application;
 use config::Config;
 use graphql::schema;
 
-pub fn start(config: Config) {
+pub fn start(mut config: Config) {
     // Prepare reactor
     let mut core = Core::new().expect("Unexpected error creating event loop core");
     let handle = Arc::new(core.handle());
@@ -71,42 +73,69 @@ pub fn start(config: Config) {
     let mut jwt_public_key: Vec<u8> = Vec::new();
     f.read_to_end(&mut jwt_public_key).unwrap();
 
-    let client = stq_http::client::Client::new(&config.to_http_config(), &handle);
-    let client_handle = client.handle();
-    let client_stream = client.stream();
-    handle.spawn(client_stream.for_each(|_| Ok(())));
+    if let Some(recorder) = config.recorder.clone() {
+        let upstreams = upstream::recorder::route_through_recorder(&mut config);
+        upstream::recorder::serve(&recorder, upstreams, &handle);
+    }
+    if let Some(ref mock) = config.mock {
+        upstream::mock::serve(mock, &handle);
+    }
+    let http_clients = upstream::HttpClients::new(&config, &handle);
     let domain = config.cors.domain.clone();
     let max_age = config.cors.max_age;
+    let allow_credentials = config.jwt_cookie.is_some();
     let cpu_pool = CpuPool::new(config.gateway.graphql_thread_pool_size);
     let jwt_leeway = config.jwt.leeway;
     let schema = Arc::new(schema::create());
+    let circuit_breakers = Arc::new(upstream::CircuitBreakers::new(&config));
+    let last_known_good = Arc::new(graphql::resilience::LastKnownGood::new(config.resilience.as_ref()));
+    let deprecations = Arc::new(graphql::deprecation::DeprecationTracker::new(&config));
+    let analytics = Arc::new(graphql::analytics::OperationAnalytics::default());
+    let session_check_cache = Duration::from_secs(config.jwt.session_check_cache_sec);
+    let active_sessions = Arc::new(graphql::session_cache::ActiveSessions::new(session_check_cache));
+    let rate_limiter = config.rate_limit.as_ref().map(|rate_limit| Arc::new(controller::rate_limit::RateLimiter::new(rate_limit)));
 
-    let serve = Http::new()
-        .serve_addr_handle(&address, &*handle, {
-            move || {
-                let domain = domain.to_owned();
-                // Prepare application
-                let app = Application::<errors::Error>::new(controller::ControllerImpl::new(
-                    client_handle.clone(),
-                    jwt_public_key.clone(),
-                    cpu_pool.clone(),
-                    jwt_leeway,
-                    config.clone(),
-                    schema.clone(),
-                ))
-                .with_middleware(move |mut resp| {
-                    let contains_acao = resp.headers().has::<AccessControlAllowOrigin>();
-                    if !contains_acao {
-                        resp.headers_mut().set(AccessControlAllowOrigin::Value(domain.clone()));
-                    }
-                    resp.headers_mut().set(AccessControlMaxAge(max_age));
-                    resp.headers_mut().set(ContentType::html());
-                    resp
-                });
-
-                Ok(app)
+    let controller = controller::ControllerImpl::new(
+        http_clients,
+        jwt_public_key,
+        cpu_pool,
+        jwt_leeway,
+        config,
+        schema,
+        rate_limiter,
+        circuit_breakers,
+        last_known_good,
+        deprecations,
+        analytics,
+        active_sessions,
+    );
+    let new_app = Arc::new(move |response_cookies: controller::cookies::ResponseCookies| {
+        let domain = domain.clone();
+        Application::<errors::Error>::new(controller.with_response_cookies(response_cookies.clone())).with_middleware(move |mut resp| {
+            let contains_acao = resp.headers().has::<AccessControlAllowOrigin>();
+            if !contains_acao {
+                resp.headers_mut().set(AccessControlAllowOrigin::Value(domain.clone()));
+            }
+            resp.headers_mut().set(AccessControlMaxAge(max_age));
+            if allow_credentials {
+                resp.headers_mut().set(AccessControlAllowCredentials);
             }
+            let cookies = response_cookies.take();
+            if !cookies.is_empty() {
+                resp.headers_mut().set(SetCookie(cookies));
+            }
+            if let Some(session_token) = response_cookies.take_session_token() {
+                let header = controller::anonymous_session::SESSION_ID_HEADER;
+                resp.headers_mut().set_raw(header, session_token);
+                resp.headers_mut().set_raw("Access-Control-Expose-Headers", header);
+            }
+            resp.headers_mut().set(ContentType::html());
+            resp
         })
+    });
+
+    let serve = Http::new()
+        .serve_addr_handle(&address, &*handle, move || Ok(PerRequest(new_app.clone())))
         .unwrap_or_else(|reason| {
             eprintln!("Http Server Initialization Error: {}", reason);
             process::exit(1);
@@ -131,3 +160,21 @@ pub fn start(config: Config) {
     });
     core.run(endless_stream).unwrap();
 }
+
+/// Builds application for every request, so that cookies set while serving a request end up in its response only
+struct PerRequest<F>(Arc<F>);
+
+impl<F, S> Service for PerRequest<F>
+where
+    F: Fn(controller::cookies::ResponseCookies) -> S,
+    S: Service<Request = Request, Response = Response, Error = hyper::Error>,
+{
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = S::Future;
+
+    fn call(&self, req: Request) -> Self::Future {
+        (self.0)(controller::cookies::ResponseCookies::default()).call(req)
+    }
+}
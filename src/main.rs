@@ -1,8 +1,28 @@
 extern crate gateway_lib;
+extern crate serde_json;
 extern crate stq_logging;
 
+use std::env;
+use std::fs;
+use std::process;
+
+use gateway_lib::config::Config;
+use gateway_lib::graphql::introspection;
+
+const SCHEMA_USAGE: &str = "Usage: gateway_runner schema <command>
+
+Commands:
+    print         Print schema in SDL
+    json          Print introspection result
+    diff <file>   Report breaking changes against introspection result saved in <file>";
+
 fn main() {
-    let config = gateway_lib::config::Config::new().expect("Can't load gateway configs. Please check your /config folder.");
+    let config = Config::new().expect("Can't load gateway configs. Please check your /config folder.");
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(|arg| arg.as_str()) == Some("schema") {
+        process::exit(schema_command(config, &args[1..]));
+    }
 
     // Prepare sentry integration
     let _sentry = gateway_lib::sentry_integration::init(config.sentry.as_ref());
@@ -12,3 +32,75 @@ fn main() {
 
     gateway_lib::start(config);
 }
+
+/// Builds schema without starting servers, returns process exit code
+fn schema_command(config: Config, args: &[String]) -> i32 {
+    let command = args.first().map(|arg| arg.as_str());
+    let saved_path = args.get(1);
+    match (command, saved_path) {
+        (Some("print"), None) | (Some("json"), None) | (Some("diff"), Some(_)) => {}
+        _ => {
+            eprintln!("{}", SCHEMA_USAGE);
+            return 2;
+        }
+    }
+
+    let schema = match introspection::introspect(config) {
+        Ok(schema) => schema,
+        Err(e) => {
+            eprintln!("Can't build schema: {}", e);
+            return 1;
+        }
+    };
+
+    match (command, saved_path) {
+        (Some("print"), _) => match introspection::print_sdl(&schema) {
+            Ok(sdl) => {
+                print!("{}", sdl);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+        (Some("json"), _) => {
+            println!("{}", serde_json::to_string_pretty(&schema).expect("Can't serialize schema"));
+            0
+        }
+        (_, Some(saved_path)) => diff(&schema, saved_path),
+        _ => 2,
+    }
+}
+
+/// Exits with 1 if there are breaking changes, so it can be used as CI check
+fn diff(schema: &serde_json::Value, saved_path: &str) -> i32 {
+    let saved = fs::read_to_string(saved_path)
+        .map_err(|e| e.to_string())
+        .and_then(|saved| serde_json::from_str::<serde_json::Value>(&saved).map_err(|e| e.to_string()));
+    let saved = match saved {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("Can't read saved schema {}: {}", saved_path, e);
+            return 1;
+        }
+    };
+
+    match introspection::breaking_changes(&saved, schema) {
+        Ok(ref changes) if changes.is_empty() => {
+            println!("No breaking changes");
+            0
+        }
+        Ok(changes) => {
+            for change in &changes {
+                println!("{}", change);
+            }
+            eprintln!("{} breaking change(s)", changes.len());
+            1
+        }
+        Err(e) => {
+            eprintln!("Can't compare with {}: {}", saved_path, e);
+            1
+        }
+    }
+}
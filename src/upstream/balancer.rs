@@ -0,0 +1,197 @@
+//! Client side load balancing between instances of a microservice. Urls are built
+//! with `Microservice::url` as before and its prefix is replaced by the chosen endpoint.
+
+use std::net::ToSocketAddrs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hyper::Uri;
+
+use config::{Balancing, EjectionConfig, Microservice};
+
+pub struct Endpoint {
+    url: String,
+    in_flight: AtomicUsize,
+    failures: AtomicUsize,
+    ejected_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            in_flight: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+            ejected_until: Mutex::new(None),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn is_ejected(&self, now: Instant) -> bool {
+        match self.ejected_until.lock().map(|until| *until) {
+            Ok(Some(until)) => now < until,
+            _ => false,
+        }
+    }
+}
+
+pub struct Balancer {
+    base_url: String,
+    balancing: Balancing,
+    ejection: Option<EjectionConfig>,
+    endpoints: RwLock<Vec<Arc<Endpoint>>>,
+    next: AtomicUsize,
+}
+
+impl Balancer {
+    /// Returns `None` if microservice has a single endpoint
+    pub fn new(microservice: &Microservice, default_ejection: Option<&EjectionConfig>) -> Option<Arc<Self>> {
+        let endpoints = match microservice.dns_refresh_ms {
+            Some(_) => resolve(&microservice.url).unwrap_or_else(|| vec![microservice.url.clone()]),
+            None if !microservice.endpoints.is_empty() => microservice.endpoints.clone(),
+            None => return None,
+        };
+
+        let balancer = Arc::new(Self {
+            base_url: microservice.url.clone(),
+            balancing: microservice.balancing,
+            ejection: microservice.ejection.clone().or_else(|| default_ejection.cloned()),
+            endpoints: RwLock::new(endpoints.into_iter().map(|url| Arc::new(Endpoint::new(url))).collect()),
+            next: AtomicUsize::new(0),
+        });
+
+        if let Some(dns_refresh_ms) = microservice.dns_refresh_ms {
+            spawn_dns_refresh(Arc::downgrade(&balancer), Duration::from_millis(dns_refresh_ms));
+        }
+
+        Some(balancer)
+    }
+
+    /// Chooses endpoint among not ejected ones, or among all if every endpoint is ejected
+    pub fn pick(&self) -> Option<Arc<Endpoint>> {
+        let endpoints = self.endpoints.read().ok()?;
+        let now = Instant::now();
+        let mut healthy = endpoints.iter().filter(|endpoint| !endpoint.is_ejected(now)).collect::<Vec<_>>();
+        if healthy.is_empty() {
+            healthy = endpoints.iter().collect();
+        }
+        if healthy.is_empty() {
+            return None;
+        }
+
+        let endpoint = match self.balancing {
+            Balancing::RoundRobin => healthy[self.next.fetch_add(1, Ordering::Relaxed) % healthy.len()],
+            Balancing::LeastInFlight => {
+                let offset = self.next.fetch_add(1, Ordering::Relaxed);
+                // rotating start breaks ties evenly
+                (0..healthy.len())
+                    .map(|i| healthy[(offset + i) % healthy.len()])
+                    .min_by_key(|endpoint| endpoint.in_flight.load(Ordering::Relaxed))?
+            }
+        };
+
+        Some(endpoint.clone())
+    }
+
+    /// Replaces base url of microservice with endpoint url
+    pub fn rewrite(&self, url: &str, endpoint: &Endpoint) -> String {
+        if url.starts_with(self.base_url.as_str()) {
+            format!("{}{}", endpoint.url, &url[self.base_url.len()..])
+        } else {
+            url.to_string()
+        }
+    }
+
+    /// Marks request to endpoint as started, request is finished when guard is dropped
+    pub fn start(&self, endpoint: Arc<Endpoint>) -> InFlightGuard {
+        endpoint.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { endpoint }
+    }
+
+    pub fn on_success(&self, endpoint: &Endpoint) {
+        endpoint.failures.store(0, Ordering::Relaxed);
+    }
+
+    pub fn on_failure(&self, endpoint: &Endpoint) {
+        let ejection = match self.ejection {
+            Some(ref ejection) => ejection,
+            None => return,
+        };
+        let failures = endpoint.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= ejection.consecutive_failures as usize {
+            if let Ok(mut ejected_until) = endpoint.ejected_until.lock() {
+                warn!("Endpoint {} is ejected after {} consecutive failures", endpoint.url, failures);
+                *ejected_until = Some(Instant::now() + Duration::from_millis(ejection.duration_ms));
+            }
+            endpoint.failures.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Keeps statistics of endpoints which are still resolved
+    fn update_endpoints(&self, urls: Vec<String>) {
+        if let Ok(mut endpoints) = self.endpoints.write() {
+            let updated = urls
+                .into_iter()
+                .map(|url| match endpoints.iter().find(|endpoint| endpoint.url == url) {
+                    Some(endpoint) => endpoint.clone(),
+                    None => Arc::new(Endpoint::new(url)),
+                })
+                .collect();
+            *endpoints = updated;
+        }
+    }
+}
+
+pub struct InFlightGuard {
+    endpoint: Arc<Endpoint>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.endpoint.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Resolves host of `url` into endpoint urls with the same scheme and port
+fn resolve(url: &str) -> Option<Vec<String>> {
+    let uri = url.parse::<Uri>().ok()?;
+    let scheme = uri.scheme().unwrap_or("http");
+    let host = uri.host()?;
+    let port = uri.port().unwrap_or(if scheme == "https" { 443 } else { 80 });
+
+    match (host, port).to_socket_addrs() {
+        Ok(addrs) => {
+            let mut urls = addrs.map(|addr| format!("{}://{}", scheme, addr)).collect::<Vec<_>>();
+            urls.sort();
+            urls.dedup();
+            if urls.is_empty() {
+                None
+            } else {
+                Some(urls)
+            }
+        }
+        Err(e) => {
+            warn!("Could not resolve {}: {}", host, e);
+            None
+        }
+    }
+}
+
+fn spawn_dns_refresh(balancer: Weak<Balancer>, interval: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let balancer = match balancer.upgrade() {
+            Some(balancer) => balancer,
+            None => return,
+        };
+        // last known addresses are kept if resolution fails
+        if let Some(urls) = resolve(&balancer.base_url) {
+            balancer.update_endpoints(urls);
+        }
+    });
+}
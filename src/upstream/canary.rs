@@ -0,0 +1,99 @@
+//! Routing of requests to alternate builds of microservices. Routes are chosen once per
+//! GraphQL operation, so that all upstream calls of the operation go the same way.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use hyper::Headers;
+
+use stq_types::UserId;
+
+use config::{CanaryRoute, Config};
+
+pub const CANARY_HEADER: &str = "X-Canary";
+
+#[derive(Clone, Debug)]
+struct ActiveRoute {
+    service: String,
+    base_url: String,
+    canary_url: String,
+}
+
+/// Canary routes chosen for a single request
+#[derive(Clone, Debug, Default)]
+pub struct CanaryRouting {
+    routes: Vec<ActiveRoute>,
+}
+
+impl CanaryRouting {
+    /// `client_key` keeps percentage based routing sticky for the same client
+    pub fn select(config: &Config, headers: &Headers, user_id: Option<UserId>, client_key: &str) -> Self {
+        let header_values = headers
+            .get_raw(CANARY_HEADER)
+            .map(|raw| {
+                raw.iter()
+                    .filter_map(|line| ::std::str::from_utf8(line).ok())
+                    .flat_map(|line| line.split(','))
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let routes = config
+            .upstreams()
+            .into_iter()
+            .filter_map(|(service, microservice)| {
+                let route = config.canary.get(service)?;
+                if matches(service, route, &header_values, user_id, client_key) {
+                    Some(ActiveRoute {
+                        service: service.to_string(),
+                        base_url: microservice.url.clone(),
+                        canary_url: route.url.clone(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Self { routes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Names of microservices routed to canary, for logs
+    pub fn services(&self) -> Vec<&str> {
+        self.routes.iter().map(|route| route.service.as_str()).collect()
+    }
+
+    /// Replaces base url of microservice with canary url if microservice is routed to canary
+    pub fn route(&self, url: &str) -> String {
+        self.routes
+            .iter()
+            .find(|route| url.starts_with(route.base_url.as_str()))
+            .map(|route| format!("{}{}", route.canary_url, &url[route.base_url.len()..]))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+fn matches(service: &str, route: &CanaryRoute, header_values: &[String], user_id: Option<UserId>, client_key: &str) -> bool {
+    if header_values.iter().any(|value| value == service || route.header_values.contains(value)) {
+        return true;
+    }
+    if let Some(user_id) = user_id {
+        if route.user_ids.contains(&user_id.0) {
+            return true;
+        }
+    }
+    match route.percentage {
+        Some(percentage) if percentage > 0 => {
+            let mut hasher = DefaultHasher::new();
+            (service, client_key).hash(&mut hasher);
+            hasher.finish() % 100 < u64::from(percentage)
+        }
+        _ => false,
+    }
+}
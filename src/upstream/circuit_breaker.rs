@@ -0,0 +1,142 @@
+//! Circuit breakers for upstream microservices. After `failure_threshold` consecutive failures
+//! circuit opens and requests fail immediately. After `open_duration_ms` a single probe request
+//! is let through, which either closes the circuit or opens it again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use juniper::FieldError;
+
+use config::{CircuitBreakerConfig, Config};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probe_until: Instant },
+}
+
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: String, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name,
+            config,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks if request can be sent to upstream
+    pub fn acquire(&self) -> bool {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return true,
+        };
+        let now = Instant::now();
+        match *state {
+            State::Closed { .. } => true,
+            // unfinished probe is given the same time as circuit is open
+            State::Open { until } | State::HalfOpen { probe_until: until } if now >= until => {
+                *state = State::HalfOpen {
+                    probe_until: now + Duration::from_millis(self.config.open_duration_ms),
+                };
+                true
+            }
+            State::Open { .. } | State::HalfOpen { .. } => false,
+        }
+    }
+
+    pub fn on_success(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = State::Closed { failures: 0 };
+        }
+    }
+
+    pub fn on_failure(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            let open = State::Open {
+                until: Instant::now() + Duration::from_millis(self.config.open_duration_ms),
+            };
+            *state = match *state {
+                State::Closed { failures } if failures + 1 < self.config.failure_threshold => State::Closed { failures: failures + 1 },
+                State::Open { until } => State::Open { until },
+                _ => {
+                    warn!("Circuit breaker for {} microservice is open", self.name);
+                    open
+                }
+            };
+        }
+    }
+
+    pub fn state_name(&self) -> &'static str {
+        match self.state.lock().map(|state| *state) {
+            Ok(State::Closed { .. }) => "closed",
+            Ok(State::Open { .. }) => "open",
+            Ok(State::HalfOpen { .. }) => "half_open",
+            Err(_) => "unknown",
+        }
+    }
+}
+
+/// Circuit breakers of all upstream microservices, matched by base url
+#[derive(Default)]
+pub struct CircuitBreakers {
+    breakers: Vec<(String, Arc<CircuitBreaker>)>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: &Config) -> Self {
+        let breakers = config
+            .upstreams()
+            .into_iter()
+            .filter_map(|(name, microservice)| {
+                microservice
+                    .circuit_breaker
+                    .clone()
+                    .or_else(|| config.gateway.circuit_breaker.clone())
+                    .map(|breaker_config| {
+                        (
+                            microservice.url.clone(),
+                            Arc::new(CircuitBreaker::new(name.to_string(), breaker_config)),
+                        )
+                    })
+            })
+            .collect();
+
+        Self { breakers }
+    }
+
+    pub fn for_url(&self, url: &str) -> Option<Arc<CircuitBreaker>> {
+        self.breakers
+            .iter()
+            .filter(|&&(ref base_url, _)| url.starts_with(base_url.as_str()))
+            .max_by_key(|&&(ref base_url, _)| base_url.len())
+            .map(|&(_, ref breaker)| breaker.clone())
+    }
+
+    /// State of every circuit breaker by microservice name
+    pub fn states(&self) -> HashMap<String, &'static str> {
+        self.breakers
+            .iter()
+            .map(|&(_, ref breaker)| (breaker.name().to_string(), breaker.state_name()))
+            .collect()
+    }
+}
+
+pub fn upstream_unavailable_error(service: &str) -> FieldError {
+    let service = service.to_string();
+    FieldError::new(
+        "Microservice is unavailable.",
+        graphql_value!({ "code": 201, "type": "UPSTREAM_UNAVAILABLE", "details": { "service": service }}),
+    )
+}
@@ -0,0 +1,67 @@
+//! Deadline of the whole GraphQL operation. Every upstream call gets only the remaining budget,
+//! which is also forwarded to microservices in `Request-Timeout` header.
+
+use std::time::{Duration, Instant};
+
+use hyper::Headers;
+use juniper::FieldError;
+
+use config::Gateway;
+
+/// Milliseconds left to handle the request, sent by clients and forwarded to microservices
+pub const REQUEST_TIMEOUT_HEADER: &str = "Request-Timeout";
+
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    at: Instant,
+}
+
+impl Deadline {
+    pub fn new(timeout: Duration) -> Self {
+        Self { at: Instant::now() + timeout }
+    }
+
+    /// Client can only shorten the configured operation timeout
+    pub fn from_headers(headers: &Headers, config: &Gateway) -> Self {
+        let requested_ms = headers
+            .get_raw(REQUEST_TIMEOUT_HEADER)
+            .and_then(|raw| raw.one())
+            .and_then(|value| ::std::str::from_utf8(value).ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let timeout_ms = match requested_ms {
+            Some(requested_ms) => requested_ms.min(config.operation_timeout_ms),
+            None => config.operation_timeout_ms,
+        };
+
+        Self::new(Duration::from_millis(timeout_ms))
+    }
+
+    /// Returns `None` if deadline has passed
+    pub fn remaining(&self) -> Option<Duration> {
+        let now = Instant::now();
+        if now < self.at {
+            Some(self.at - now)
+        } else {
+            None
+        }
+    }
+
+    pub fn remaining_ms(&self) -> Option<u64> {
+        let remaining = self.remaining()?;
+        match remaining.as_secs() * 1000 + u64::from(remaining.subsec_nanos() / 1_000_000) {
+            0 => None,
+            ms => Some(ms),
+        }
+    }
+
+    pub fn set_header(&self, headers: &mut Headers) {
+        headers.set_raw(REQUEST_TIMEOUT_HEADER, self.remaining_ms().unwrap_or(0).to_string());
+    }
+}
+
+pub fn deadline_exceeded_error() -> FieldError {
+    FieldError::new(
+        "Operation deadline exceeded.",
+        graphql_value!({ "code": 202, "type": "DEADLINE_EXCEEDED", "details": { "Operation took longer than allowed." }}),
+    )
+}
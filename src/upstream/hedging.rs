@@ -0,0 +1,129 @@
+//! Hedged requests: if an idempotent read is not answered within a percentile of its usual latency,
+//! the same request is sent again and whichever answers first wins, the other one is cancelled.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either};
+use futures::prelude::*;
+use hyper::Method;
+
+use stq_http::client::Error;
+
+use config::HedgingConfig;
+
+use super::path_matches;
+use super::timer::Timer;
+
+/// Number of latest latencies the percentile is calculated from
+const MAX_SAMPLES: usize = 1000;
+/// `min_delay_ms` is used until this number of latencies is observed,
+/// after that percentile is recalculated every time this number of latencies is observed
+const MIN_SAMPLES: usize = 20;
+
+#[derive(Default)]
+struct Latencies {
+    samples: VecDeque<Duration>,
+    percentile: Option<Duration>,
+    since_calculated: usize,
+}
+
+pub struct Hedger {
+    base_url: String,
+    config: HedgingConfig,
+    latencies: Mutex<Latencies>,
+}
+
+impl Hedger {
+    pub fn new(base_url: String, config: HedgingConfig) -> Self {
+        Self {
+            base_url,
+            config,
+            latencies: Mutex::new(Latencies {
+                samples: VecDeque::with_capacity(MAX_SAMPLES),
+                ..Latencies::default()
+            }),
+        }
+    }
+
+    /// Only reads are hedged - `GET` requests and `POST` ones to endpoints marked as read only
+    pub fn matches(&self, method: &Method, url: &str) -> bool {
+        let is_read = match *method {
+            Method::Get => true,
+            Method::Post => self.config.read_only_post,
+            _ => false,
+        };
+        if !is_read || !url.starts_with(self.base_url.as_str()) {
+            return false;
+        }
+
+        let path = url[self.base_url.len()..].split('?').next().unwrap_or_default();
+        path_matches(&self.config.path, path)
+    }
+
+    pub fn delay(&self) -> Duration {
+        let min_delay = Duration::from_millis(self.config.min_delay_ms);
+        match self.latencies.lock().ok().and_then(|latencies| latencies.percentile) {
+            Some(percentile) => percentile.max(min_delay),
+            None => min_delay,
+        }
+    }
+
+    fn record(&self, latency: Duration) {
+        if let Ok(mut latencies) = self.latencies.lock() {
+            if latencies.samples.len() >= MAX_SAMPLES {
+                latencies.samples.pop_front();
+            }
+            latencies.samples.push_back(latency);
+            latencies.since_calculated += 1;
+            if latencies.since_calculated >= MIN_SAMPLES {
+                latencies.percentile = Some(self.percentile(&latencies.samples));
+                latencies.since_calculated = 0;
+            }
+        }
+    }
+
+    fn percentile(&self, samples: &VecDeque<Duration>) -> Duration {
+        let mut samples = samples.iter().cloned().collect::<Vec<_>>();
+        samples.sort();
+        let percentile = self.config.percentile.max(0.0).min(100.0) / 100.0;
+        let index = (percentile * (samples.len() - 1) as f64).round() as usize;
+        samples[index]
+    }
+
+    pub fn hedge<T, F>(hedger: Arc<Self>, timer: &Timer, send: F) -> Box<Future<Item = T, Error = Error> + Send>
+    where
+        T: 'static + Send,
+        F: Fn() -> Box<Future<Item = T, Error = Error> + Send> + Send + 'static,
+    {
+        let timed_send = {
+            let hedger = hedger.clone();
+            move || -> Box<Future<Item = T, Error = Error> + Send> {
+                let hedger = hedger.clone();
+                let started_at = Instant::now();
+                // every completed attempt is recorded, failed ones included, so that slow errors raise the delay
+                // as well. Attempts cancelled by a faster one are not, which biases the percentile towards
+                // faster responses while hedging is frequent: the delay is driven by attempts that were not cancelled.
+                Box::new(send().then(move |r| {
+                    hedger.record(started_at.elapsed());
+                    r
+                }))
+            }
+        };
+
+        let first = timed_send();
+        let delay = timer.delay(hedger.delay());
+        Box::new(first.select2(delay).then(move |r| -> Box<Future<Item = T, Error = Error> + Send> {
+            match r {
+                Ok(Either::A((x, _))) => Box::new(future::ok(x)),
+                Err(Either::A((e, _))) => Box::new(future::err(e)),
+                Ok(Either::B((_, first))) | Err(Either::B((_, first))) => {
+                    debug!("Hedging request to {}", hedger.config.path);
+                    // the slower request is dropped, which cancels it
+                    Box::new(future::select_ok(vec![first, timed_send()]).map(|(x, _)| x))
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,291 @@
+//! Http clients of upstream microservices with per service timeout, retries, pool size, load balancing and hedging
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{self, Either, Loop};
+use futures::prelude::*;
+use futures::stream::Stream;
+use hyper::{Headers, Method};
+use juniper::FieldError;
+use serde::de::DeserializeOwned;
+use tokio_core::reactor::Handle;
+
+use stq_http::client::{Client, ClientHandle, Error, HttpClient, TimeLimitedHttpClient};
+
+use config::{Config, Microservice};
+
+use super::balancer::{Balancer, Endpoint, InFlightGuard};
+use super::deadline::Deadline;
+use super::hedging::Hedger;
+use super::timer::Timer;
+
+/// Network errors, timeouts and server errors are worth retrying,
+/// client errors mean that microservice is alive
+pub fn is_upstream_failure(e: &Error) -> bool {
+    match *e {
+        Error::Api(ref status, _) => status.is_server_error(),
+        _ => true,
+    }
+}
+
+#[derive(Clone)]
+pub struct UpstreamClient {
+    handle: ClientHandle,
+    timeout: Duration,
+    retries: usize,
+    retry_backoff: Duration,
+    balancer: Option<Arc<Balancer>>,
+    hedgers: Vec<Arc<Hedger>>,
+    timer: Timer,
+}
+
+impl UpstreamClient {
+    fn new(handle: ClientHandle, config: &Config, microservice: Option<&Microservice>, timer: Timer) -> Self {
+        let gateway = &config.gateway;
+        Self {
+            handle,
+            timer,
+            timeout: Duration::from_millis(
+                microservice
+                    .and_then(|microservice| microservice.timeout_ms)
+                    .unwrap_or(gateway.http_timeout_ms),
+            ),
+            retries: microservice
+                .and_then(|microservice| microservice.retries)
+                .unwrap_or(gateway.http_client_retries),
+            retry_backoff: Duration::from_millis(
+                microservice
+                    .and_then(|microservice| microservice.retry_backoff_ms)
+                    .unwrap_or(gateway.http_retry_backoff_ms),
+            ),
+            balancer: microservice.and_then(|microservice| Balancer::new(microservice, gateway.ejection.as_ref())),
+            hedgers: microservice
+                .map(|microservice| {
+                    microservice
+                        .hedging
+                        .iter()
+                        .map(|hedging| Arc::new(Hedger::new(microservice.url.clone(), hedging.clone())))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Timeout of a single request to microservice
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sends request, only idempotent `GET` requests are retried.
+    /// Neither single attempt nor retries exceed the `deadline`.
+    pub fn request_json<T>(
+        &self,
+        method: Method,
+        url: String,
+        body: Option<String>,
+        headers: Option<Headers>,
+        deadline: Option<Deadline>,
+    ) -> Box<Future<Item = T, Error = Error> + Send>
+    where
+        T: DeserializeOwned + 'static + Send,
+    {
+        let retries = if method == Method::Get { self.retries } else { 0 };
+        let retry_backoff = self.retry_backoff;
+        let hedger = self.hedgers.iter().find(|hedger| hedger.matches(&method, &url)).cloned();
+        let client = self.clone();
+
+        Box::new(future::loop_fn(0, move |attempt| {
+            let url_for_log = url.clone();
+            let backoff = retry_backoff * (attempt as u32 + 1);
+            let can_retry = move || match deadline.and_then(|deadline| deadline.remaining()) {
+                Some(remaining) => remaining > backoff,
+                None => deadline.is_none(),
+            };
+
+            let send = {
+                let (client, method, url, body, headers) = (client.clone(), method.clone(), url.clone(), body.clone(), headers.clone());
+                move || client.send::<T>(method.clone(), &url, body.clone(), headers.clone(), deadline)
+            };
+            let response = match hedger {
+                Some(ref hedger) => Hedger::hedge(hedger.clone(), &client.timer, send),
+                None => send(),
+            };
+
+            let timer = client.timer.clone();
+            response.then(move |r| -> Box<Future<Item = Loop<T, usize>, Error = Error> + Send> {
+                match r {
+                    Err(ref e) if attempt < retries && is_upstream_failure(e) && can_retry() => {
+                        warn!("Request to microservice: {:?} failed with error `{:?}`, retrying", url_for_log, e);
+                        Box::new(timer.delay(backoff).then(move |_| Ok(Loop::Continue(attempt + 1))))
+                    }
+                    Err(e) => Box::new(future::err(e)),
+                    Ok(x) => Box::new(future::ok(Loop::Break(x))),
+                }
+            })
+        }))
+    }
+
+    /// Sends single request to one of microservice endpoints
+    fn send<T>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<String>,
+        mut headers: Option<Headers>,
+        deadline: Option<Deadline>,
+    ) -> Box<Future<Item = T, Error = Error> + Send>
+    where
+        T: DeserializeOwned + 'static + Send,
+    {
+        let endpoint = self
+            .balancer
+            .as_ref()
+            .and_then(|balancer| balancer.pick().map(|endpoint| (balancer.clone(), endpoint)));
+        let (request_url, in_flight) = match endpoint {
+            Some((ref balancer, ref endpoint)) => (balancer.rewrite(url, endpoint), Some(balancer.start(endpoint.clone()))),
+            None => (url.to_string(), None),
+        };
+        let timeout = match deadline {
+            Some(deadline) => {
+                deadline.set_header(headers.get_or_insert_with(Headers::new));
+                deadline.remaining().map(|remaining| remaining.min(self.timeout)).unwrap_or_default()
+            }
+            None => self.timeout,
+        };
+
+        Box::new(
+            TimeLimitedHttpClient::new(self.handle.clone(), timeout)
+                .request_json::<T>(method, request_url, body, headers)
+                .then(move |r| {
+                    drop(in_flight);
+                    let cut_by_deadline = deadline.map_or(false, |deadline| deadline.remaining().is_none());
+                    if let Some((balancer, endpoint)) = endpoint {
+                        match r {
+                            Err(ref e) if is_upstream_failure(e) && !cut_by_deadline => balancer.on_failure(&endpoint),
+                            Err(_) => {}
+                            Ok(_) => balancer.on_success(&endpoint),
+                        }
+                    }
+                    r
+                }),
+        )
+    }
+}
+
+/// Clients of every upstream microservice, matched by base url
+#[derive(Clone)]
+pub struct HttpClients {
+    default: UpstreamClient,
+    upstreams: Vec<(String, UpstreamClient)>,
+    timer: Timer,
+}
+
+impl HttpClients {
+    pub fn new(config: &Config, handle: &Handle) -> Self {
+        let timer = Timer::new();
+        let default = UpstreamClient::new(spawn_client(config.to_http_config(), handle), config, None, timer.clone());
+        let upstreams = config
+            .upstreams()
+            .into_iter()
+            .map(|(_, microservice)| {
+                let client_handle = spawn_client(config.to_http_config_for(microservice), handle);
+                let client = UpstreamClient::new(client_handle, config, Some(microservice), timer.clone());
+                (microservice.url.clone(), client)
+            })
+            .collect();
+
+        Self { default, upstreams, timer }
+    }
+
+    pub fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    /// Waits for call made by another client, like `RestApiClient`, at most for `timeout`.
+    /// Resolves to `None` if the call has timed out.
+    pub fn wait_with_timeout<F: Future>(&self, call: F, timeout: Duration) -> Result<Option<F::Item>, F::Error> {
+        match call.select2(self.timer.delay(timeout)).wait() {
+            Ok(Either::A((x, _))) => Ok(Some(x)),
+            Err(Either::A((e, _))) => Err(e),
+            Ok(Either::B(_)) | Err(Either::B(_)) => Ok(None),
+        }
+    }
+
+    pub fn for_url(&self, url: &str) -> &UpstreamClient {
+        self.upstreams
+            .iter()
+            .filter(|&&(ref base_url, _)| url.starts_with(base_url.as_str()))
+            .max_by_key(|&&(ref base_url, _)| base_url.len())
+            .map(|&(_, ref client)| client)
+            .unwrap_or(&self.default)
+    }
+
+    /// Endpoint of microservice for clients which build urls themselves, counted as in flight until dropped.
+    /// Results of the call are reported to the balancer by `PickedEndpoint::on_success` and `on_failure`.
+    pub fn endpoint(&self, base_url: &str) -> PickedEndpoint {
+        let balancer = match self.for_url(base_url).balancer {
+            Some(ref balancer) => balancer.clone(),
+            None => return PickedEndpoint::unbalanced(base_url),
+        };
+        match balancer.pick() {
+            Some(endpoint) => PickedEndpoint {
+                url: balancer.rewrite(base_url, &endpoint),
+                in_flight: Some(balancer.start(endpoint.clone())),
+                balanced: Some((balancer, endpoint)),
+            },
+            None => PickedEndpoint::unbalanced(base_url),
+        }
+    }
+}
+
+/// Endpoint picked by balancer for a call made by another client, like `RestApiClient`
+pub struct PickedEndpoint {
+    url: String,
+    balanced: Option<(Arc<Balancer>, Arc<Endpoint>)>,
+    in_flight: Option<InFlightGuard>,
+}
+
+impl PickedEndpoint {
+    fn unbalanced(base_url: &str) -> Self {
+        Self {
+            url: base_url.to_string(),
+            balanced: None,
+            in_flight: None,
+        }
+    }
+
+    /// Base url of the endpoint
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn on_success(mut self) {
+        self.in_flight.take();
+        if let Some((ref balancer, ref endpoint)) = self.balanced {
+            balancer.on_success(endpoint);
+        }
+    }
+
+    pub fn on_failure(mut self) {
+        self.in_flight.take();
+        if let Some((ref balancer, ref endpoint)) = self.balanced {
+            balancer.on_failure(endpoint);
+        }
+    }
+}
+
+pub fn upstream_timeout_error(service: &str) -> FieldError {
+    let service = service.to_string();
+    FieldError::new(
+        "Microservice has not responded in time.",
+        graphql_value!({ "code": 200, "details": { "service": service }}),
+    )
+}
+
+fn spawn_client(http_config: ::stq_http::client::Config, handle: &Handle) -> ClientHandle {
+    let client = Client::new(&http_config, handle);
+    let client_handle = client.handle();
+    handle.spawn(client.stream().for_each(|_| Ok(())));
+    client_handle
+}
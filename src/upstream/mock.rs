@@ -0,0 +1,147 @@
+//! Offline mode: http server answering upstream calls from JSON fixture files,
+//! so that the whole schema can be run without microservices.
+//!
+//! Every `*.json` file in `fixtures_path` holds an array of fixtures:
+//! `[{ "method": "GET", "path": "/stores/stores/{id}", "status": 200, "body": {...} }]`.
+//! Path includes prefix of microservice url, `{..}` segment matches any id and trailing `*`
+//! matches the rest of path. Query parameters listed in fixture path must be present in request,
+//! `{..}` value matches any. The first matching fixture in file name order wins.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::future::{self, FutureResult};
+use futures::prelude::*;
+use hyper;
+use hyper::header::ContentType;
+use hyper::server::{Http, Request, Response, Service};
+use hyper::StatusCode;
+use serde_json;
+use tokio_core::reactor::Handle;
+
+use config::Mock;
+
+use super::path_matches;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    pub path: String,
+    #[serde(default = "default_status")]
+    pub status: u16,
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl Fixture {
+    fn matches(&self, method: &str, path: &str, query: Option<&str>) -> bool {
+        if !self.method.eq_ignore_ascii_case(method) {
+            return false;
+        }
+
+        let mut pattern = self.path.splitn(2, '?');
+        let path_pattern = pattern.next().unwrap_or_default();
+        let query_pattern = pattern.next();
+        if !path_matches(path_pattern, path) {
+            return false;
+        }
+
+        let params = query.map(|query| query.split('&').collect::<Vec<_>>()).unwrap_or_default();
+        query_pattern
+            .map(|query_pattern| {
+                query_pattern.split('&').all(|expected| match expected.find('=') {
+                    Some(i) if expected[i + 1..].starts_with('{') => params.iter().any(|param| param.starts_with(&expected[..=i])),
+                    _ => params.contains(&expected),
+                })
+            })
+            .unwrap_or(true)
+    }
+}
+
+/// Reads fixtures from all `*.json` files of directory
+pub fn load_fixtures(fixtures_path: &str) -> io::Result<Vec<Fixture>> {
+    let mut paths = fs::read_dir(fixtures_path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut fixtures = Vec::new();
+    for path in paths {
+        fixtures.extend(load_file(&path)?);
+    }
+    Ok(fixtures)
+}
+
+fn load_file(path: &Path) -> io::Result<Vec<Fixture>> {
+    let file = File::open(path)?;
+    serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+pub struct MockService {
+    fixtures: Arc<Vec<Fixture>>,
+}
+
+impl Service for MockService {
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = FutureResult<Response, hyper::Error>;
+
+    fn call(&self, req: Request) -> Self::Future {
+        let method = req.method().to_string();
+        let fixture = self
+            .fixtures
+            .iter()
+            .find(|fixture| fixture.matches(&method, req.path(), req.query()));
+
+        let (status, body) = match fixture {
+            Some(fixture) => (
+                StatusCode::try_from(fixture.status).unwrap_or(StatusCode::InternalServerError),
+                fixture.body.to_string(),
+            ),
+            None => {
+                let description = format!("No fixture for {} {}", method, req.uri());
+                warn!("{}", description);
+                (
+                    StatusCode::NotFound,
+                    json!({ "code": 404, "description": description, "payload": null }).to_string(),
+                )
+            }
+        };
+
+        future::ok(
+            Response::new()
+                .with_status(status)
+                .with_header(ContentType::json())
+                .with_body(body),
+        )
+    }
+}
+
+/// Starts mock microservices server on the event loop of gateway
+pub fn serve(config: &Mock, handle: &Handle) {
+    let fixtures = Arc::new(load_fixtures(&config.fixtures_path).expect("Can't load fixtures for offline mode"));
+    info!("Offline mode: serving {} fixtures on {}", fixtures.len(), config.url);
+
+    let address = config.url.parse().expect("Mock server address must be set in configuration");
+    let serve = Http::new()
+        .serve_addr_handle(&address, handle, move || Ok(MockService { fixtures: fixtures.clone() }))
+        .expect("Mock server initialization error");
+
+    let conn_handle = handle.clone();
+    handle.spawn(
+        serve
+            .for_each(move |conn| {
+                conn_handle.spawn(conn.map(|_| ()).map_err(|why| error!("Mock server error: {:?}", why)));
+                Ok(())
+            })
+            .map_err(|_| ()),
+    );
+}
@@ -0,0 +1,34 @@
+//! Calls to upstream microservices: resilience, load balancing, hedging, canary routing, offline mocks and recording
+pub mod balancer;
+pub mod canary;
+pub mod circuit_breaker;
+pub mod deadline;
+pub mod hedging;
+pub mod http;
+pub mod mock;
+pub mod recorder;
+pub mod timer;
+
+pub use self::canary::*;
+pub use self::circuit_breaker::*;
+pub use self::deadline::*;
+pub use self::http::*;
+
+/// `{..}` segment of pattern matches any segment of path, trailing `*` matches the rest of path
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern_segments = pattern.trim_matches('/').split('/');
+    let mut path_segments = path.trim_matches('/').split('/');
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (Some("*"), _) => return true,
+            (Some(pattern_segment), Some(path_segment)) => {
+                let is_placeholder = pattern_segment.starts_with('{') && pattern_segment.ends_with('}');
+                if !is_placeholder && pattern_segment != path_segment {
+                    return false;
+                }
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Record and replay of upstream traffic. Microservice urls are pointed to a local proxy,
+//! so that both `Context::request` and `RestApiClient` calls go through it.
+//!
+//! In `record` mode proxy forwards requests to microservices and appends every request/response pair
+//! to `<path>/<correlation token>.jsonl`. In `replay` mode the same requests with the same
+//! `Correlation-Token` header are answered from those files in recorded order.
+//!
+//! Recordings never hold credentials: headers, including `Authorization` and `Cookie`, are not written,
+//! values of sensitive JSON keys and query parameters (see `SENSITIVE_KEYS`) are replaced by `[REDACTED]`
+//! in both directions. Request bodies are redacted before matching on replay as well.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use futures::future;
+use futures::prelude::*;
+use hyper;
+use hyper::client::HttpConnector;
+use hyper::header::{ContentLength, ContentType, Host};
+use hyper::server::{Http, Request, Response, Service};
+use hyper::{Client, Method, StatusCode};
+use serde_json;
+use tokio_core::reactor::Handle;
+
+use config::{Config, Recorder as RecorderConfig, RecorderMode};
+
+const CORRELATION_TOKEN_HEADER: &str = "Correlation-Token";
+
+/// Keys whose values are not persisted, matched case insensitively as a part of key name
+pub const SENSITIVE_KEYS: &[&str] = &[
+    "password",
+    "token",
+    "secret",
+    "authorization",
+    "cookie",
+    "api_key",
+    "private_key",
+    "card_number",
+    "cvc",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub method: String,
+    /// Path with microservice name prefix and redacted query
+    pub path: String,
+    /// Redacted request body
+    pub request_body: String,
+    pub status: u16,
+    /// Redacted response body
+    pub response_body: String,
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE_KEYS.iter().any(|sensitive| key.contains(sensitive))
+}
+
+fn redact_value(value: &mut serde_json::Value) {
+    match *value {
+        serde_json::Value::Object(ref mut object) => {
+            for (key, value) in object.iter_mut() {
+                if is_sensitive(key) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(ref mut values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Replaces values of sensitive keys of JSON body, bodies which are not JSON are left as they are
+pub fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+/// Replaces values of sensitive query parameters of path
+pub fn redact_path(path: &str) -> String {
+    let mut parts = path.splitn(2, '?');
+    let path_part = parts.next().unwrap_or_default();
+    match parts.next() {
+        Some(query) => {
+            let query = query
+                .split('&')
+                .map(|param| match param.find('=') {
+                    Some(i) if is_sensitive(&param[..i]) => format!("{}={}", &param[..i], REDACTED),
+                    _ => param.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            format!("{}?{}", path_part, query)
+        }
+        None => path_part.to_string(),
+    }
+}
+
+impl Recording {
+    fn matches(&self, method: &str, path: &str, request_body: &str) -> bool {
+        self.method == method && self.path == path && self.request_body == request_body
+    }
+}
+
+/// Points every microservice to recorder proxy. Returns original urls keyed by microservice name.
+pub fn route_through_recorder(config: &mut Config) -> HashMap<String, String> {
+    let proxy_url = match config.recorder {
+        Some(ref recorder) => format!("http://{}", recorder.url),
+        None => return HashMap::new(),
+    };
+
+    let names = config.upstreams().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+    let mut upstreams = HashMap::new();
+    for name in names {
+        let microservice = config.upstream_mut(name);
+        upstreams.insert(name.to_string(), microservice.url.clone());
+        microservice.url = format!("{}/{}", proxy_url, name);
+        // instances are not balanced while recording
+        microservice.endpoints.clear();
+        microservice.dns_refresh_ms = None;
+    }
+    upstreams
+}
+
+struct Recordings {
+    path: PathBuf,
+    /// Replayed recordings by correlation token with flag if recording was already served
+    replayed: Mutex<HashMap<String, Vec<(Recording, bool)>>>,
+}
+
+impl Recordings {
+    fn file(&self, correlation_token: &str) -> PathBuf {
+        let name = correlation_token
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect::<String>();
+        self.path.join(format!("{}.jsonl", name))
+    }
+
+    fn append(&self, correlation_token: &str, recording: &Recording) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(self.file(correlation_token))?;
+        let line = serde_json::to_string(recording).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writeln!(file, "{}", line)
+    }
+
+    fn load(&self, correlation_token: &str) -> io::Result<Vec<Recording>> {
+        let file = File::open(self.file(correlation_token))?;
+        BufReader::new(file)
+            .lines()
+            .filter(|line| line.as_ref().map(|line| !line.trim().is_empty()).unwrap_or(true))
+            .map(|line| line.and_then(|line| serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))))
+            .collect()
+    }
+
+    /// Serves recordings in recorded order, the last one is repeated if request is made more times than recorded
+    fn replay(&self, correlation_token: &str, method: &str, path: &str, request_body: &str) -> Option<Recording> {
+        let mut replayed = self.replayed.lock().ok()?;
+        if !replayed.contains_key(correlation_token) {
+            let recordings = self.load(correlation_token).ok()?;
+            replayed.insert(
+                correlation_token.to_string(),
+                recordings.into_iter().map(|recording| (recording, false)).collect(),
+            );
+        }
+        let recordings = replayed.get_mut(correlation_token)?;
+
+        if let Some(&mut (ref recording, ref mut served)) = recordings
+            .iter_mut()
+            .find(|&&mut (ref recording, served)| !served && recording.matches(method, path, request_body))
+        {
+            *served = true;
+            return Some(recording.clone());
+        }
+        recordings
+            .iter()
+            .rev()
+            .find(|&&(ref recording, _)| recording.matches(method, path, request_body))
+            .map(|&(ref recording, _)| recording.clone())
+    }
+}
+
+pub struct RecorderService {
+    mode: RecorderMode,
+    upstreams: Arc<HashMap<String, String>>,
+    recordings: Arc<Recordings>,
+    client: Client<HttpConnector>,
+}
+
+impl RecorderService {
+    fn forward(&self, req: Request, path: String, correlation_token: Option<String>) -> Box<Future<Item = Response, Error = hyper::Error>> {
+        let mut segments = path.trim_left_matches('/').splitn(2, '/');
+        let name = segments.next().unwrap_or_default().to_string();
+        let rest = segments.next().unwrap_or_default().to_string();
+        let base_url = match self.upstreams.get(&name) {
+            Some(base_url) => base_url.clone(),
+            None => return Box::new(future::ok(not_found(&format!("Unknown microservice {}", name)))),
+        };
+        let uri = match format!("{}/{}", base_url, rest).parse() {
+            Ok(uri) => uri,
+            Err(e) => return Box::new(future::err(hyper::Error::from(e))),
+        };
+
+        let method = req.method().clone();
+        let mut headers = req.headers().clone();
+        headers.remove::<Host>();
+        headers.remove::<ContentLength>();
+        let client = self.client.clone();
+        let recordings = self.recordings.clone();
+
+        Box::new(req.body().concat2().and_then(move |request_body| {
+            let request_body = request_body.to_vec();
+            let mut upstream_req = Request::new(method.clone(), uri);
+            *upstream_req.headers_mut() = headers;
+            upstream_req.set_body(request_body.clone());
+
+            client.request(upstream_req).and_then(move |resp| {
+                let status = resp.status();
+                let headers = resp.headers().clone();
+                resp.body().concat2().map(move |response_body| {
+                    if let Some(correlation_token) = correlation_token {
+                        let recording = Recording {
+                            method: method.to_string(),
+                            path: redact_path(&path),
+                            request_body: redact_body(&String::from_utf8_lossy(&request_body)),
+                            status: status.as_u16(),
+                            response_body: redact_body(&String::from_utf8_lossy(&response_body)),
+                        };
+                        if let Err(e) = recordings.append(&correlation_token, &recording) {
+                            error!("Could not write recording: {}", e);
+                        }
+                    }
+                    Response::new().with_status(status).with_headers(headers).with_body(response_body.to_vec())
+                })
+            })
+        }))
+    }
+
+    fn replay(&self, req: Request, path: String, correlation_token: Option<String>) -> Box<Future<Item = Response, Error = hyper::Error>> {
+        let correlation_token = match correlation_token {
+            Some(correlation_token) => correlation_token,
+            None => return Box::new(future::ok(not_found("Request has no correlation token"))),
+        };
+        let method = req.method().to_string();
+        let path = redact_path(&path);
+        let recordings = self.recordings.clone();
+
+        Box::new(req.body().concat2().map(move |request_body| {
+            let request_body = redact_body(&String::from_utf8_lossy(&request_body));
+            match recordings.replay(&correlation_token, &method, &path, &request_body) {
+                Some(recording) => Response::new()
+                    .with_status(StatusCode::try_from(recording.status).unwrap_or(StatusCode::InternalServerError))
+                    .with_header(ContentType::json())
+                    .with_body(recording.response_body),
+                None => not_found(&format!("No recording for {} {} in {}", method, path, correlation_token)),
+            }
+        }))
+    }
+}
+
+impl Service for RecorderService {
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = Box<Future<Item = Response, Error = hyper::Error>>;
+
+    fn call(&self, req: Request) -> Self::Future {
+        let path = match req.query() {
+            Some(query) => format!("{}?{}", req.path(), query),
+            None => req.path().to_string(),
+        };
+        let correlation_token = req
+            .headers()
+            .get_raw(CORRELATION_TOKEN_HEADER)
+            .and_then(|raw| raw.one())
+            .map(|value| String::from_utf8_lossy(value).into_owned());
+
+        match self.mode {
+            RecorderMode::Record => self.forward(req, path, correlation_token),
+            RecorderMode::Replay => self.replay(req, path, correlation_token),
+        }
+    }
+}
+
+fn not_found(description: &str) -> Response {
+    warn!("{}", description);
+    Response::new()
+        .with_status(StatusCode::NotFound)
+        .with_header(ContentType::json())
+        .with_body(json!({ "code": 404, "description": description, "payload": null }).to_string())
+}
+
+/// Starts recorder proxy on the event loop of gateway, `upstreams` are original microservice urls
+pub fn serve(config: &RecorderConfig, upstreams: HashMap<String, String>, handle: &Handle) {
+    if let Err(e) = fs::create_dir_all(&config.path) {
+        error!("Could not create recordings directory {}: {}", config.path, e);
+    }
+    info!("Recorder in {:?} mode on {}, recordings in {}", config.mode, config.url, config.path);
+
+    let mode = config.mode;
+    let upstreams = Arc::new(upstreams);
+    let recordings = Arc::new(Recordings {
+        path: PathBuf::from(&config.path),
+        replayed: Mutex::new(HashMap::new()),
+    });
+    let client_handle = handle.clone();
+
+    let address = config.url.parse().expect("Recorder address must be set in configuration");
+    let serve = Http::new()
+        .serve_addr_handle(&address, handle, move || {
+            Ok(RecorderService {
+                mode,
+                upstreams: upstreams.clone(),
+                recordings: recordings.clone(),
+                client: Client::new(&client_handle),
+            })
+        })
+        .expect("Recorder initialization error");
+
+    let conn_handle = handle.clone();
+    handle.spawn(
+        serve
+            .for_each(move |conn| {
+                conn_handle.spawn(conn.map(|_| ()).map_err(|why| error!("Recorder error: {:?}", why)));
+                Ok(())
+            })
+            .map_err(|_| ()),
+    );
+}
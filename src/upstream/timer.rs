@@ -0,0 +1,129 @@
+//! Timer shared by upstream calls. Retry backoffs, hedging delays and timeouts are waited for without
+//! blocking a thread, and without reactor handle, which is not available on graphql thread pool.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::sync::oneshot;
+use futures::{Async, Future, Poll};
+
+/// Idle timer thread checks this often if the timer is still in use
+const IDLE_CHECK_MS: u64 = 1000;
+
+struct Entry {
+    at: Instant,
+    tx: oneshot::Sender<()>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    // the earliest entry is on top of the heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.at.cmp(&self.at)
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    entries: Mutex<BinaryHeap<Entry>>,
+    condvar: Condvar,
+}
+
+impl Shared {
+    fn entries(&self) -> MutexGuard<BinaryHeap<Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Delays are served by a single background thread, which exits when the last clone of timer is dropped
+#[derive(Clone)]
+pub struct Timer {
+    shared: Arc<Shared>,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        let shared = Arc::new(Shared::default());
+        let timer_shared = shared.clone();
+        thread::Builder::new()
+            .name("upstream-timer".to_string())
+            .spawn(move || run(&timer_shared))
+            .expect("Could not start upstream timer thread");
+
+        Self { shared }
+    }
+
+    pub fn delay(&self, duration: Duration) -> Delay {
+        let (tx, rx) = oneshot::channel();
+        let at = Instant::now() + duration;
+        let mut entries = self.shared.entries();
+        let is_earliest = entries.peek().map_or(true, |first| at < first.at);
+        entries.push(Entry { at, tx });
+        if is_earliest {
+            self.shared.condvar.notify_one();
+        }
+
+        Delay(rx)
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolves when the delay has passed
+pub struct Delay(oneshot::Receiver<()>);
+
+impl Future for Delay {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        match self.0.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            // sender is only dropped along with timer thread
+            Ok(Async::Ready(())) | Err(_) => Ok(Async::Ready(())),
+        }
+    }
+}
+
+fn run(shared: &Arc<Shared>) {
+    let mut entries = shared.entries();
+    loop {
+        let now = Instant::now();
+        while entries.peek().map_or(false, |first| first.at <= now) {
+            if let Some(entry) = entries.pop() {
+                // receiver is gone if the delay is not needed anymore
+                let _ = entry.tx.send(());
+            }
+        }
+
+        let wait = match entries.peek() {
+            Some(first) => first.at - now,
+            None if Arc::strong_count(shared) == 1 => return,
+            None => Duration::from_millis(IDLE_CHECK_MS),
+        };
+        entries = match shared.condvar.wait_timeout(entries, wait) {
+            Ok((entries, _)) => entries,
+            Err(e) => e.into_inner().0,
+        };
+    }
+}
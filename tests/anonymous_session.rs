@@ -0,0 +1,92 @@
+extern crate gateway_lib;
+extern crate hyper;
+extern crate jsonwebtoken;
+#[macro_use]
+extern crate serde_json;
+extern crate stq_types;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyper::header::Headers;
+use jsonwebtoken::{encode, Algorithm, Header};
+
+use gateway_lib::config::AnonymousSession;
+use gateway_lib::controller::anonymous_session::{issue, session_id_from_request, set_session_token};
+use gateway_lib::controller::cookies::ResponseCookies;
+use stq_types::SessionId;
+
+const SESSION_ID: &str = "4f1ed6a2-43a1-4b4e-9a3c-0d3b6c2f5e10";
+
+fn config() -> AnonymousSession {
+    AnonymousSession {
+        secret: "secret".to_string(),
+        idle_timeout_sec: 600,
+        cookie_name: Some("session".to_string()),
+        cookie_domain: None,
+    }
+}
+
+fn session_id() -> SessionId {
+    SESSION_ID.parse().unwrap()
+}
+
+fn session_id_of(headers: &Headers) -> Option<String> {
+    session_id_from_request(headers, &config()).unwrap().map(|id| id.to_string())
+}
+
+fn header(token: &str) -> Headers {
+    let mut headers = Headers::new();
+    headers.set_raw("SessionId", token.to_string());
+    headers
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+#[test]
+fn issued_token_is_accepted_from_header_and_cookie() {
+    let token = issue(&config(), Some(session_id())).unwrap().token;
+
+    assert_eq!(session_id_of(&header(&token)), Some(SESSION_ID.to_string()));
+
+    let mut headers = Headers::new();
+    headers.set_raw("Cookie", format!("session={}", token));
+    assert_eq!(session_id_of(&headers), Some(SESSION_ID.to_string()));
+}
+
+#[test]
+fn forged_token_is_rejected() {
+    let mut other_config = config();
+    other_config.secret = "other secret".to_string();
+    let forged = issue(&other_config, Some(session_id())).unwrap().token;
+
+    assert!(session_id_from_request(&header(&forged), &config()).is_err());
+    assert!(session_id_from_request(&header(SESSION_ID), &config()).is_err());
+}
+
+#[test]
+fn expired_token_starts_new_session() {
+    let claims = json!({ "session_id": SESSION_ID, "exp": now() - 60 });
+    let expired = encode(&Header::new(Algorithm::HS256), &claims, b"secret").unwrap();
+
+    assert_eq!(session_id_of(&header(&expired)), None);
+}
+
+#[test]
+fn refresh_keeps_session_id_and_prolongs_token() {
+    let claims = json!({ "session_id": SESSION_ID, "exp": now() + 10 });
+    let old_token = encode(&Header::new(Algorithm::HS256), &claims, b"secret").unwrap();
+    let session_id = session_id_from_request(&header(&old_token), &config()).unwrap();
+
+    let refreshed = issue(&config(), session_id).unwrap();
+    assert_eq!(refreshed.session_id.to_string(), SESSION_ID);
+    assert!(refreshed.exp >= now() + 599);
+
+    let cookies = ResponseCookies::default();
+    set_session_token(&cookies, &config(), &refreshed);
+    assert_eq!(cookies.take_session_token(), Some(refreshed.token.clone()));
+    let set_cookies = cookies.take();
+    assert_eq!(set_cookies.len(), 1);
+    assert!(set_cookies[0].starts_with(&format!("session={};", refreshed.token)));
+}
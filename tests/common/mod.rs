@@ -0,0 +1,318 @@
+//! Harness starting gateway against in-process fake microservices answering from fixtures
+
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::{encode, Algorithm, Header};
+use serde_json::{self, Value};
+
+use gateway_lib::config::{Config, Mock};
+use gateway_lib::graphql::models::jwt::JWTPayload;
+use stq_static_resources::Provider;
+use stq_types::UserId;
+
+const PRIVATE_KEY_PATH: &str = "config/keys/private_key.der";
+
+pub struct TestGateway {
+    address: String,
+    fixtures_path: PathBuf,
+}
+
+impl TestGateway {
+    /// Starts gateway with every microservice answered from `fixtures`,
+    /// array of `{ "method", "path", "status", "body" }` objects
+    pub fn start(fixtures: Value) -> Self {
+        Self::start_with(fixtures, |_| {})
+    }
+
+    /// Starts gateway with config changed by `configure`
+    pub fn start_with<F: FnOnce(&mut Config)>(fixtures: Value, configure: F) -> Self {
+        let gateway_address = free_address();
+        let mock_address = free_address();
+
+        let fixtures_path = env::temp_dir().join(format!("gateway-fixtures-{}", mock_address.replace(':', "-")));
+        fs::create_dir_all(&fixtures_path).expect("Can't create fixtures directory");
+        fs::write(fixtures_path.join("fixtures.json"), fixtures.to_string()).expect("Can't write fixtures");
+
+        let mut config = Config::new().expect("Can't load gateway configs");
+        config.gateway.url = gateway_address.clone();
+        let names = config.upstreams().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        for name in names {
+            let microservice = config.upstream_mut(name);
+            microservice.url = format!("http://{}/{}", mock_address, name);
+            microservice.hedging.clear();
+        }
+        config.mock = Some(Mock {
+            url: mock_address.clone(),
+            fixtures_path: fixtures_path.to_string_lossy().into_owned(),
+        });
+        config.rate_limit = None;
+        configure(&mut config);
+
+        thread::spawn(move || gateway_lib::start(config));
+        wait_for(&gateway_address);
+        wait_for(&mock_address);
+
+        Self {
+            address: gateway_address,
+            fixtures_path,
+        }
+    }
+
+    /// Posts GraphQL request and returns response body
+    pub fn graphql(&self, query: &str, variables: Value, token: Option<&str>) -> Value {
+        let authorization = token.map(|token| format!("Bearer {}", token));
+        let headers = authorization
+            .as_ref()
+            .map(|authorization| vec![("Authorization", authorization.as_str())]);
+        self.graphql_with_headers(query, variables, &headers.unwrap_or_default())
+    }
+
+    pub fn graphql_with_headers(&self, query: &str, variables: Value, headers: &[(&str, &str)]) -> Value {
+        self.graphql_response(query, variables, headers).1
+    }
+
+    /// Posts GraphQL request and returns response headers and body
+    pub fn graphql_response(&self, query: &str, variables: Value, headers: &[(&str, &str)]) -> (Vec<(String, String)>, Value) {
+        let body = json!({ "query": query, "variables": variables }).to_string();
+        let headers = headers
+            .iter()
+            .map(|&(name, value)| format!("{}: {}\r\n", name, value))
+            .collect::<String>();
+        let request = format!(
+            "POST /graphql HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\n\r\n{}",
+            self.address,
+            headers,
+            body.len(),
+            body
+        );
+
+        let (headers, body) = self.send(&request);
+        let body = serde_json::from_str(&body).unwrap_or_else(|e| panic!("Response is not json: {}, {}", e, body));
+        (headers, body)
+    }
+
+    /// Sends GET request and returns response body
+    pub fn get(&self, path: &str) -> String {
+        self.send(&format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, self.address)).1
+    }
+
+    fn send(&self, request: &str) -> (Vec<(String, String)>, String) {
+        let mut stream = TcpStream::connect(&self.address).expect("Can't connect to gateway");
+        stream.write_all(request.as_bytes()).expect("Can't send request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("Can't read response");
+
+        let mut parts = response.splitn(2, "\r\n\r\n");
+        let head = parts.next().unwrap_or_default();
+        let body = parts.next().expect("Response has no body").to_string();
+        let headers = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut header = line.splitn(2, ':');
+                Some((header.next()?.trim().to_string(), header.next()?.trim().to_string()))
+            })
+            .collect();
+        (headers, body)
+    }
+}
+
+impl Drop for TestGateway {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.fixtures_path);
+    }
+}
+
+/// JWT signed with the key pair gateway is configured with
+pub fn user_token(user_id: i32) -> String {
+    token(user_id, None)
+}
+
+/// JWT of superuser `actor_id` acting on behalf of `user_id`
+pub fn impersonation_token(actor_id: i32, user_id: i32) -> String {
+    token(user_id, Some(UserId(actor_id)))
+}
+
+fn token(user_id: i32, actor: Option<UserId>) -> String {
+    let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 + 3600;
+    let payload = JWTPayload {
+        user_id: UserId(user_id),
+        exp,
+        provider: Provider::Email,
+        actor,
+        user_session_id: None,
+    };
+    let key = fs::read(PRIVATE_KEY_PATH).expect("Can't read private key");
+    encode(&Header::new(Algorithm::RS256), &payload, &key).expect("Can't sign JWT")
+}
+
+/// User as returned by users microservice
+pub fn user(id: i32) -> Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    json!({
+        "id": id,
+        "email": format!("user{}@example.com", id),
+        "is_active": true,
+        "phone": null,
+        "first_name": "Test",
+        "last_name": "User",
+        "middle_name": null,
+        "gender": null,
+        "birthdate": null,
+        "avatar": null,
+        "is_blocked": false,
+        "emarsys_id": null,
+        "referal": null,
+        "utm_marks": null,
+        "country": null,
+        "referer": null,
+        "created_at": epoch,
+        "updated_at": epoch,
+        "revoke_before": epoch,
+    })
+}
+
+/// Fixture answering JWT revocation check of `user_token`
+pub fn user_session(id: &str, user_id: i32) -> Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    json!({
+        "id": id,
+        "user_id": user_id,
+        "device": null,
+        "ip": null,
+        "created_at": epoch,
+        "last_seen_at": epoch,
+        "revoked_at": null,
+    })
+}
+
+/// Product as returned by stores microservice
+pub fn product(id: i32, base_product_id: i32) -> Value {
+    json!({
+        "id": id,
+        "base_product_id": base_product_id,
+        "is_active": true,
+        "discount": null,
+        "photo_main": null,
+        "additional_photos": null,
+        "vendor_code": format!("CODE-{}", id),
+        "cashback": null,
+        "currency": "STQ",
+        "price": 100.0,
+        "pre_order": false,
+        "pre_order_days": 0,
+        "customer_price": { "price": 100.0, "currency": "STQ" },
+    })
+}
+
+/// Base product with a single product of the same id as returned by stores microservice
+pub fn base_product(id: i32, store_id: i32) -> Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    json!({
+        "id": id,
+        "is_active": true,
+        "store_id": store_id,
+        "name": [{ "lang": "en", "text": format!("Base product {}", id) }],
+        "short_description": [{ "lang": "en", "text": "Short description" }],
+        "long_description": null,
+        "seo_title": null,
+        "seo_description": null,
+        "currency": "STQ",
+        "category_id": 1,
+        "views": 0,
+        "rating": 0.0,
+        "slug": format!("base-product-{}", id),
+        "status": "published",
+        "variants": [product(id, id)],
+        "created_at": epoch,
+        "updated_at": epoch,
+        "length_cm": null,
+        "width_cm": null,
+        "height_cm": null,
+        "volume_cubic_cm": 1000,
+        "weight_g": 200,
+    })
+}
+
+/// Store as returned by stores microservice, `base_products` are set only for cart requests
+pub fn store(id: i32, base_products: Option<Vec<Value>>) -> Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    json!({
+        "id": id,
+        "user_id": 1,
+        "name": [{ "lang": "en", "text": format!("Store {}", id) }],
+        "is_active": true,
+        "short_description": [{ "lang": "en", "text": "Short description" }],
+        "long_description": null,
+        "slug": format!("store-{}", id),
+        "cover": null,
+        "logo": null,
+        "phone": null,
+        "email": null,
+        "address": null,
+        "facebook_url": null,
+        "twitter_url": null,
+        "instagram_url": null,
+        "default_language": "en",
+        "slogan": null,
+        "rating": 0.0,
+        "country": "Russia",
+        "country_code": "RUS",
+        "status": "published",
+        "administrative_area_level_1": null,
+        "administrative_area_level_2": null,
+        "locality": null,
+        "political": null,
+        "postal_code": null,
+        "route": null,
+        "street_number": null,
+        "place_id": null,
+        "created_at": epoch,
+        "updated_at": epoch,
+        "base_products": base_products,
+    })
+}
+
+pub fn current_user_fixture(id: i32) -> Value {
+    json!({ "method": "GET", "path": "/users/users/current", "body": user(id) })
+}
+
+pub fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|&&(ref header, _)| header.eq_ignore_ascii_case(name))
+        .map(|&(_, ref value)| value.as_str())
+}
+
+pub fn error_messages(response: &Value) -> Vec<String> {
+    response["errors"]
+        .as_array()
+        .map(|errors| {
+            errors
+                .iter()
+                .filter_map(|error| error["message"].as_str().map(|message| message.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn free_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Can't find free port");
+    listener.local_addr().expect("Can't get local address").to_string()
+}
+
+fn wait_for(address: &str) {
+    for _ in 0..100 {
+        if TcpStream::connect(address).is_ok() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("{} is not listening", address);
+}
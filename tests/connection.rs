@@ -0,0 +1,228 @@
+extern crate base64;
+extern crate gateway_lib;
+extern crate hyper;
+extern crate juniper;
+extern crate stq_routes;
+
+use std::str::FromStr;
+
+use hyper::Method;
+
+use stq_routes::model::Model;
+use stq_routes::service::Service;
+
+use gateway_lib::graphql::models::{Connection, ConnectionArgs, Cursor, PageInfo, TotalCount, ID};
+
+const RECORDS_LIMIT: i32 = 10;
+
+fn cursor(cursor: Cursor) -> Option<juniper::ID> {
+    Some(cursor.into())
+}
+
+fn nodes<T: Clone>(connection: &Connection<T, PageInfo>) -> Vec<T> {
+    connection.edges.iter().map(|edge| edge.node.clone()).collect()
+}
+
+fn edge_cursors<T>(connection: &Connection<T, PageInfo>) -> Vec<Cursor> {
+    connection
+        .edges
+        .iter()
+        .map(|edge| Cursor::from_str(&edge.cursor).unwrap())
+        .collect()
+}
+
+#[test]
+fn cursors_round_trip() {
+    for cursor in &[Cursor::Offset(0), Cursor::Offset(42), Cursor::Key(7), Cursor::Key(-1)] {
+        assert_eq!(Cursor::from_str(&cursor.to_string()).unwrap(), *cursor);
+    }
+}
+
+#[test]
+fn legacy_cursors_are_accepted() {
+    assert_eq!(Cursor::from_str("5").unwrap(), Cursor::Offset(5));
+
+    let node_id = ID::new(Service::Stores, Model::Store, 7).to_string();
+    assert_eq!(Cursor::from_str(&node_id).unwrap(), Cursor::Key(7));
+}
+
+#[test]
+fn invalid_cursors_are_rejected() {
+    assert!(Cursor::from_str("").is_err());
+    assert!(Cursor::from_str("not a cursor").is_err());
+    assert!(Cursor::from_str("-1").is_err());
+    assert!(Cursor::from_str(&base64::encode("cursor|offset|-1")).is_err());
+    assert!(Cursor::from_str(&base64::encode("cursor|page|1")).is_err());
+}
+
+#[test]
+fn first_and_after_window() {
+    let window = ConnectionArgs::forward(Some(2), None).offset_window(RECORDS_LIMIT).unwrap();
+    assert_eq!((window.offset, window.count), (0, 3));
+
+    let window = ConnectionArgs::forward(Some(2), cursor(Cursor::Offset(1)))
+        .offset_window(RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!((window.offset, window.count), (2, 3));
+
+    let window = ConnectionArgs::forward(Some(100), None).offset_window(RECORDS_LIMIT).unwrap();
+    assert_eq!((window.offset, window.count), (0, RECORDS_LIMIT + 1));
+
+    let window = ConnectionArgs::new(Some(5), cursor(Cursor::Offset(1)), None, cursor(Cursor::Offset(4)))
+        .offset_window(RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!((window.offset, window.count), (2, 3));
+}
+
+#[test]
+fn last_and_before_window() {
+    let window = ConnectionArgs::new(None, None, Some(2), cursor(Cursor::Offset(5)))
+        .offset_window(RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!((window.offset, window.count), (3, 2));
+
+    let window = ConnectionArgs::new(None, None, Some(5), cursor(Cursor::Offset(2)))
+        .offset_window(RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!((window.offset, window.count), (0, 2));
+}
+
+#[test]
+fn unsupported_windows_are_rejected() {
+    assert!(ConnectionArgs::new(Some(1), None, Some(1), None)
+        .offset_window(RECORDS_LIMIT)
+        .is_err());
+    assert!(ConnectionArgs::new(None, None, Some(1), None).offset_window(RECORDS_LIMIT).is_err());
+    assert!(ConnectionArgs::forward(Some(1), cursor(Cursor::Key(1)))
+        .offset_window(RECORDS_LIMIT)
+        .is_err());
+}
+
+#[test]
+fn slice_pages_forward() {
+    let all = vec![0, 1, 2, 3, 4];
+
+    let connection = ConnectionArgs::forward(Some(2), None).slice(all.clone(), RECORDS_LIMIT).unwrap();
+    assert_eq!(nodes(&connection), vec![0, 1]);
+    assert_eq!(edge_cursors(&connection), vec![Cursor::Offset(0), Cursor::Offset(1)]);
+    assert!(connection.page_info.has_next_page);
+    assert!(!connection.page_info.has_previous_page);
+
+    let connection = ConnectionArgs::forward(Some(2), cursor(Cursor::Offset(2)))
+        .slice(all.clone(), RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!(nodes(&connection), vec![3, 4]);
+    assert!(!connection.page_info.has_next_page);
+    assert!(connection.page_info.has_previous_page);
+
+    let connection = ConnectionArgs::forward(None, None).slice(all, RECORDS_LIMIT).unwrap();
+    assert_eq!(nodes(&connection), vec![0, 1, 2, 3, 4]);
+    assert!(!connection.page_info.has_next_page);
+    assert!(!connection.page_info.has_previous_page);
+}
+
+#[test]
+fn slice_pages_backward() {
+    let all = vec![0, 1, 2, 3, 4];
+
+    let connection = ConnectionArgs::new(None, None, Some(2), None)
+        .slice(all.clone(), RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!(nodes(&connection), vec![3, 4]);
+    assert!(!connection.page_info.has_next_page);
+    assert!(connection.page_info.has_previous_page);
+
+    let connection = ConnectionArgs::new(None, None, Some(2), cursor(Cursor::Offset(3)))
+        .slice(all.clone(), RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!(nodes(&connection), vec![1, 2]);
+    assert_eq!(edge_cursors(&connection), vec![Cursor::Offset(1), Cursor::Offset(2)]);
+    assert!(connection.page_info.has_next_page);
+    assert!(connection.page_info.has_previous_page);
+
+    let connection = ConnectionArgs::new(None, None, Some(5), cursor(Cursor::Offset(2)))
+        .slice(all, RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!(nodes(&connection), vec![0, 1]);
+    assert!(connection.page_info.has_next_page);
+    assert!(!connection.page_info.has_previous_page);
+}
+
+#[test]
+fn slice_past_the_end_is_empty() {
+    let connection = ConnectionArgs::forward(Some(2), cursor(Cursor::Offset(4)))
+        .slice(vec![0, 1, 2, 3, 4], RECORDS_LIMIT)
+        .unwrap();
+
+    assert!(connection.edges.is_empty());
+    assert!(!connection.page_info.has_next_page);
+    assert!(connection.page_info.has_previous_page);
+    assert!(connection.page_info.start_cursor.is_none());
+    assert!(connection.page_info.end_cursor.is_none());
+}
+
+#[test]
+fn slice_counts_whole_list() {
+    let connection = ConnectionArgs::forward(Some(2), None)
+        .slice(vec![0, 1, 2, 3, 4], RECORDS_LIMIT)
+        .unwrap();
+
+    match connection.total_count {
+        Some(TotalCount::Known(total)) => assert_eq!(total, 5),
+        ref other => panic!("Unexpected total count {:?}", other),
+    }
+}
+
+#[test]
+fn total_count_requests() {
+    match TotalCount::get("http://stores/stores/count".to_string()) {
+        TotalCount::Request { method, url, body } => {
+            assert_eq!(method, Method::Get);
+            assert_eq!(url, "http://stores/stores/count");
+            assert!(body.is_none());
+        }
+        other => panic!("Unexpected total count {:?}", other),
+    }
+
+    match TotalCount::post("http://stores/stores/search/count".to_string(), "{}".to_string()) {
+        TotalCount::Request { method, url, body } => {
+            assert_eq!(method, Method::Post);
+            assert_eq!(url, "http://stores/stores/search/count");
+            assert_eq!(body, Some("{}".to_string()));
+        }
+        other => panic!("Unexpected total count {:?}", other),
+    }
+}
+
+#[test]
+fn key_window_pages_forward_only() {
+    let window = ConnectionArgs::forward(Some(2), None).key_window(RECORDS_LIMIT).unwrap();
+    assert_eq!((window.after, window.count), (None, 3));
+
+    let connection = window.connection(vec![10, 11, 12], |node| *node);
+    assert_eq!(nodes(&connection), vec![10, 11]);
+    assert_eq!(edge_cursors(&connection), vec![Cursor::Key(10), Cursor::Key(11)]);
+    assert!(connection.page_info.has_next_page);
+    assert!(!connection.page_info.has_previous_page);
+
+    let window = ConnectionArgs::forward(Some(2), cursor(Cursor::Key(11)))
+        .key_window(RECORDS_LIMIT)
+        .unwrap();
+    assert_eq!(window.after, Some(11));
+
+    let connection = window.connection(vec![12], |node| *node);
+    assert_eq!(nodes(&connection), vec![12]);
+    assert!(!connection.page_info.has_next_page);
+    assert!(connection.page_info.has_previous_page);
+}
+
+#[test]
+fn key_window_rejects_backward_paging_and_offsets() {
+    assert!(ConnectionArgs::new(None, None, Some(2), None).key_window(RECORDS_LIMIT).is_err());
+    assert!(ConnectionArgs::new(Some(2), None, None, cursor(Cursor::Key(1)))
+        .key_window(RECORDS_LIMIT)
+        .is_err());
+    assert!(ConnectionArgs::forward(Some(2), cursor(Cursor::Offset(1)))
+        .key_window(RECORDS_LIMIT)
+        .is_err());
+}
@@ -0,0 +1,546 @@
+extern crate gateway_lib;
+extern crate hyper;
+extern crate jsonwebtoken;
+#[macro_use]
+extern crate serde_json;
+extern crate stq_routes;
+extern crate stq_static_resources;
+extern crate stq_types;
+extern crate uuid;
+
+mod common;
+
+use stq_routes::model::Model;
+use stq_routes::service::Service;
+use stq_static_resources::OrderState;
+use stq_types::{CartItem, CartItemId, CouponId, DeliveryMethodId, ProductId, Quantity, ShippingId, StoreId, UserId};
+use uuid::Uuid;
+
+use gateway_lib::config::{AnonymousSession, IntrospectionAccess, JWTCookie};
+use gateway_lib::controller::anonymous_session;
+use gateway_lib::graphql::models::{GatewayModel, RawId, ID};
+
+use common::*;
+
+const NODE_QUERY: &str = "query Node($id: ID!) { node(id: $id) { id ... on User { email } } }";
+
+const CREATE_ORDERS_MUTATION: &str = "mutation CreateOrders($input: CreateOrderInputV2!) {
+    createOrdersV2(input: $input) { invoice { id } }
+}";
+
+const SET_COUPON_MUTATION: &str = "mutation SetCoupon($input: SetCouponInCartInputV2!) {
+    setCouponInCartV2(input: $input) { id }
+}";
+
+fn create_orders_input() -> serde_json::Value {
+    json!({
+        "input": {
+            "clientMutationId": "1",
+            "addressFull": {},
+            "receiverName": "Receiver",
+            "receiverPhone": "+70000000000",
+            "currency": "STQ",
+            "userCountryCode": "RUS",
+        }
+    })
+}
+
+fn set_coupon_input() -> serde_json::Value {
+    json!({
+        "input": {
+            "clientMutationId": "1",
+            "couponCode": "CODE",
+            "storeId": 1,
+            "userCountryCode": "RUS",
+        }
+    })
+}
+
+/// Product `1` of store `1` in cart of user with shipping package `1` selected
+fn cart_item(user_id: i32, coupon_id: Option<i32>) -> serde_json::Value {
+    serde_json::to_value(CartItem {
+        id: CartItemId(Uuid::new_v4()),
+        customer: UserId(user_id).into(),
+        product_id: ProductId(1),
+        quantity: Quantity(1),
+        selected: true,
+        comment: String::new(),
+        store_id: StoreId(1),
+        pre_order: false,
+        pre_order_days: 0,
+        coupon_id: coupon_id.map(CouponId),
+        delivery_method_id: Some(DeliveryMethodId::ShippingPackage { id: ShippingId(1) }),
+    })
+    .unwrap()
+}
+
+/// Every call of cart client is answered with the same cart, whatever its route is
+fn cart_fixtures(cart: serde_json::Value) -> Vec<serde_json::Value> {
+    ["GET", "POST", "PUT", "DELETE"]
+        .iter()
+        .map(|method| json!({ "method": method, "path": "/orders/*", "body": cart }))
+        .collect()
+}
+
+fn coupon(id: i32) -> serde_json::Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    json!({
+        "id": id,
+        "code": "CODE",
+        "title": "Coupon",
+        "store_id": 1,
+        "scope": "BaseProducts",
+        "percent": 10,
+        "quantity": 1,
+        "expired_at": null,
+        "is_active": true,
+        "created_at": epoch,
+        "updated_at": epoch,
+    })
+}
+
+fn create_orders_fixtures(invoice_id: &str) -> serde_json::Value {
+    let epoch = json!({ "secs_since_epoch": 0, "nanos_since_epoch": 0 });
+    let mut fixtures = vec![
+        current_user_fixture(1),
+        json!({ "method": "GET", "path": "/users/users/1", "body": user(1) }),
+        json!({ "method": "GET", "path": "/stores/products/1/seller_price", "body": {
+            "price": 100.0,
+            "currency": "STQ",
+            "discount": null,
+        } }),
+        json!({ "method": "GET", "path": "/stores/products/1", "body": product(1, 1) }),
+        json!({ "method": "GET", "path": "/stores/base_products/1?visibility=published", "body": base_product(1, 1) }),
+        json!({ "method": "GET", "path": "/warehouses/*", "body": [{
+            "id": "7c9a1f5e-8f2b-4c4a-9d2e-3b5a6c7d8e90",
+            "store_id": 1,
+            "slug": "warehouse",
+            "country_code": "RUS",
+        }] }),
+        json!({ "method": "GET", "path": "/delivery/*", "body": {
+            "id": 1,
+            "shipping_id": 1,
+            "name": "Package",
+            "logo": "",
+            "price": 10.0,
+            "currency": "STQ",
+            "base_product_id": 1,
+            "store_id": 1,
+        } }),
+        json!({ "method": "POST", "path": "/saga/create_order", "body": {
+            "invoice_id": invoice_id,
+            "transactions": [],
+            "amount": 110.0,
+            "currency": "STQ",
+            "price_reserved": epoch,
+            "state": OrderState::InProcessing,
+            "wallet": null,
+            "amount_captured": 0.0,
+        } }),
+    ];
+    fixtures.extend(cart_fixtures(json!([cart_item(1, None)])));
+    json!(fixtures)
+}
+
+fn set_coupon_fixtures() -> serde_json::Value {
+    let mut fixtures = vec![
+        current_user_fixture(1),
+        json!({ "method": "POST", "path": "/stores/{model}/validate/code", "body": "Valid" }),
+        json!({ "method": "POST", "path": "/stores/{model}/search/code", "body": coupon(1) }),
+        json!({ "method": "GET", "path": "/stores/{model}/1/base_products", "body": [base_product(1, 1)] }),
+        json!({ "method": "POST", "path": "/stores/stores/cart", "body": [store(1, Some(vec![base_product(1, 1)]))] }),
+    ];
+    fixtures.extend(cart_fixtures(json!([cart_item(1, None)])));
+    json!(fixtures)
+}
+
+#[test]
+fn node_resolves_user() {
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": "/users/users/1", "body": user(1) },
+    ]));
+    let id = ID::new(Service::Users, Model::User, 1).to_string();
+
+    let response = gateway.graphql(NODE_QUERY, json!({ "id": id }), None);
+
+    assert_eq!(response["data"]["node"]["id"], json!(id));
+    assert_eq!(response["data"]["node"]["email"], json!("user1@example.com"));
+    assert!(response["errors"].is_null());
+}
+
+#[test]
+fn nodes_keep_order_of_ids() {
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": "/users/users/1", "body": user(1) },
+        { "method": "POST", "path": "/stores/base_products/search_by_ids", "body": [base_product(3, 1)] },
+    ]));
+    let user_id = ID::new(Service::Users, Model::User, 1).to_string();
+    let base_product_id = ID::new(Service::Stores, Model::BaseProduct, 3).to_string();
+    let missing_id = ID::new(Service::Stores, Model::BaseProduct, 4).to_string();
+    let query = "query Nodes($ids: [ID!]!) { nodes(ids: $ids) { id ... on User { email } } }";
+
+    let response = gateway.graphql(query, json!({ "ids": [base_product_id, "1", missing_id, user_id] }), None);
+
+    assert!(response["errors"].is_null());
+    assert_eq!(
+        response["data"]["nodes"],
+        json!([
+            { "id": base_product_id },
+            { "id": "1" },
+            null,
+            { "id": user_id, "email": "user1@example.com" },
+        ])
+    );
+}
+
+#[test]
+fn nodes_resolve_malformed_ids_to_null_with_error() {
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": "/users/users/1", "body": user(1) },
+    ]));
+    let user_id = ID::new(Service::Users, Model::User, 1).to_string();
+    let query = "query Nodes($ids: [ID!]!) { nodes(ids: $ids) { id } }";
+
+    let response = gateway.graphql(query, json!({ "ids": ["not an id", user_id] }), None);
+
+    assert_eq!(response["data"]["nodes"], json!([null, { "id": user_id }]));
+    assert_eq!(error_messages(&response), vec!["Id parsing error".to_string()]);
+}
+
+#[test]
+fn node_resolves_payout() {
+    let payout_id = "6bc0cf05-b74b-4c8f-9c8e-1a3b0b7c4c11";
+    let payout = json!({
+        "id": payout_id,
+        "gross_amount": "10",
+        "net_amount": "9.5",
+        "target": { "CryptoWallet": { "currency": "STQ", "wallet_address": "0x1", "blockchain_fee": "0.5" } },
+        "user_id": 1,
+        "status": { "Processing": { "initiated_at": "2018-10-01T12:30:00" } },
+        "order_ids": [],
+    });
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": format!("/billing/payouts/{}", payout_id), "body": payout },
+    ]));
+    let id = ID::with_gateway_model(
+        Service::Billing,
+        GatewayModel::Payout,
+        RawId::Uuid(Uuid::parse_str(payout_id).unwrap()),
+    )
+    .to_string();
+    let query = "query Node($id: ID!) { node(id: $id) { id ... on Payout { rawId } } }";
+
+    let response = gateway.graphql(query, json!({ "id": id }), None);
+
+    assert!(response["errors"].is_null());
+    assert_eq!(response["data"]["node"], json!({ "id": id, "rawId": payout_id }));
+}
+
+#[test]
+fn nodes_fail_on_too_many_ids() {
+    let gateway = TestGateway::start_with(json!([]), |config| config.gateway.records_limit = 2);
+    let ids = (1..4)
+        .map(|id| ID::new(Service::Users, Model::User, id).to_string())
+        .collect::<Vec<_>>();
+    let query = "query Nodes($ids: [ID!]!) { nodes(ids: $ids) { id } }";
+
+    let response = gateway.graphql(query, json!({ "ids": ids }), None);
+
+    assert!(response["data"].is_null());
+    assert_eq!(error_messages(&response), vec!["Too many ids".to_string()]);
+}
+
+#[test]
+fn timestamps_are_utc_date_times() {
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": "/users/users/1", "body": user(1) },
+    ]));
+    let id = ID::new(Service::Users, Model::User, 1).to_string();
+    let query = "query Node($id: ID!) { node(id: $id) { ... on User { createdAt } } }";
+
+    let response = gateway.graphql(query, json!({ "id": id }), None);
+
+    assert_eq!(response["data"]["node"]["createdAt"], json!("1970-01-01T00:00:00Z"));
+}
+
+#[test]
+fn node_fails_on_malformed_id() {
+    let gateway = TestGateway::start(json!([]));
+
+    let response = gateway.graphql(NODE_QUERY, json!({ "id": "not an id" }), None);
+
+    assert!(response["data"]["node"].is_null());
+    assert_eq!(error_messages(&response), vec!["Id parsing error".to_string()]);
+}
+
+#[test]
+fn node_fails_when_microservice_fails() {
+    let gateway = TestGateway::start(json!([
+        { "method": "GET", "path": "/users/users/{id}", "status": 500, "body": { "code": 500, "description": "Internal error", "payload": null } },
+    ]));
+    let id = ID::new(Service::Users, Model::User, 1).to_string();
+
+    let response = gateway.graphql(NODE_QUERY, json!({ "id": id }), None);
+
+    assert!(response["data"]["node"].is_null());
+    assert_eq!(error_messages(&response).len(), 1);
+}
+
+#[test]
+fn deprecated_field_usage_is_counted_by_client() {
+    let gateway = TestGateway::start(json!([current_user_fixture(1)]));
+
+    gateway.graphql("mutation { renewJWT { token } }", json!({}), Some(&user_token(1)));
+    let metrics = gateway.get("/metrics");
+
+    assert!(metrics.contains("gateway_deprecated_field_usage_total{field=\"Mutation.renewJWT\",client=\"user:1\"} 1"));
+}
+
+#[test]
+fn operations_are_counted_by_client() {
+    let gateway = TestGateway::start(json!([current_user_fixture(1)]));
+    let authorization = format!("Bearer {}", user_token(1));
+    let headers = [
+        ("Authorization", authorization.as_str()),
+        ("X-Client-Name", "web"),
+        ("X-Client-Version", "1.2.0"),
+    ];
+
+    let response = gateway.graphql_with_headers("query Viewer { me { rawId } }", json!({}), &headers);
+    assert_eq!(response["data"]["me"]["rawId"], json!(1));
+    let metrics = gateway.get("/metrics");
+
+    let labels = "operation=\"Viewer\",client=\"web\",version=\"1.2.0\"";
+    assert!(metrics.contains(&format!("gateway_operations_total{{{}}} 1", labels)));
+    assert!(metrics.contains(&format!("gateway_operation_errors_total{{{}}} 0", labels)));
+    assert!(metrics.contains(&format!(
+        "gateway_operation_upstream_calls_total{{{},microservice=\"users\"}} 1",
+        labels
+    )));
+}
+
+#[test]
+fn rejected_operations_are_counted_as_failed() {
+    let gateway = TestGateway::start_with(json!([]), |config| {
+        config.schema_explorer.introspection = IntrospectionAccess::Superusers
+    });
+    let headers = [("X-Client-Name", "web"), ("X-Client-Version", "1.2.0")];
+
+    let response = gateway.graphql_with_headers("query Schema { __schema { queryType { name } } }", json!({}), &headers);
+    assert_eq!(error_messages(&response), vec!["Introspection is forbidden.".to_string()]);
+    let metrics = gateway.get("/metrics");
+
+    let labels = "operation=\"Schema\",client=\"web\",version=\"1.2.0\"";
+    assert!(metrics.contains(&format!("gateway_operations_total{{{}}} 1", labels)));
+    assert!(metrics.contains(&format!("gateway_operation_errors_total{{{}}} 1", labels)));
+}
+
+#[test]
+fn metrics_are_served_to_allowed_ips_only() {
+    let gateway = TestGateway::start_with(json!([]), |config| config.metrics.allowed_ips.clear());
+
+    gateway.graphql("query Typename { __typename }", json!({}), None);
+    let metrics = gateway.get("/metrics");
+
+    assert!(!metrics.contains("gateway_operations_total"));
+}
+
+#[test]
+fn introspection_is_restricted_by_config() {
+    let gateway = TestGateway::start_with(json!([]), |config| {
+        config.schema_explorer.introspection = IntrospectionAccess::Superusers
+    });
+    let query = "{ __schema { queryType { name } } }";
+
+    let response = gateway.graphql(query, json!({}), None);
+    assert_eq!(error_messages(&response), vec!["Introspection is forbidden.".to_string()]);
+
+    let response = gateway.graphql("{ __typename }", json!({}), None);
+    assert_eq!(response["data"]["__typename"], json!("Query"));
+}
+
+#[test]
+fn jwt_cookie_requires_csrf_header() {
+    let gateway = TestGateway::start_with(json!([current_user_fixture(1)]), |config| {
+        config.jwt_cookie = Some(JWTCookie {
+            name: "jwt".to_string(),
+            csrf_cookie_name: "csrf".to_string(),
+            csrf_header_name: "X-CSRF-Token".to_string(),
+            domain: None,
+            same_site: "Strict".to_string(),
+            max_age: 3600,
+        })
+    });
+    let jwt_cookie = format!("jwt={}; csrf=secret", user_token(1));
+
+    let response = gateway.graphql_with_headers("{ me { rawId } }", json!({}), &[("Cookie", jwt_cookie.as_str())]);
+    assert_eq!(error_messages(&response), vec!["CSRF token mismatch.".to_string()]);
+
+    let headers = [("Cookie", jwt_cookie.as_str()), ("X-CSRF-Token", "secret")];
+    let response = gateway.graphql_with_headers("{ me { rawId } }", json!({}), &headers);
+    assert_eq!(response["data"]["me"]["rawId"], json!(1));
+
+    let response = gateway.graphql_with_headers("{ __typename }", json!({}), &[("Cookie", "jwt=; csrf=secret")]);
+    assert_eq!(response["data"]["__typename"], json!("Query"));
+}
+
+#[test]
+fn impersonation_token_can_not_be_renewed() {
+    let gateway = TestGateway::start(json!([current_user_fixture(2)]));
+    let token = impersonation_token(1, 2);
+
+    for mutation in &["mutation { renewJWT { token } }", "mutation { refreshJWT }"] {
+        let response = gateway.graphql(mutation, json!({}), Some(&token));
+        assert_eq!(
+            error_messages(&response),
+            vec!["Operation is forbidden while impersonating.".to_string()]
+        );
+    }
+}
+
+#[test]
+fn revoke_session_requires_session_owner() {
+    let own_session = "6bc0cf05-b74b-4c8f-9c8e-1a3b0b7c4c11";
+    let other_session = "0f5c4a9e-1c3e-4f4e-8f60-6a2b5b0f4d22";
+    let gateway = TestGateway::start(json!([
+        current_user_fixture(1),
+        { "method": "GET", "path": "/stores/roles/by-user-id/1", "body": [] },
+        { "method": "GET", "path": format!("/users/user_sessions/{}", own_session), "body": user_session(own_session, 1) },
+        { "method": "DELETE", "path": format!("/users/user_sessions/{}", own_session), "body": user_session(own_session, 1) },
+        { "method": "GET", "path": format!("/users/user_sessions/{}", other_session), "body": user_session(other_session, 2) },
+        { "method": "DELETE", "path": format!("/users/user_sessions/{}", other_session), "body": user_session(other_session, 2) },
+    ]));
+    let revoke = |id: &str, token: Option<&str>| {
+        gateway.graphql(
+            "mutation RevokeSession($input: RevokeSessionInput!) { revokeSession(input: $input) { id } }",
+            json!({ "input": { "clientMutationId": "1", "id": id } }),
+            token,
+        )
+    };
+
+    let response = revoke(own_session, None);
+    assert_eq!(
+        error_messages(&response),
+        vec!["Could not revoke session for unauthorized user.".to_string()]
+    );
+
+    let response = revoke(other_session, Some(&user_token(1)));
+    assert_eq!(error_messages(&response), vec!["Session not found.".to_string()]);
+
+    let response = revoke(own_session, Some(&user_token(1)));
+    assert_eq!(response["data"]["revokeSession"]["id"], json!(own_session));
+}
+
+#[test]
+fn session_token_sent_in_header_is_prolonged_in_header() {
+    let session_config = AnonymousSession {
+        secret: "secret".to_string(),
+        idle_timeout_sec: 600,
+        cookie_name: None,
+        cookie_domain: None,
+    };
+    let session_id = "4f1ed6a2-43a1-4b4e-9a3c-0d3b6c2f5e10";
+    let token = anonymous_session::issue(&session_config, session_id.parse().ok()).unwrap().token;
+    let gateway = TestGateway::start_with(json!([]), {
+        let session_config = session_config.clone();
+        move |config| config.anonymous_session = Some(session_config)
+    });
+
+    let (headers, response) = gateway.graphql_response("{ __typename }", json!({}), &[("SessionId", token.as_str())]);
+    assert_eq!(response["data"]["__typename"], json!("Query"));
+    let prolonged = header(&headers, "SessionId").expect("No prolonged session token");
+
+    let mut request_headers = hyper::Headers::new();
+    request_headers.set_raw("SessionId", prolonged.to_string());
+    let prolonged_session_id = anonymous_session::session_id_from_request(&request_headers, &session_config).unwrap();
+    assert_eq!(prolonged_session_id.map(|id| id.to_string()), Some(session_id.to_string()));
+
+    let (_, response) = gateway.graphql_response("{ __typename }", json!({}), &[("SessionId", "forged")]);
+    assert_eq!(error_messages(&response), vec!["Invalid session id.".to_string()]);
+}
+
+#[test]
+fn create_orders_requires_user() {
+    let gateway = TestGateway::start(json!([]));
+
+    let response = gateway.graphql(CREATE_ORDERS_MUTATION, create_orders_input(), None);
+
+    assert!(response["data"].is_null());
+    assert_eq!(
+        error_messages(&response),
+        vec!["Could not create orders for unauthorized user.".to_string()]
+    );
+}
+
+#[test]
+fn create_orders_creates_invoice_for_cart() {
+    let invoice_id = "1d6e2c3b-4a5f-4e6d-8c7b-9a0b1c2d3e4f";
+    let gateway = TestGateway::start(create_orders_fixtures(invoice_id));
+
+    let response = gateway.graphql(CREATE_ORDERS_MUTATION, create_orders_input(), Some(&user_token(1)));
+
+    assert!(response["errors"].is_null(), "{}", response);
+    assert_eq!(response["data"]["createOrdersV2"]["invoice"]["id"], json!(invoice_id));
+}
+
+#[test]
+fn create_orders_fails_when_orders_microservice_fails() {
+    let gateway = TestGateway::start(json!([current_user_fixture(1)]));
+
+    let response = gateway.graphql(CREATE_ORDERS_MUTATION, create_orders_input(), Some(&user_token(1)));
+
+    assert!(response["data"].is_null());
+    assert_eq!(error_messages(&response).len(), 1);
+}
+
+#[test]
+fn set_coupon_requires_user_or_session() {
+    let gateway = TestGateway::start(json!([]));
+
+    let response = gateway.graphql(SET_COUPON_MUTATION, set_coupon_input(), None);
+
+    assert!(response["data"]["setCouponInCartV2"].is_null());
+    assert_eq!(
+        error_messages(&response),
+        vec!["Could not set coupon in cart for unauthorized user.".to_string()]
+    );
+}
+
+#[test]
+fn set_coupon_returns_cart_of_user() {
+    let gateway = TestGateway::start(set_coupon_fixtures());
+
+    let response = gateway.graphql(SET_COUPON_MUTATION, set_coupon_input(), Some(&user_token(1)));
+
+    assert!(response["errors"].is_null(), "{}", response);
+    let cart_id = ID::new(Service::Orders, Model::Cart, 1).to_string();
+    assert_eq!(response["data"]["setCouponInCartV2"]["id"], json!(cart_id));
+}
+
+#[test]
+fn set_coupon_fails_when_coupon_is_already_applied() {
+    let mut fixtures = set_coupon_fixtures();
+    fixtures
+        .as_array_mut()
+        .unwrap()
+        .insert(0, json!({ "method": "GET", "path": "/orders/*", "body": [cart_item(1, Some(1))] }));
+    let gateway = TestGateway::start(fixtures);
+
+    let response = gateway.graphql(SET_COUPON_MUTATION, set_coupon_input(), Some(&user_token(1)));
+
+    assert!(response["data"]["setCouponInCartV2"].is_null());
+    assert_eq!(error_messages(&response), vec!["Coupon not set".to_string()]);
+}
+
+#[test]
+fn set_coupon_fails_for_unknown_coupon() {
+    let gateway = TestGateway::start(json!([
+        current_user_fixture(1),
+        { "method": "POST", "path": "/stores/{model}/validate/code", "body": null },
+    ]));
+
+    let response = gateway.graphql(SET_COUPON_MUTATION, set_coupon_input(), Some(&user_token(1)));
+
+    assert!(response["data"]["setCouponInCartV2"].is_null());
+    assert_eq!(error_messages(&response), vec!["Coupon not found".to_string()]);
+}
@@ -0,0 +1,114 @@
+extern crate base64;
+extern crate gateway_lib;
+extern crate stq_routes;
+extern crate uuid;
+
+use std::str::FromStr;
+
+use stq_routes::model::Model;
+use stq_routes::service::Service;
+use uuid::Uuid;
+
+use gateway_lib::graphql::models::{parse_uuid, GatewayModel, IdModel, RawId, ID};
+
+const UUID: &str = "6bc0cf05-b74b-4c8f-9c8e-1a3b0b7c4c11";
+
+fn uuid() -> Uuid {
+    Uuid::parse_str(UUID).unwrap()
+}
+
+#[test]
+fn raw_ids_round_trip() {
+    let raw_ids = vec![
+        RawId::Int(42),
+        RawId::Int(-1),
+        RawId::Uuid(uuid()),
+        RawId::Composite(vec![RawId::Uuid(uuid()), RawId::Int(7)]),
+    ];
+
+    for raw_id in raw_ids {
+        assert_eq!(RawId::from_str(&raw_id.to_string()).unwrap(), raw_id);
+    }
+}
+
+#[test]
+fn raw_ids_are_formatted_as_keys() {
+    assert_eq!(RawId::Int(42).to_string(), "42");
+    assert_eq!(RawId::Uuid(uuid()).to_string(), UUID);
+    assert_eq!(
+        RawId::Composite(vec![RawId::Uuid(uuid()), RawId::Int(7)]).to_string(),
+        format!("{}:7", UUID)
+    );
+}
+
+#[test]
+fn malformed_raw_ids_are_rejected() {
+    assert!(RawId::from_str("").is_err());
+    assert!(RawId::from_str("not a key").is_err());
+    assert!(RawId::from_str("1:not a key").is_err());
+}
+
+#[test]
+fn raw_id_accessors_check_key_kind() {
+    assert_eq!(RawId::Int(1).int().unwrap(), 1);
+    assert!(RawId::Int(1).uuid().is_err());
+    assert_eq!(RawId::Uuid(uuid()).uuid().unwrap(), uuid());
+    assert!(RawId::Uuid(uuid()).int().is_err());
+    assert_eq!(
+        RawId::Composite(vec![RawId::Int(1), RawId::Int(2)]).composite().unwrap(),
+        &[RawId::Int(1), RawId::Int(2)]
+    );
+    assert!(RawId::Int(1).composite().is_err());
+}
+
+#[test]
+fn global_ids_round_trip() {
+    let ids = vec![
+        ID::new(Service::Stores, Model::Store, 1),
+        ID::with_uuid(Service::Warehouses, Model::Warehouse, uuid()),
+        ID::with_key(
+            Service::Warehouses,
+            Model::Stock,
+            RawId::Composite(vec![RawId::Uuid(uuid()), RawId::Int(7)]),
+        ),
+        ID::with_gateway_model(Service::Billing, GatewayModel::Payout, RawId::Uuid(uuid())),
+        ID::with_gateway_model(Service::Billing, GatewayModel::StoreSubscription, RawId::Int(1)),
+        ID::with_gateway_model(Service::Billing, GatewayModel::Subscription, RawId::Int(2)),
+    ];
+
+    for id in ids {
+        let encoded = id.to_string();
+        let decoded = ID::from_str(&encoded).unwrap();
+        assert_eq!(decoded.to_string(), encoded);
+        assert_eq!(decoded.raw_id, id.raw_id);
+    }
+}
+
+#[test]
+fn gateway_models_are_decoded_as_gateway_models() {
+    let id = ID::with_gateway_model(Service::Billing, GatewayModel::Payout, RawId::Uuid(uuid())).to_string();
+
+    match ID::from_str(&id).unwrap().model {
+        IdModel::Gateway(model) => assert_eq!(model, GatewayModel::Payout),
+        IdModel::Route(model) => panic!("Unexpected model {}", model),
+    }
+}
+
+#[test]
+fn malformed_global_ids_are_rejected() {
+    assert!(ID::from_str("not base64!").is_err());
+    assert!(ID::from_str(&base64::encode("stores|store")).is_err());
+    assert!(ID::from_str(&base64::encode("stores|no_such_model|1")).is_err());
+    assert!(ID::from_str(&base64::encode("stores|store|not a key")).is_err());
+}
+
+#[test]
+fn uuids_are_parsed_raw_or_from_global_ids() {
+    assert_eq!(parse_uuid(UUID).unwrap(), uuid());
+
+    let id = ID::with_uuid(Service::Orders, Model::Order, uuid()).to_string();
+    assert_eq!(parse_uuid(&id).unwrap(), uuid());
+
+    let id = ID::new(Service::Stores, Model::Store, 1).to_string();
+    assert!(parse_uuid(&id).is_err());
+}
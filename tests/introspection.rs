@@ -0,0 +1,295 @@
+extern crate gateway_lib;
+#[macro_use]
+extern crate serde_json;
+
+use serde_json::Value;
+
+use gateway_lib::graphql::introspection::{breaking_changes, print_sdl, schema_of};
+
+fn named(kind: &str, name: &str) -> Value {
+    json!({ "kind": kind, "name": name, "ofType": null })
+}
+
+fn non_null(type_ref: Value) -> Value {
+    json!({ "kind": "NON_NULL", "name": null, "ofType": type_ref })
+}
+
+fn list(type_ref: Value) -> Value {
+    json!({ "kind": "LIST", "name": null, "ofType": type_ref })
+}
+
+fn string() -> Value {
+    named("SCALAR", "String")
+}
+
+fn input_value(name: &str, type_ref: Value) -> Value {
+    json!({ "name": name, "description": null, "type": type_ref, "defaultValue": null })
+}
+
+fn field(name: &str, type_ref: Value, args: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "description": null,
+        "args": args,
+        "type": type_ref,
+        "isDeprecated": false,
+        "deprecationReason": null,
+    })
+}
+
+fn object(name: &str, fields: Vec<Value>) -> Value {
+    json!({ "kind": "OBJECT", "name": name, "description": null, "fields": fields, "interfaces": [] })
+}
+
+fn input_object(name: &str, fields: Vec<Value>) -> Value {
+    json!({ "kind": "INPUT_OBJECT", "name": name, "description": null, "inputFields": fields })
+}
+
+fn enum_type(name: &str, values: &[&str]) -> Value {
+    let values = values
+        .iter()
+        .map(|value| json!({ "name": value, "description": null, "isDeprecated": false, "deprecationReason": null }))
+        .collect::<Vec<_>>();
+    json!({ "kind": "ENUM", "name": name, "description": null, "enumValues": values })
+}
+
+fn schema(types: Vec<Value>) -> Value {
+    json!({
+        "data": {
+            "__schema": {
+                "queryType": { "name": "Query" },
+                "mutationType": null,
+                "subscriptionType": null,
+                "types": types,
+                "directives": [],
+            }
+        }
+    })
+}
+
+/// Schema with `Query.user(id: ID!): User`, `User { name: String!, email: String, roles: [Role] }`,
+/// `UserInput { name: String }` and `Role { USER, ADMIN }`
+fn base_types() -> Vec<Value> {
+    vec![
+        object(
+            "Query",
+            vec![field(
+                "user",
+                named("OBJECT", "User"),
+                vec![input_value("id", non_null(named("SCALAR", "ID")))],
+            )],
+        ),
+        object(
+            "User",
+            vec![
+                field("name", non_null(string()), vec![]),
+                field("email", string(), vec![]),
+                field("roles", list(named("ENUM", "Role")), vec![]),
+            ],
+        ),
+        input_object("UserInput", vec![input_value("name", string())]),
+        enum_type("Role", &["USER", "ADMIN"]),
+    ]
+}
+
+fn with_type(mut types: Vec<Value>, type_: Value) -> Vec<Value> {
+    for existing in types.iter_mut() {
+        if existing["name"] == type_["name"] {
+            *existing = type_;
+            return types;
+        }
+    }
+    types.push(type_);
+    types
+}
+
+fn changes(new_types: Vec<Value>) -> Vec<String> {
+    breaking_changes(&schema(base_types()), &schema(new_types)).unwrap()
+}
+
+#[test]
+fn same_schema_has_no_breaking_changes() {
+    assert!(changes(base_types()).is_empty());
+}
+
+#[test]
+fn removed_field_is_breaking() {
+    let user = object(
+        "User",
+        vec![
+            field("name", non_null(string()), vec![]),
+            field("roles", list(named("ENUM", "Role")), vec![]),
+        ],
+    );
+
+    assert_eq!(changes(with_type(base_types(), user)), vec!["Field User.email was removed"]);
+}
+
+#[test]
+fn output_may_become_non_null() {
+    let user = object(
+        "User",
+        vec![
+            field("name", non_null(string()), vec![]),
+            field("email", non_null(string()), vec![]),
+            field("roles", non_null(list(non_null(named("ENUM", "Role")))), vec![]),
+        ],
+    );
+
+    assert!(changes(with_type(base_types(), user)).is_empty());
+}
+
+#[test]
+fn output_becoming_nullable_is_breaking() {
+    let user = object(
+        "User",
+        vec![
+            field("name", string(), vec![]),
+            field("email", string(), vec![]),
+            field("roles", list(named("ENUM", "Role")), vec![]),
+        ],
+    );
+
+    assert_eq!(
+        changes(with_type(base_types(), user)),
+        vec!["Field User.name changed type from String! to String"]
+    );
+}
+
+#[test]
+fn input_becoming_non_null_is_breaking() {
+    let input = input_object("UserInput", vec![input_value("name", non_null(string()))]);
+
+    assert_eq!(
+        changes(with_type(base_types(), input)),
+        vec!["Input field name of UserInput changed type from String to String!"]
+    );
+}
+
+#[test]
+fn input_may_become_nullable() {
+    let query = object(
+        "Query",
+        vec![field(
+            "user",
+            named("OBJECT", "User"),
+            vec![input_value("id", named("SCALAR", "ID"))],
+        )],
+    );
+
+    assert!(changes(with_type(base_types(), query)).is_empty());
+}
+
+#[test]
+fn added_required_argument_is_breaking() {
+    let query = object(
+        "Query",
+        vec![field(
+            "user",
+            named("OBJECT", "User"),
+            vec![
+                input_value("id", non_null(named("SCALAR", "ID"))),
+                input_value("storeId", non_null(named("SCALAR", "Int"))),
+                input_value("locale", string()),
+            ],
+        )],
+    );
+
+    assert_eq!(
+        changes(with_type(base_types(), query)),
+        vec!["Required argument storeId was added to Query.user"]
+    );
+}
+
+#[test]
+fn added_required_argument_with_default_is_safe() {
+    let mut store_id = input_value("storeId", non_null(named("SCALAR", "Int")));
+    store_id["defaultValue"] = json!("1");
+    let query = object(
+        "Query",
+        vec![field(
+            "user",
+            named("OBJECT", "User"),
+            vec![input_value("id", non_null(named("SCALAR", "ID"))), store_id],
+        )],
+    );
+
+    assert!(changes(with_type(base_types(), query)).is_empty());
+}
+
+#[test]
+fn removed_enum_value_is_breaking() {
+    let role = enum_type("Role", &["USER"]);
+
+    assert_eq!(changes(with_type(base_types(), role)), vec!["Value ADMIN was removed from Role"]);
+}
+
+#[test]
+fn removed_type_is_breaking() {
+    let types = base_types().into_iter().filter(|type_| type_["name"] != "UserInput").collect();
+
+    assert_eq!(changes(types), vec!["Type UserInput was removed"]);
+}
+
+#[test]
+fn schema_of_accepts_bare_schema() {
+    let bare = json!({ "__schema": schema(vec![])["data"]["__schema"].clone() });
+
+    assert!(schema_of(&bare).is_ok());
+    assert!(schema_of(&json!({ "data": null })).is_err());
+}
+
+#[test]
+fn sdl_prints_types_sorted_by_name() {
+    let sdl = print_sdl(&schema(base_types())).unwrap();
+
+    assert_eq!(
+        sdl,
+        "type Query {
+  user(id: ID!): User
+}
+
+enum Role {
+  USER
+  ADMIN
+}
+
+type User {
+  name: String!
+  email: String
+  roles: [Role]
+}
+
+input UserInput {
+  name: String
+}
+"
+    );
+}
+
+#[test]
+fn sdl_escapes_block_string_descriptions() {
+    let mut role = enum_type("Role", &["USER"]);
+    role["description"] = json!("Role, see \"\"\"docs\"\"\"");
+    let mut user = object("User", vec![field("name", string(), vec![])]);
+    user["fields"][0]["description"] = json!("First line\nsecond \"\"\" line");
+
+    let sdl = print_sdl(&schema(vec![role, user])).unwrap();
+
+    assert_eq!(
+        sdl,
+        "\"\"\"Role, see \\\"\"\"docs\\\"\"\"\"\"\"
+enum Role {
+  USER
+}
+
+type User {
+  \"\"\"
+  First line
+second \\\"\"\" line
+  \"\"\"
+  name: String
+}
+"
+    );
+}
@@ -0,0 +1,21 @@
+extern crate gateway_lib;
+
+use std::collections::HashMap;
+
+use gateway_lib::graphql::metrics::{bounded_key, escape_label, MAX_KEYS, OTHER};
+
+#[test]
+fn escape_label_escapes_quotes_backslashes_and_newlines() {
+    assert_eq!(escape_label("web"), "web");
+    assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+}
+
+#[test]
+fn bounded_key_buckets_new_keys_beyond_limit() {
+    let mut map = (0..MAX_KEYS).map(|i| (i.to_string(), 0)).collect::<HashMap<_, _>>();
+    assert_eq!(bounded_key(&map, "1".to_string(), || OTHER.to_string()), "1");
+    assert_eq!(bounded_key(&map, "new".to_string(), || OTHER.to_string()), OTHER);
+
+    map.remove("1");
+    assert_eq!(bounded_key(&map, "new".to_string(), || OTHER.to_string()), "new");
+}
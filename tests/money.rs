@@ -0,0 +1,99 @@
+extern crate bigdecimal;
+extern crate gateway_lib;
+extern crate stq_static_resources;
+
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use stq_static_resources::Currency;
+
+use gateway_lib::graphql::models::money::{
+    ceil_with_scale, currency_scale, decimal, decimal_to_f64, exchange, round_with_scale, Money, EXCHANGE_SCALE, FIAT_SCALE,
+};
+
+fn big(value: &str) -> BigDecimal {
+    BigDecimal::from_str(value).unwrap()
+}
+
+#[test]
+fn decimal_keeps_shortest_representation() {
+    assert_eq!(decimal(0.1).unwrap(), big("0.1"));
+    assert_eq!(decimal(19.99).unwrap(), big("19.99"));
+    assert_eq!(decimal(-2.5).unwrap(), big("-2.5"));
+    assert_eq!(decimal(0.1).unwrap() + decimal(0.2).unwrap(), big("0.3"));
+}
+
+#[test]
+fn decimal_rejects_non_finite_amounts() {
+    assert!(decimal(std::f64::NAN).is_err());
+    assert!(decimal(std::f64::INFINITY).is_err());
+}
+
+#[test]
+fn money_from_f64_keeps_currency() {
+    assert_eq!(
+        Money::from_f64(12.5, Currency::STQ).unwrap(),
+        Money::new(big("12.5"), Currency::STQ)
+    );
+}
+
+#[test]
+fn exchange_divides_by_rate_with_exchange_scale() {
+    assert_eq!(exchange(big("10"), 4.0).unwrap(), big("2.5"));
+
+    let converted = exchange(big("1"), 3.0).unwrap();
+    assert_eq!(converted.to_string(), "0.33333333");
+}
+
+#[test]
+fn exchange_rejects_zero_rate() {
+    assert!(exchange(big("10"), 0.0).is_err());
+}
+
+#[test]
+fn ceil_with_scale_rounds_up() {
+    assert_eq!(ceil_with_scale(big("1.001"), 2), big("1.01"));
+    assert_eq!(ceil_with_scale(big("1.0000001"), 2), big("1.01"));
+    assert_eq!(ceil_with_scale(big("0.123456789"), EXCHANGE_SCALE), big("0.12345679"));
+}
+
+#[test]
+fn ceil_with_scale_keeps_exact_amounts() {
+    assert_eq!(ceil_with_scale(big("1.01"), 2), big("1.01"));
+    assert_eq!(ceil_with_scale(big("5"), 2), big("5"));
+}
+
+#[test]
+fn round_with_scale_rounds_half_away_from_zero() {
+    assert_eq!(round_with_scale(big("1.005"), 2), big("1.01"));
+    assert_eq!(round_with_scale(big("1.0049"), 2), big("1.00"));
+    assert_eq!(round_with_scale(big("-1.005"), 2), big("-1.01"));
+}
+
+#[test]
+fn currency_scale_depends_on_currency_type() {
+    assert_eq!(currency_scale(Currency::EUR), FIAT_SCALE);
+    assert_eq!(currency_scale(Currency::USD), FIAT_SCALE);
+    assert_eq!(currency_scale(Currency::STQ), EXCHANGE_SCALE);
+    assert_eq!(currency_scale(Currency::BTC), EXCHANGE_SCALE);
+}
+
+#[test]
+fn fiat_amounts_are_rounded_to_cents() {
+    let exchanged = exchange(big("10"), 3.0).unwrap();
+    assert_eq!(Money::new(exchanged.clone(), Currency::EUR).rounded_amount().to_string(), "3.33");
+    assert_eq!(Money::new(exchanged, Currency::STQ).rounded_amount().to_string(), "3.33333333");
+    assert_eq!(Money::new(big("0.125"), Currency::USD).rounded_amount().to_string(), "0.13");
+}
+
+#[test]
+fn short_amounts_are_not_padded() {
+    assert_eq!(Money::new(big("100"), Currency::EUR).rounded_amount().to_string(), "100");
+    assert_eq!(Money::new(big("12.5"), Currency::STQ).rounded_amount().to_string(), "12.5");
+}
+
+#[test]
+fn decimal_to_f64_round_trips_amounts() {
+    assert_eq!(decimal_to_f64(&big("19.99")), 19.99);
+    assert_eq!(decimal_to_f64(&decimal(0.1).unwrap()), 0.1);
+}
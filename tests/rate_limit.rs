@@ -0,0 +1,151 @@
+extern crate gateway_lib;
+extern crate stq_types;
+
+use std::collections::HashMap;
+
+use stq_types::UserId;
+
+use gateway_lib::config::{OperationLimit, RateLimit};
+use gateway_lib::controller::rate_limit::{client_keys, RateLimiter};
+use gateway_lib::graphql::document::Parser;
+
+fn root_fields(query: &str, operation_name: Option<&str>) -> Vec<String> {
+    let document = Parser::new(query).document();
+    document
+        .operation(operation_name)
+        .map(|operation| document.root_fields(operation))
+        .unwrap_or_default()
+}
+
+fn limiter(capacity: u32) -> RateLimiter {
+    let mut operations = HashMap::new();
+    // config crate gives lowercased keys
+    let limit = OperationLimit {
+        capacity,
+        period_sec: 3600,
+    };
+    operations.insert("createuser".to_string(), limit);
+    RateLimiter::new(&RateLimit { operations })
+}
+
+fn fields(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn root_fields_resolve_aliases() {
+    let query = r#"mutation { first: createUser(input: {email: "a"}) { id } second: createUser(input: {email: "b"}) { id } }"#;
+
+    assert_eq!(root_fields(query, None), fields(&["createUser", "createUser"]));
+}
+
+#[test]
+fn root_fields_expand_inline_fragments() {
+    let query = r#"
+        mutation {
+            ... on Mutation { createUser(input: {email: "a"}) { id } }
+            ... @include(if: true) { getJWTByEmail(input: {}) { token } }
+        }
+    "#;
+
+    assert_eq!(root_fields(query, None), fields(&["createUser", "getJWTByEmail"]));
+}
+
+#[test]
+fn root_fields_expand_named_fragments() {
+    let query = r#"
+        mutation Login { ...Auth }
+        fragment Auth on Mutation { jwt: getJWTByEmail(input: {}) { token } ...Nested }
+        fragment Nested on Mutation { createUser(input: {}) { id } ...Auth }
+    "#;
+
+    assert_eq!(root_fields(query, Some("Login")), fields(&["getJWTByEmail", "createUser"]));
+}
+
+#[test]
+fn root_fields_of_selected_operation() {
+    let query = "query Me { me { id } } mutation Register { createUser(input: {}) { id } }";
+
+    assert_eq!(root_fields(query, Some("Register")), fields(&["createUser"]));
+    assert_eq!(root_fields(query, Some("Me")), fields(&["me"]));
+    assert!(root_fields(query, None).is_empty());
+}
+
+#[test]
+fn limiter_takes_token_per_selection() {
+    let limiter = limiter(2);
+
+    assert_eq!(limiter.check(&fields(&["createUser"]), "client"), Ok(()));
+    assert!(limiter.check(&fields(&["createUser", "createUser"]), "client").is_err());
+    assert_eq!(limiter.check(&fields(&["createUser"]), "client"), Ok(()));
+    assert!(limiter.check(&fields(&["createUser"]), "client").is_err());
+}
+
+#[test]
+fn limiter_keeps_buckets_per_client() {
+    let limiter = limiter(1);
+
+    assert_eq!(limiter.check(&fields(&["createUser"]), "client"), Ok(()));
+    match limiter.check(&fields(&["CREATEUSER"]), "client") {
+        Err(retry_after) => assert!(retry_after > 0 && retry_after <= 3600),
+        Ok(()) => panic!("bucket is expected to be empty"),
+    }
+    assert_eq!(limiter.check(&fields(&["createUser"]), "other client"), Ok(()));
+    assert_eq!(limiter.check(&fields(&["me", "search"]), "client"), Ok(()));
+}
+
+#[test]
+fn limiter_applies_to_fields_behind_fragments() {
+    let limiter = limiter(1);
+    let query = "mutation { ...F } fragment F on Mutation { createUser(input: {}) { id } }";
+
+    assert_eq!(limiter.check(&root_fields(query, None), "client"), Ok(()));
+    assert!(limiter.check(&root_fields(query, None), "client").is_err());
+}
+
+#[test]
+fn rotating_session_id_does_not_reset_anonymous_limit() {
+    let limiter = limiter(1);
+    let ip = Some("10.0.0.1".to_string());
+
+    let keys = client_keys(None, Some("first session".to_string()), ip.clone());
+    assert_eq!(limiter.check_keys(&fields(&["createUser"]), &keys), Ok(()));
+
+    let keys = client_keys(None, Some("second session".to_string()), ip.clone());
+    assert!(limiter.check_keys(&fields(&["createUser"]), &keys).is_err());
+
+    let keys = client_keys(None, None, ip);
+    assert!(limiter.check_keys(&fields(&["createUser"]), &keys).is_err());
+}
+
+#[test]
+fn users_are_limited_by_user_id_only() {
+    let limiter = limiter(1);
+    let ip = Some("10.0.0.1".to_string());
+
+    assert_eq!(
+        client_keys(Some(UserId(1)), Some("session".to_string()), ip.clone()),
+        vec!["user:1".to_string()]
+    );
+    assert_eq!(
+        limiter.check_keys(&fields(&["createUser"]), &client_keys(Some(UserId(1)), None, ip.clone())),
+        Ok(())
+    );
+    assert_eq!(
+        limiter.check_keys(&fields(&["createUser"]), &client_keys(Some(UserId(2)), None, ip)),
+        Ok(())
+    );
+}
+
+#[test]
+fn rejected_request_takes_no_tokens() {
+    let limiter = limiter(1);
+    let exhausted = vec!["ip:10.0.0.1".to_string()];
+    let fresh = vec!["session:fresh".to_string()];
+
+    assert_eq!(limiter.check_keys(&fields(&["createUser"]), &exhausted), Ok(()));
+    assert!(limiter
+        .check_keys(&fields(&["createUser"]), &[fresh[0].clone(), exhausted[0].clone()])
+        .is_err());
+    assert_eq!(limiter.check_keys(&fields(&["createUser"]), &fresh), Ok(()));
+}
@@ -0,0 +1,47 @@
+extern crate gateway_lib;
+#[macro_use]
+extern crate serde_json;
+
+use gateway_lib::upstream::recorder::{redact_body, redact_path};
+
+#[test]
+fn sensitive_json_keys_are_redacted() {
+    let body = json!({
+        "email": "user@example.com",
+        "password": "secret password",
+        "session": { "refreshToken": "abc", "ttl": 60 },
+        "providers": [{ "name": "stripe", "client_secret": "xyz" }],
+    })
+    .to_string();
+
+    let redacted: serde_json::Value = serde_json::from_str(&redact_body(&body)).unwrap();
+
+    assert_eq!(
+        redacted,
+        json!({
+            "email": "user@example.com",
+            "password": "[REDACTED]",
+            "session": { "refreshToken": "[REDACTED]", "ttl": 60 },
+            "providers": [{ "name": "stripe", "client_secret": "[REDACTED]" }],
+        })
+    );
+}
+
+#[test]
+fn bodies_which_are_not_json_are_kept() {
+    assert_eq!(redact_body(""), "");
+    assert_eq!(redact_body("plain text"), "plain text");
+}
+
+#[test]
+fn sensitive_query_parameters_are_redacted() {
+    assert_eq!(
+        redact_path("/users/users/password_reset?token=abc&lang=en"),
+        "/users/users/password_reset?token=[REDACTED]&lang=en"
+    );
+    assert_eq!(
+        redact_path("/stores/stores/1?visibility=active"),
+        "/stores/stores/1?visibility=active"
+    );
+    assert_eq!(redact_path("/stores/stores/1"), "/stores/stores/1");
+}
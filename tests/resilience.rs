@@ -0,0 +1,35 @@
+extern crate gateway_lib;
+#[macro_use]
+extern crate juniper;
+
+use juniper::FieldError;
+
+use gateway_lib::graphql::resilience::is_upstream_failure_error;
+use gateway_lib::upstream::{deadline_exceeded_error, upstream_timeout_error, upstream_unavailable_error};
+
+fn microservice_error(status: &str) -> FieldError {
+    let status = status.to_string();
+    FieldError::new(
+        "Error response from microservice",
+        graphql_value!({ "code": 100, "details": { "status": status }}),
+    )
+}
+
+#[test]
+fn upstream_failures_are_served_by_resilience_policy() {
+    assert!(is_upstream_failure_error(&upstream_unavailable_error("orders")));
+    assert!(is_upstream_failure_error(&upstream_timeout_error("orders")));
+    assert!(is_upstream_failure_error(&deadline_exceeded_error()));
+    assert!(is_upstream_failure_error(&microservice_error("503 Service Unavailable")));
+}
+
+#[test]
+fn business_errors_are_returned_as_they_are() {
+    assert!(!is_upstream_failure_error(&microservice_error("400 Bad Request")));
+    assert!(!is_upstream_failure_error(&microservice_error("404 Not Found")));
+    assert!(!is_upstream_failure_error(&FieldError::new(
+        "Could not get users cart.",
+        graphql_value!({ "code": 100, "details": { "No user id or session id in request header." }}),
+    )));
+    assert!(!is_upstream_failure_error(&FieldError::from("Unexpected error")));
+}